@@ -0,0 +1,67 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generate typed Rust client code from SCALE encoded runtime metadata.
+//!
+//! The metadata file holds a `RuntimeMetadataPrefixed`, as returned by the `state_getMetadata`
+//! RPC method. V14 metadata is converted to V15 first, and the generated code is written to
+//! the output file or to the standard output.
+
+use codec::Decode;
+use frame_metadata::{
+	codegen::generate, metadata::Metadata, v15::RuntimeMetadataV15, RuntimeMetadata,
+	RuntimeMetadataPrefixed,
+};
+use std::{env, fs, process::ExitCode};
+
+const USAGE: &str = "usage: frame-metadata-codegen <METADATA> [OUTPUT]";
+
+fn main() -> ExitCode {
+	match run() {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(error) => {
+			eprintln!("{error}");
+			ExitCode::FAILURE
+		}
+	}
+}
+
+fn run() -> Result<(), String> {
+	let mut args = env::args().skip(1);
+	let (Some(input), output, None) = (args.next(), args.next(), args.next()) else {
+		return Err(USAGE.into());
+	};
+
+	let bytes = fs::read(&input).map_err(|e| format!("cannot read `{input}`: {e}"))?;
+	let prefixed = RuntimeMetadataPrefixed::decode(&mut &bytes[..])
+		.map_err(|e| format!("cannot decode `{input}`: {e}"))?;
+	let metadata = match prefixed.1 {
+		RuntimeMetadata::V14(v14) => RuntimeMetadataV15::try_from(v14)
+			.map(Metadata::from)
+			.map_err(|e| e.to_string())?,
+		metadata => Metadata::try_from(metadata).map_err(|e| e.to_string())?,
+	};
+
+	let code = generate(&metadata).map_err(|e| e.to_string())?;
+	match output {
+		Some(output) => {
+			fs::write(&output, code).map_err(|e| format!("cannot write `{output}`: {e}"))
+		}
+		None => {
+			print!("{code}");
+			Ok(())
+		}
+	}
+}
@@ -0,0 +1,1090 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generation of typed Rust client code from the metadata.
+//!
+//! [`generate`](crate::codegen::generate) turns V15 or V16 metadata into the source of a Rust
+//! module with:
+//!
+//! - `types`: a type for every struct and enum of the type registry, nested in modules that
+//!   follow the `path` of the type. Primitives, sequences, arrays, tuples and compacts are
+//!   expressed with the matching Rust types, and `Option`, `Result` and `Cow` with their `core`
+//!   counterparts. Registry types that share a path get a numeric suffix.
+//! - `support`: the few items the rest of the code builds on, such as storage addresses and
+//!   runtime API calls.
+//! - `pallets`: a module per pallet, with constructors of the outer call for each of its calls,
+//!   a struct per event, a storage address per storage entry and an accessor per constant.
+//! - `runtime_apis`: a module per runtime API, with a function per method building its call.
+//!
+//! The generated code only needs `alloc` and `parity-scale-codec` with its `derive` feature, so
+//! it compiles for `no_std` crates that declare `extern crate alloc`. The hash functions of
+//! storage keys are left to the user, through the `support::Hashing` trait.
+
+use crate::{
+	metadata::{Metadata, Pallet, StorageEntry},
+	storage_key::storage_prefix,
+	v14::{StorageEntryType, StorageHasher},
+};
+use scale_info::{
+	form::PortableForm,
+	prelude::{
+		collections::{BTreeMap, BTreeSet},
+		format,
+		string::{String, ToString},
+		vec::Vec,
+	},
+	Field, PortableRegistry, Type, TypeDef, TypeDefPrimitive,
+};
+
+/// An error that can occur when generating code from the metadata.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CodegenError {
+	/// The metadata has no outer enums, as is the case for V14 metadata.
+	MissingOuterEnums,
+	/// A type ID referenced by the metadata was not found in the type registry.
+	TypeNotFound(u32),
+	/// The type cannot be expressed by the generated code, such as a compact that does not
+	/// wrap an integer.
+	UnsupportedType(u32),
+	/// The outer call enum has no variant for the pallet with the given name.
+	MissingOuterCall(String),
+	/// The key of a map with several hashers is not a tuple with a type per hasher.
+	StorageKeyMismatch {
+		/// The pallet name.
+		pallet: String,
+		/// The storage entry name.
+		entry: String,
+	},
+}
+
+impl core::fmt::Display for CodegenError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			CodegenError::MissingOuterEnums => {
+				write!(f, "metadata has no outer enums")
+			}
+			CodegenError::TypeNotFound(id) => {
+				write!(f, "type with ID {id} not found in the type registry")
+			}
+			CodegenError::UnsupportedType(id) => {
+				write!(f, "type with ID {id} cannot be expressed in Rust")
+			}
+			CodegenError::MissingOuterCall(pallet) => {
+				write!(f, "outer call enum has no variant for pallet `{pallet}`")
+			}
+			CodegenError::StorageKeyMismatch { pallet, entry } => {
+				write!(
+					f,
+					"key type of `{pallet}::{entry}` does not have a type per hasher"
+				)
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CodegenError {}
+
+/// Generate the source of a Rust module with typed client code for the metadata.
+///
+/// V14 metadata has no outer enums and is rejected, but it can be converted to V15 first.
+pub fn generate(metadata: &Metadata) -> Result<String, CodegenError> {
+	let outer_enums = metadata
+		.outer_enums()
+		.ok_or(CodegenError::MissingOuterEnums)?;
+	let generator = Generator::new(metadata, outer_enums.call_enum_ty.id);
+
+	let mut w = Writer::default();
+	w.line("// Generated from the runtime metadata by `frame-metadata`. Do not edit.");
+	w.line("");
+	generator.types(&mut w)?;
+	w.line("");
+	w.out.push_str(SUPPORT);
+	w.line("");
+	generator.pallets(&mut w)?;
+	w.line("");
+	generator.runtime_apis(&mut w)?;
+	Ok(w.out)
+}
+
+/// The lints that the generated modules allow, as they keep the names of the metadata.
+const ALLOW: &str = "#[allow(non_camel_case_types, non_snake_case, missing_docs, clippy::all)]";
+
+/// The `support` module of the generated code.
+const SUPPORT: &str = r##"#[allow(non_camel_case_types, non_snake_case, missing_docs, clippy::all)]
+pub mod support {
+	/// A hasher of the keys of storage maps.
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	pub enum StorageHasher {
+		Blake2_128,
+		Blake2_256,
+		Blake2_128Concat,
+		Twox128,
+		Twox256,
+		Twox64Concat,
+		Identity,
+	}
+
+	/// The hash functions of storage keys.
+	pub trait Hashing {
+		fn blake2_128(data: &[u8]) -> [u8; 16];
+		fn blake2_256(data: &[u8]) -> [u8; 32];
+		fn twox_64(data: &[u8]) -> [u8; 8];
+		fn twox_128(data: &[u8]) -> [u8; 16];
+		fn twox_256(data: &[u8]) -> [u8; 32];
+	}
+
+	impl StorageHasher {
+		/// Append the hash of `data` to `out`, followed by `data` for concatenating hashers.
+		pub fn hash_to<H: Hashing>(&self, data: &[u8], out: &mut ::alloc::vec::Vec<u8>) {
+			match self {
+				StorageHasher::Blake2_128 => out.extend_from_slice(&H::blake2_128(data)),
+				StorageHasher::Blake2_256 => out.extend_from_slice(&H::blake2_256(data)),
+				StorageHasher::Blake2_128Concat => {
+					out.extend_from_slice(&H::blake2_128(data));
+					out.extend_from_slice(data);
+				}
+				StorageHasher::Twox128 => out.extend_from_slice(&H::twox_128(data)),
+				StorageHasher::Twox256 => out.extend_from_slice(&H::twox_256(data)),
+				StorageHasher::Twox64Concat => {
+					out.extend_from_slice(&H::twox_64(data));
+					out.extend_from_slice(data);
+				}
+				StorageHasher::Identity => out.extend_from_slice(data),
+			}
+		}
+	}
+
+	/// The address of a storage value of type `V`.
+	#[derive(Clone, Debug, PartialEq, Eq)]
+	pub struct StorageAddress<V> {
+		pub pallet: &'static str,
+		pub entry: &'static str,
+		/// The 128-bit XX hashes of the storage prefix of the pallet and of the entry name.
+		pub prefix: [u8; 32],
+		/// The SCALE encoded keys of a map, with their hashers.
+		pub keys: ::alloc::vec::Vec<(StorageHasher, ::alloc::vec::Vec<u8>)>,
+		value: ::core::marker::PhantomData<fn() -> V>,
+	}
+
+	impl<V> StorageAddress<V> {
+		pub fn new(
+			pallet: &'static str,
+			entry: &'static str,
+			prefix: [u8; 32],
+			keys: ::alloc::vec::Vec<(StorageHasher, ::alloc::vec::Vec<u8>)>,
+		) -> Self {
+			StorageAddress { pallet, entry, prefix, keys, value: ::core::marker::PhantomData }
+		}
+
+		/// The storage key, with the keys hashed by the hash functions of `H`.
+		pub fn to_key<H: Hashing>(&self) -> ::alloc::vec::Vec<u8> {
+			let mut key = self.prefix.to_vec();
+			for (hasher, data) in &self.keys {
+				hasher.hash_to::<H>(data, &mut key);
+			}
+			key
+		}
+	}
+
+	impl<V: ::parity_scale_codec::Decode> StorageAddress<V> {
+		/// Decode the value stored at the address.
+		pub fn decode_value(&self, mut bytes: &[u8]) -> ::core::result::Result<V, ::parity_scale_codec::Error> {
+			::parity_scale_codec::DecodeAll::decode_all(&mut bytes)
+		}
+	}
+
+	/// An event of a pallet.
+	pub trait StaticEvent: ::parity_scale_codec::Decode {
+		const PALLET: &'static str;
+		const PALLET_INDEX: u8;
+		const EVENT: &'static str;
+		const EVENT_INDEX: u8;
+
+		/// Decode the event from an encoded outer event, if it is this event.
+		fn decode_from(event: &[u8]) -> ::core::option::Option<::core::result::Result<Self, ::parity_scale_codec::Error>> {
+			match event {
+				[pallet, index, fields @ ..] if *pallet == Self::PALLET_INDEX && *index == Self::EVENT_INDEX => {
+					Some(::parity_scale_codec::DecodeAll::decode_all(&mut &fields[..]))
+				}
+				_ => None,
+			}
+		}
+	}
+
+	/// A call of a runtime API method with output type `R`.
+	#[derive(Clone, Debug, PartialEq, Eq)]
+	pub struct RuntimeApiCall<R> {
+		/// The `Trait_method` name of the method.
+		pub method: &'static str,
+		/// The SCALE encoded arguments.
+		pub args: ::alloc::vec::Vec<u8>,
+		output: ::core::marker::PhantomData<fn() -> R>,
+	}
+
+	impl<R> RuntimeApiCall<R> {
+		pub fn new(method: &'static str, args: ::alloc::vec::Vec<u8>) -> Self {
+			RuntimeApiCall { method, args, output: ::core::marker::PhantomData }
+		}
+	}
+
+	impl<R: ::parity_scale_codec::Decode> RuntimeApiCall<R> {
+		/// Decode the output of the call.
+		pub fn decode_output(&self, mut bytes: &[u8]) -> ::core::result::Result<R, ::parity_scale_codec::Error> {
+			::parity_scale_codec::DecodeAll::decode_all(&mut bytes)
+		}
+	}
+
+	/// A sequence of bits, kept in the words of `STORE` bytes that encode it.
+	#[derive(Clone, Debug, PartialEq, Eq)]
+	pub struct BitSequence<const STORE: usize> {
+		/// The number of bits.
+		pub len: u32,
+		/// The bytes of the words holding the bits.
+		pub bytes: ::alloc::vec::Vec<u8>,
+	}
+
+	impl<const STORE: usize> ::parity_scale_codec::Encode for BitSequence<STORE> {
+		fn size_hint(&self) -> usize {
+			5 + self.bytes.len()
+		}
+
+		fn encode_to<T: ::parity_scale_codec::Output + ?Sized>(&self, dest: &mut T) {
+			::parity_scale_codec::Encode::encode_to(&::parity_scale_codec::Compact(self.len), dest);
+			dest.write(&self.bytes);
+		}
+	}
+
+	impl<const STORE: usize> ::parity_scale_codec::Decode for BitSequence<STORE> {
+		fn decode<I: ::parity_scale_codec::Input>(input: &mut I) -> ::core::result::Result<Self, ::parity_scale_codec::Error> {
+			let len = <::parity_scale_codec::Compact<u32> as ::parity_scale_codec::Decode>::decode(input)?.0;
+			let size = (len as usize).div_ceil(STORE * 8) * STORE;
+			if let Some(remaining) = input.remaining_len()? {
+				if remaining < size {
+					return Err("Not enough data for the bit sequence".into());
+				}
+			}
+			let mut bytes = ::alloc::vec![0; size];
+			input.read(&mut bytes)?;
+			Ok(BitSequence { len, bytes })
+		}
+	}
+}
+"##;
+
+/// The `super::` prefixes that lead from a module at the given depth to the generated root.
+const ROOTS: [&str; 4] = ["", "super::", "super::super::", "super::super::super::"];
+
+/// Rust source, written line by line with tab indentation.
+#[derive(Default)]
+struct Writer {
+	out: String,
+	indent: usize,
+}
+
+impl Writer {
+	fn line(&mut self, line: &str) {
+		if !line.is_empty() {
+			self.out.extend((0..self.indent).map(|_| '\t'));
+			self.out.push_str(line);
+		}
+		self.out.push('\n');
+	}
+
+	fn open(&mut self, line: &str) {
+		self.line(line);
+		self.indent += 1;
+	}
+
+	fn close(&mut self, line: &str) {
+		self.indent -= 1;
+		self.line(line);
+	}
+}
+
+/// A module of the `types` module, with the IDs of the types defined in it.
+#[derive(Default)]
+struct Module {
+	types: Vec<(String, u32)>,
+	children: BTreeMap<String, Module>,
+}
+
+/// The standard types that are expressed with their `core` counterparts.
+enum Special {
+	Option(u32),
+	Result(u32, u32),
+	Cow(u32),
+}
+
+/// The hashers and type IDs of the keys of a storage entry.
+type StorageKeys<'a> = Vec<(&'a StorageHasher, u32)>;
+
+struct Generator<'a> {
+	metadata: &'a Metadata,
+	types: &'a PortableRegistry,
+	call_enum_ty: u32,
+	/// The module path and name of every type with a definition.
+	names: BTreeMap<u32, (Vec<String>, String)>,
+	/// The fields that are boxed to break cycles of types containing each other, by the ID of
+	/// the struct or enum, the variant index and the field position.
+	boxed: BTreeSet<(u32, Option<u8>, usize)>,
+}
+
+impl<'a> Generator<'a> {
+	fn new(metadata: &'a Metadata, call_enum_ty: u32) -> Self {
+		let types = metadata.types();
+		let mut generator = Generator {
+			metadata,
+			types,
+			call_enum_ty,
+			names: BTreeMap::new(),
+			boxed: BTreeSet::new(),
+		};
+
+		let mut by_path: BTreeMap<Vec<String>, Vec<u32>> = BTreeMap::new();
+		for ty in &types.types {
+			if !matches!(ty.ty.type_def, TypeDef::Composite(_) | TypeDef::Variant(_))
+				|| generator.special(&ty.ty).is_some()
+			{
+				continue;
+			}
+			let mut path: Vec<String> = ty.ty.path.segments.iter().map(|s| s.to_string()).collect();
+			if path.is_empty() {
+				path.push(format!("Type{}", ty.id));
+			}
+			by_path.entry(path).or_default().push(ty.id);
+		}
+		for (path, ids) in by_path {
+			let (name, module) = path.split_last().expect("paths are not empty; qed");
+			for (n, id) in ids.iter().enumerate() {
+				let name = match ids.len() {
+					1 => name.clone(),
+					_ => format!("{name}{}", n + 1),
+				};
+				generator.names.insert(*id, (module.to_vec(), name));
+			}
+		}
+
+		for &id in generator.names.keys() {
+			for (variant, position, field) in generator.fields_of(id) {
+				if generator.reaches(field, id, &mut BTreeSet::new()) {
+					generator.boxed.insert((id, variant, position));
+				}
+			}
+		}
+
+		generator
+	}
+
+	fn resolve(&self, id: u32) -> Result<&'a Type<PortableForm>, CodegenError> {
+		self.types.resolve(id).ok_or(CodegenError::TypeNotFound(id))
+	}
+
+	/// The standard type that `ty` is, if any.
+	fn special(&self, ty: &Type<PortableForm>) -> Option<Special> {
+		let field = |variant: &str| match &ty.type_def {
+			TypeDef::Variant(def) => def
+				.variants
+				.iter()
+				.find(|v| AsRef::<str>::as_ref(&v.name) == variant)
+				.and_then(|v| v.fields.first())
+				.map(|f| f.ty.id),
+			_ => None,
+		};
+		let segments: Vec<&str> = ty.path.segments.iter().map(AsRef::as_ref).collect();
+		match segments.as_slice() {
+			["Option"] => field("Some").map(Special::Option),
+			["Result"] => Some(Special::Result(field("Ok")?, field("Err")?)),
+			["Cow"] => match &ty.type_def {
+				TypeDef::Composite(def) if def.fields.len() == 1 => {
+					Some(Special::Cow(def.fields[0].ty.id))
+				}
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+
+	/// The variant index, position and type ID of every field of a struct or enum.
+	fn fields_of(&self, id: u32) -> Vec<(Option<u8>, usize, u32)> {
+		match self.types.resolve(id).map(|ty| &ty.type_def) {
+			Some(TypeDef::Composite(def)) => def
+				.fields
+				.iter()
+				.enumerate()
+				.map(|(position, field)| (None, position, field.ty.id))
+				.collect(),
+			Some(TypeDef::Variant(def)) => def
+				.variants
+				.iter()
+				.flat_map(|variant| {
+					variant
+						.fields
+						.iter()
+						.enumerate()
+						.map(|(position, field)| (Some(variant.index), position, field.ty.id))
+				})
+				.collect(),
+			_ => Vec::new(),
+		}
+	}
+
+	/// Whether a value of type `from` contains a value of type `target` without indirection.
+	fn reaches(&self, from: u32, target: u32, visited: &mut BTreeSet<u32>) -> bool {
+		if from == target {
+			return true;
+		}
+		if !visited.insert(from) {
+			return false;
+		}
+		let inner: Vec<u32> = match self.types.resolve(from).map(|ty| &ty.type_def) {
+			Some(TypeDef::Composite(_) | TypeDef::Variant(_)) => self
+				.fields_of(from)
+				.into_iter()
+				.map(|(_, _, field)| field)
+				.collect(),
+			Some(TypeDef::Array(def)) => [def.type_param.id].into(),
+			Some(TypeDef::Tuple(def)) => def.fields.iter().map(|f| f.id).collect(),
+			_ => Vec::new(),
+		};
+		inner
+			.into_iter()
+			.any(|ty| self.reaches(ty, target, visited))
+	}
+
+	/// The Rust type of the type `id`, for code in the module at the given root.
+	fn ty(&self, id: u32, root: &str) -> Result<String, CodegenError> {
+		if let Some((module, name)) = self.names.get(&id) {
+			let mut path = format!("{root}types::");
+			for segment in module {
+				path.push_str(&ident(segment));
+				path.push_str("::");
+			}
+			path.push_str(name);
+			return Ok(path);
+		}
+
+		let ty = self.resolve(id)?;
+		if let Some(special) = self.special(ty) {
+			return Ok(match special {
+				Special::Option(some) => {
+					format!("::core::option::Option<{}>", self.ty(some, root)?)
+				}
+				Special::Result(ok, err) => format!(
+					"::core::result::Result<{}, {}>",
+					self.ty(ok, root)?,
+					self.ty(err, root)?
+				),
+				Special::Cow(inner) => self.ty(inner, root)?,
+			});
+		}
+
+		let ty = match &ty.type_def {
+			TypeDef::Primitive(primitive) => primitive_ty(primitive).into(),
+			TypeDef::Sequence(def) => {
+				format!("::alloc::vec::Vec<{}>", self.ty(def.type_param.id, root)?)
+			}
+			TypeDef::Array(def) => format!("[{}; {}]", self.ty(def.type_param.id, root)?, def.len),
+			TypeDef::Tuple(def) => {
+				let fields = def
+					.fields
+					.iter()
+					.map(|field| self.ty(field.id, root))
+					.collect::<Result<Vec<_>, _>>()?;
+				match fields.as_slice() {
+					[field] => format!("({field},)"),
+					fields => format!("({})", fields.join(", ")),
+				}
+			}
+			TypeDef::Compact(def) => format!(
+				"::parity_scale_codec::Compact<{}>",
+				self.compact_ty(def.type_param.id)?
+			),
+			TypeDef::BitSequence(def) => {
+				let store = match &self.resolve(def.bit_store_type.id)?.type_def {
+					TypeDef::Primitive(TypeDefPrimitive::U8) => 1,
+					TypeDef::Primitive(TypeDefPrimitive::U16) => 2,
+					TypeDef::Primitive(TypeDefPrimitive::U32) => 4,
+					TypeDef::Primitive(TypeDefPrimitive::U64) => 8,
+					_ => return Err(CodegenError::UnsupportedType(id)),
+				};
+				format!("{root}support::BitSequence<{store}>")
+			}
+			// Structs and enums always have a name.
+			TypeDef::Composite(_) | TypeDef::Variant(_) => {
+				return Err(CodegenError::UnsupportedType(id))
+			}
+		};
+		Ok(ty)
+	}
+
+	/// The integer or unit type of a compact, looking through structs with a single field.
+	fn compact_ty(&self, id: u32) -> Result<&'static str, CodegenError> {
+		match &self.resolve(id)?.type_def {
+			TypeDef::Primitive(
+				primitive @ (TypeDefPrimitive::U8
+				| TypeDefPrimitive::U16
+				| TypeDefPrimitive::U32
+				| TypeDefPrimitive::U64
+				| TypeDefPrimitive::U128),
+			) => Ok(primitive_ty(primitive)),
+			TypeDef::Tuple(def) if def.fields.is_empty() => Ok("()"),
+			TypeDef::Composite(def) if def.fields.len() == 1 => {
+				self.compact_ty(def.fields[0].ty.id)
+			}
+			_ => Err(CodegenError::UnsupportedType(id)),
+		}
+	}
+
+	/// The names and Rust types of `fields`, boxing those of `container` that need it.
+	fn fields(
+		&self,
+		fields: &[Field<PortableForm>],
+		root: &str,
+		container: Option<(u32, Option<u8>)>,
+	) -> Result<Vec<(Option<String>, String)>, CodegenError> {
+		fields
+			.iter()
+			.enumerate()
+			.map(|(position, field)| {
+				let mut ty = self.ty(field.ty.id, root)?;
+				if container
+					.is_some_and(|(id, variant)| self.boxed.contains(&(id, variant, position)))
+				{
+					ty = format!("::alloc::boxed::Box<{ty}>");
+				}
+				Ok((field.name.as_ref().map(|name| ident(name)), ty))
+			})
+			.collect()
+	}
+
+	/// Write a struct with the given fields.
+	fn write_struct(&self, w: &mut Writer, name: &str, fields: &[(Option<String>, String)]) {
+		w.line(DERIVE);
+		match fields {
+			[] => w.line(&format!("pub struct {name};")),
+			[(Some(_), _), ..] => {
+				w.open(&format!("pub struct {name} {{"));
+				for (field, ty) in fields {
+					w.line(&format!(
+						"pub {}: {ty},",
+						field.as_deref().unwrap_or_default()
+					));
+				}
+				w.close("}");
+			}
+			[(None, _), ..] => {
+				let fields: Vec<String> =
+					fields.iter().map(|(_, ty)| format!("pub {ty}")).collect();
+				w.line(&format!("pub struct {name}({});", fields.join(", ")));
+			}
+		}
+	}
+
+	fn types(&self, w: &mut Writer) -> Result<(), CodegenError> {
+		let mut root = Module::default();
+		for (id, (path, name)) in &self.names {
+			let module = path.iter().fold(&mut root, |module, segment| {
+				module.children.entry(segment.clone()).or_default()
+			});
+			module.types.push((name.clone(), *id));
+		}
+		self.type_module(w, "types", &mut root, 1)
+	}
+
+	fn type_module(
+		&self,
+		w: &mut Writer,
+		name: &str,
+		module: &mut Module,
+		depth: usize,
+	) -> Result<(), CodegenError> {
+		let root = "super::".repeat(depth);
+		if depth == 1 {
+			w.line(ALLOW);
+		}
+		w.open(&format!("pub mod {} {{", ident(name)));
+		for (name, child) in &mut module.children {
+			self.type_module(w, name, child, depth + 1)?;
+		}
+		module.types.sort();
+		for (name, id) in &module.types {
+			match &self.resolve(*id)?.type_def {
+				TypeDef::Composite(def) => {
+					let fields = self.fields(&def.fields, &root, Some((*id, None)))?;
+					self.write_struct(w, name, &fields);
+				}
+				TypeDef::Variant(def) => {
+					w.line(DERIVE);
+					w.open(&format!("pub enum {name} {{"));
+					for variant in &def.variants {
+						let fields =
+							self.fields(&variant.fields, &root, Some((*id, Some(variant.index))))?;
+						w.line(&format!("#[codec(index = {})]", variant.index));
+						let variant = ident(AsRef::<str>::as_ref(&variant.name));
+						match fields.as_slice() {
+							[] => w.line(&format!("{variant},")),
+							[(Some(_), _), ..] => {
+								w.open(&format!("{variant} {{"));
+								for (field, ty) in &fields {
+									w.line(&format!(
+										"{}: {ty},",
+										field.as_deref().unwrap_or_default()
+									));
+								}
+								w.close("},");
+							}
+							[(None, _), ..] => {
+								let fields: Vec<&str> =
+									fields.iter().map(|(_, ty)| ty.as_str()).collect();
+								w.line(&format!("{variant}({}),", fields.join(", ")));
+							}
+						}
+					}
+					w.close("}");
+				}
+				_ => return Err(CodegenError::UnsupportedType(*id)),
+			}
+		}
+		w.close("}");
+		Ok(())
+	}
+
+	fn pallets(&self, w: &mut Writer) -> Result<(), CodegenError> {
+		w.line(ALLOW);
+		w.open("pub mod pallets {");
+		for pallet in self.metadata.pallets() {
+			w.open(&format!("pub mod {} {{", ident(&snake_case(pallet.name()))));
+			w.line(&format!("pub const NAME: &str = {:?};", pallet.name()));
+			w.line(&format!("pub const INDEX: u8 = {};", pallet.index()));
+			if let Some(call_ty) = pallet.call_ty() {
+				self.calls(w, &pallet, call_ty)?;
+			}
+			if !pallet.events().is_empty() {
+				self.events(w, &pallet)?;
+			}
+			if pallet.storage_entries().next().is_some() {
+				self.storage(w, &pallet)?;
+			}
+			if pallet.constants().next().is_some() {
+				self.constants(w, &pallet)?;
+			}
+			w.close("}");
+		}
+		w.close("}");
+		Ok(())
+	}
+
+	fn calls(&self, w: &mut Writer, pallet: &Pallet<'a>, call_ty: u32) -> Result<(), CodegenError> {
+		let root = ROOTS[3];
+		let outer_variant = match &self.resolve(self.call_enum_ty)?.type_def {
+			TypeDef::Variant(def) => def.variants.iter().find(|v| v.index == pallet.index()),
+			_ => None,
+		}
+		.filter(|variant| variant.fields.len() == 1)
+		.ok_or_else(|| CodegenError::MissingOuterCall(pallet.name().into()))?;
+		let outer_boxed = self
+			.boxed
+			.contains(&(self.call_enum_ty, Some(pallet.index()), 0));
+		let outer_ty = self.ty(self.call_enum_ty, root)?;
+		let call_path = self.ty(call_ty, root)?;
+
+		w.open("pub mod calls {");
+		for call in pallet.calls() {
+			let fields = self.fields(&call.fields, root, None)?;
+			let mut params = Vec::new();
+			let mut args = Vec::new();
+			for (position, (name, ty)) in fields.iter().enumerate() {
+				let param = name.clone().unwrap_or_else(|| format!("arg{position}"));
+				params.push(format!("{param}: {ty}"));
+				let arg = boxed(
+					&param,
+					self.boxed.contains(&(call_ty, Some(call.index), position)),
+				);
+				args.push(match name {
+					Some(name) if arg != *name => format!("{name}: {arg}"),
+					_ => arg,
+				});
+			}
+			let call_value = match fields.as_slice() {
+				[] => format!("{call_path}::{}", ident(AsRef::<str>::as_ref(&call.name))),
+				[(Some(_), _), ..] => {
+					format!(
+						"{call_path}::{} {{ {} }}",
+						ident(AsRef::<str>::as_ref(&call.name)),
+						args.join(", ")
+					)
+				}
+				[(None, _), ..] => {
+					format!(
+						"{call_path}::{}({})",
+						ident(AsRef::<str>::as_ref(&call.name)),
+						args.join(", ")
+					)
+				}
+			};
+
+			w.open(&format!(
+				"pub fn {}({}) -> {outer_ty} {{",
+				ident(AsRef::<str>::as_ref(&call.name)),
+				params.join(", ")
+			));
+			w.line(&format!(
+				"{outer_ty}::{}({})",
+				ident(AsRef::<str>::as_ref(&outer_variant.name)),
+				boxed(&call_value, outer_boxed)
+			));
+			w.close("}");
+		}
+		w.close("}");
+		Ok(())
+	}
+
+	fn events(&self, w: &mut Writer, pallet: &Pallet<'a>) -> Result<(), CodegenError> {
+		let root = ROOTS[3];
+		w.open("pub mod events {");
+		for event in pallet.events() {
+			let name = ident(AsRef::<str>::as_ref(&event.name));
+			let fields = self.fields(&event.fields, root, None)?;
+			self.write_struct(w, &name, &fields);
+			w.open(&format!("impl {root}support::StaticEvent for {name} {{"));
+			w.line(&format!(
+				"const PALLET: &'static str = {:?};",
+				pallet.name()
+			));
+			w.line(&format!("const PALLET_INDEX: u8 = {};", pallet.index()));
+			w.line(&format!("const EVENT: &'static str = {:?};", event.name));
+			w.line(&format!("const EVENT_INDEX: u8 = {};", event.index));
+			w.close("}");
+		}
+		w.close("}");
+		Ok(())
+	}
+
+	fn storage(&self, w: &mut Writer, pallet: &Pallet<'a>) -> Result<(), CodegenError> {
+		let root = ROOTS[3];
+		let pallet_prefix = pallet.storage_prefix().unwrap_or_default();
+		w.open("pub mod storage {");
+		for entry in pallet.storage_entries() {
+			let (keys, value) = self.storage_keys(pallet, &entry)?;
+			let mut params = Vec::new();
+			let mut hashed = Vec::new();
+			for (position, (hasher, ty)) in keys.iter().enumerate() {
+				let param = match keys.len() {
+					1 => "key".to_string(),
+					_ => format!("key{position}"),
+				};
+				params.push(format!("{param}: &{}", self.ty(*ty, root)?));
+				hashed.push(format!(
+					"({root}support::StorageHasher::{hasher:?}, ::parity_scale_codec::Encode::encode({param}))"
+				));
+			}
+			let prefix = storage_prefix(pallet_prefix, entry.name);
+
+			w.open(&format!(
+				"pub fn {}({}) -> {root}support::StorageAddress<{}> {{",
+				ident(&snake_case(entry.name)),
+				params.join(", "),
+				self.ty(value, root)?
+			));
+			w.open(&format!("{root}support::StorageAddress::new("));
+			w.line(&format!("{:?},", pallet.name()));
+			w.line(&format!("{:?},", entry.name));
+			w.line(&format!("{prefix:?},"));
+			match hashed.as_slice() {
+				[] => w.line("::alloc::vec::Vec::new(),"),
+				hashed => w.line(&format!("::alloc::vec![{}],", hashed.join(", "))),
+			}
+			w.close(")");
+			w.close("}");
+		}
+		w.close("}");
+		Ok(())
+	}
+
+	/// The hashers and types of the keys of a storage entry, and the type of its value.
+	fn storage_keys(
+		&self,
+		pallet: &Pallet<'a>,
+		entry: &StorageEntry<'a>,
+	) -> Result<(StorageKeys<'a>, u32), CodegenError> {
+		match entry.ty {
+			StorageEntryType::Plain(value) => Ok((Vec::new(), value.id)),
+			StorageEntryType::Map {
+				hashers,
+				key,
+				value,
+			} => {
+				let keys = match hashers.as_slice() {
+					[hasher] => [(hasher, key.id)].into(),
+					hashers => match &self.resolve(key.id)?.type_def {
+						TypeDef::Tuple(def) if def.fields.len() == hashers.len() => hashers
+							.iter()
+							.zip(def.fields.iter().map(|f| f.id))
+							.collect(),
+						_ => {
+							return Err(CodegenError::StorageKeyMismatch {
+								pallet: pallet.name().into(),
+								entry: entry.name.into(),
+							})
+						}
+					},
+				};
+				Ok((keys, value.id))
+			}
+		}
+	}
+
+	fn constants(&self, w: &mut Writer, pallet: &Pallet<'a>) -> Result<(), CodegenError> {
+		let root = ROOTS[3];
+		w.open("pub mod constants {");
+		for constant in pallet.constants() {
+			w.open(&format!(
+				"pub fn {}() -> ::core::result::Result<{}, ::parity_scale_codec::Error> {{",
+				ident(&snake_case(constant.name)),
+				self.ty(constant.ty, root)?
+			));
+			w.line(&format!("let mut value: &[u8] = &{:?};", constant.value));
+			w.line("::parity_scale_codec::DecodeAll::decode_all(&mut value)");
+			w.close("}");
+		}
+		w.close("}");
+		Ok(())
+	}
+
+	fn runtime_apis(&self, w: &mut Writer) -> Result<(), CodegenError> {
+		let root = ROOTS[2];
+		w.line(ALLOW);
+		w.open("pub mod runtime_apis {");
+		for api in self.metadata.runtime_apis() {
+			w.open(&format!("pub mod {} {{", ident(&snake_case(api.name()))));
+			for method in api.methods() {
+				let mut params = Vec::new();
+				let mut args = Vec::new();
+				for (name, ty) in &method.inputs {
+					params.push(format!("{}: {}", ident(name), self.ty(*ty, root)?));
+					args.push(ident(name));
+				}
+				let encoded = match args.as_slice() {
+					[] => "::alloc::vec::Vec::new()".to_string(),
+					[arg] => format!("::parity_scale_codec::Encode::encode(&{arg})"),
+					args => format!(
+						"::parity_scale_codec::Encode::encode(&({}))",
+						args.join(", ")
+					),
+				};
+
+				w.open(&format!(
+					"pub fn {}({}) -> {root}support::RuntimeApiCall<{}> {{",
+					ident(method.name),
+					params.join(", "),
+					self.ty(method.output, root)?
+				));
+				w.line(&format!(
+					"{root}support::RuntimeApiCall::new(\"{}_{}\", {encoded})",
+					api.name(),
+					method.name
+				));
+				w.close("}");
+			}
+			w.close("}");
+		}
+		w.close("}");
+		Ok(())
+	}
+}
+
+/// The derives of the generated structs and enums.
+const DERIVE: &str = "#[derive(Clone, Debug, PartialEq, Eq, ::parity_scale_codec::Encode, ::parity_scale_codec::Decode)]";
+
+/// The Rust type of a primitive.
+fn primitive_ty(primitive: &TypeDefPrimitive) -> &'static str {
+	match primitive {
+		TypeDefPrimitive::Bool => "bool",
+		// A `char` is encoded as its `u32` code point, which SCALE codec has no impl for.
+		TypeDefPrimitive::Char => "u32",
+		TypeDefPrimitive::Str => "::alloc::string::String",
+		TypeDefPrimitive::U8 => "u8",
+		TypeDefPrimitive::U16 => "u16",
+		TypeDefPrimitive::U32 => "u32",
+		TypeDefPrimitive::U64 => "u64",
+		TypeDefPrimitive::U128 => "u128",
+		TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => "[u8; 32]",
+		TypeDefPrimitive::I8 => "i8",
+		TypeDefPrimitive::I16 => "i16",
+		TypeDefPrimitive::I32 => "i32",
+		TypeDefPrimitive::I64 => "i64",
+		TypeDefPrimitive::I128 => "i128",
+	}
+}
+
+/// Wrap `expr` in a `Box` if `boxed` is set.
+fn boxed(expr: &str, boxed: bool) -> String {
+	match boxed {
+		true => format!("::alloc::boxed::Box::new({expr})"),
+		false => expr.into(),
+	}
+}
+
+/// The Rust keywords, which are turned into raw identifiers.
+const KEYWORDS: &[&str] = &[
+	"abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+	"else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+	"loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+	"static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+	"virtual", "where", "while", "yield",
+];
+
+/// A valid Rust identifier for `name`.
+fn ident(name: &str) -> String {
+	match name {
+		// These keywords cannot be raw identifiers.
+		"self" | "Self" | "super" | "crate" => format!("{name}_"),
+		name if KEYWORDS.contains(&name) => format!("r#{name}"),
+		name => name.into(),
+	}
+}
+
+/// Convert a `CamelCase` name to `snake_case`, keeping acronyms together.
+fn snake_case(name: &str) -> String {
+	let chars: Vec<char> = name.chars().collect();
+	let mut out = String::new();
+	for (i, &c) in chars.iter().enumerate() {
+		if !c.is_uppercase() {
+			out.push(c);
+			continue;
+		}
+		let prev = i.checked_sub(1).map(|i| chars[i]);
+		let next = chars.get(i + 1);
+		let boundary = prev.is_some_and(|prev| {
+			prev.is_lowercase()
+				|| prev.is_ascii_digit()
+				|| (prev.is_uppercase() && next.is_some_and(|next| next.is_lowercase()))
+		});
+		if boundary && !out.ends_with('_') {
+			out.push('_');
+		}
+		out.extend(c.to_lowercase());
+	}
+	out
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test::{kusama_v14, kusama_with_account_nonce_api};
+
+	/// Kusama whose `account_nonce` runtime API method takes an `AccountId32` and a `u32`
+	/// named `type`.
+	fn metadata() -> Metadata {
+		kusama_with_account_nonce_api(&[("account", 0), ("type", 4)])
+	}
+
+	#[test]
+	fn should_generate_calls_storage_and_constants() {
+		let code = generate(&metadata()).unwrap();
+
+		assert!(code.contains(
+			"pub fn transfer_keep_alive(\
+			dest: super::super::super::types::sp_runtime::multiaddress::MultiAddress, \
+			value: ::parity_scale_codec::Compact<u128>) \
+			-> super::super::super::types::kusama_runtime::Call {"
+		));
+		assert!(code.contains(
+			"super::super::super::types::kusama_runtime::Call::Balances(\
+			super::super::super::types::pallet_balances::pallet::Call::transfer_keep_alive \
+			{ dest, value })"
+		));
+
+		let prefix = format!("{:?},", storage_prefix("System", "Account"));
+		let account = code
+			.find("pub fn account(key: &super::super::super::types::sp_core::crypto::AccountId32)")
+			.unwrap();
+		assert!(code[account..].starts_with(
+			"pub fn account(key: &super::super::super::types::sp_core::crypto::AccountId32) \
+			-> super::super::super::support::StorageAddress<\
+			super::super::super::types::frame_system::AccountInfo> {"
+		));
+		assert!(code[account..].contains(&prefix));
+		assert!(code[account..].contains(
+			"(super::super::super::support::StorageHasher::Blake2_128Concat, \
+			::parity_scale_codec::Encode::encode(key))"
+		));
+
+		assert!(code.contains(
+			"pub fn existential_deposit() -> ::core::result::Result<u128, ::parity_scale_codec::Error> {"
+		));
+		assert!(code.contains("pub const INDEX: u8 = 4;"));
+	}
+
+	#[test]
+	fn should_box_fields_of_recursive_types() {
+		let code = generate(&metadata()).unwrap();
+
+		// The outer call contains the calls of `Utility`, which contain the outer call.
+		assert!(code.contains(
+			"Utility(::alloc::boxed::Box<super::super::types::pallet_utility::pallet::Call>),"
+		));
+		assert!(code.contains(
+			"super::super::super::types::kusama_runtime::Call::Utility(::alloc::boxed::Box::new(\
+			super::super::super::types::pallet_utility::pallet::Call::as_derivative \
+			{ index, call: ::alloc::boxed::Box::new(call) }))"
+		));
+		// A sequence of outer calls needs no box.
+		assert!(code.contains(
+			"calls: ::alloc::vec::Vec<super::super::super::types::kusama_runtime::Call>,"
+		));
+	}
+
+	#[test]
+	fn should_generate_runtime_api_stubs() {
+		let code = generate(&metadata()).unwrap();
+
+		assert!(code.contains(
+			"pub fn account_nonce(\
+			account: super::super::types::sp_core::crypto::AccountId32, r#type: u32) \
+			-> super::super::support::RuntimeApiCall<u32> {"
+		));
+		assert!(code.contains(
+			"super::super::support::RuntimeApiCall::new(\"AccountNonceApi_account_nonce\", \
+			::parity_scale_codec::Encode::encode(&(account, r#type)))"
+		));
+	}
+
+	#[test]
+	fn should_reject_metadata_without_outer_enums() {
+		let metadata = Metadata::from(kusama_v14());
+		assert_eq!(generate(&metadata), Err(CodegenError::MissingOuterEnums));
+	}
+
+	#[test]
+	fn should_convert_names_to_snake_case() {
+		let cases = [
+			("Balances", "balances"),
+			(
+				"ElectionProviderMultiPhase",
+				"election_provider_multi_phase",
+			),
+			("XcmPallet", "xcm_pallet"),
+			("XCMPallet", "xcm_pallet"),
+			("AccountNonceApi", "account_nonce_api"),
+			("Ump2Queue", "ump2_queue"),
+			("already_snake", "already_snake"),
+		];
+		for (name, expected) in cases {
+			assert_eq!(snake_case(name), expected);
+		}
+	}
+}
@@ -0,0 +1,616 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Classification of the differences between two versions of the metadata by whether they
+//! break clients built against the old version.
+//!
+//! A change is backward compatible if everything encoded against the old metadata, such as a
+//! call or a storage key, still means the same with the new metadata, and everything the new
+//! runtime returns for it can still be decoded with the old metadata. See
+//! [`check_compatibility`](crate::compatibility::check_compatibility).
+
+use crate::{
+	diff::{
+		diff, Change, PalletDiff, RuntimeApiMethodDiff, StorageEntryDiff, TypeChange, VariantDiff,
+	},
+	metadata::{Metadata, Pallet},
+};
+use scale_info::{
+	form::PortableForm,
+	prelude::{string::String, vec, vec::Vec},
+	Variant,
+};
+
+#[cfg(feature = "serde_full")]
+use serde::Serialize;
+
+/// How an item changed between the old and the new metadata.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde_full", derive(Serialize))]
+pub enum Compatibility {
+	/// The item did not change.
+	Unchanged,
+	/// The item changed, but clients of the old metadata can keep using it.
+	BackwardCompatible,
+	/// The item changed in a way that clients of the old metadata cannot use it anymore.
+	Breaking,
+}
+
+/// The kind of a classified item.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde_full", derive(Serialize))]
+pub enum ItemKind {
+	/// A pallet call.
+	Call,
+	/// A pallet event.
+	Event,
+	/// A pallet storage entry.
+	StorageEntry,
+	/// A runtime API method.
+	RuntimeApiMethod,
+}
+
+/// A part of an item whose type changed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde_full", derive(Serialize))]
+pub enum Part {
+	/// The fields of a call or event.
+	Fields,
+	/// The key of a storage entry.
+	Key,
+	/// The value of a storage entry.
+	Value,
+	/// The parameters of a runtime API method.
+	Inputs,
+	/// The output of a runtime API method.
+	Output,
+}
+
+/// Why an item is not unchanged.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde_full", derive(Serialize))]
+pub enum Reason {
+	/// The item only exists in the new metadata.
+	Added,
+	/// The item only exists in the old metadata.
+	Removed,
+	/// The index of the pallet of a call or event changed.
+	PalletIndexChanged,
+	/// The index of a call or event variant changed.
+	VariantIndexChanged,
+	/// The prefix of the storage entries of the pallet changed.
+	StoragePrefixChanged,
+	/// The hashers of the key of a storage entry changed.
+	HashersChanged,
+	/// A storage entry became optional or stopped being optional. The stored bytes are the
+	/// same, only the value returned for a missing entry changed.
+	ModifierChanged,
+	/// The default value of a storage entry changed.
+	DefaultChanged,
+	/// A part of the item is encoded the same way, but the names of some of its types, fields
+	/// or variants changed.
+	Renamed(Part),
+	/// A part of the item is encoded differently.
+	EncodingChanged(Part),
+}
+
+impl Reason {
+	/// How compatible a change for this reason is.
+	pub fn compatibility(&self) -> Compatibility {
+		match self {
+			Reason::Added
+			| Reason::ModifierChanged
+			| Reason::DefaultChanged
+			| Reason::Renamed(_) => Compatibility::BackwardCompatible,
+			Reason::Removed
+			| Reason::PalletIndexChanged
+			| Reason::VariantIndexChanged
+			| Reason::StoragePrefixChanged
+			| Reason::HashersChanged
+			| Reason::EncodingChanged(_) => Compatibility::Breaking,
+		}
+	}
+
+	fn type_change(part: Part, change: TypeChange) -> Reason {
+		match change {
+			TypeChange::Renamed => Reason::Renamed(part),
+			TypeChange::Encoding => Reason::EncodingChanged(part),
+		}
+	}
+}
+
+/// The classification of a single item.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde_full", derive(Serialize))]
+pub struct ItemCompatibility {
+	/// The kind of the item.
+	pub kind: ItemKind,
+	/// The name of the pallet of a call, event or storage entry, or of the runtime API of a
+	/// method.
+	pub parent: String,
+	/// The name of the item.
+	pub name: String,
+	/// How compatible the changes of the item are, which is the least compatible of its
+	/// reasons.
+	pub compatibility: Compatibility,
+	/// Why the item is not unchanged, empty if it is.
+	pub reasons: Vec<Reason>,
+}
+
+/// The classification of every call, event, storage entry and runtime API method in either
+/// version of the metadata.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde_full", derive(Serialize))]
+pub struct CompatibilityReport {
+	/// The classified items, grouped by pallet or runtime API. Pallets, runtime APIs and their
+	/// items are each in the order of the old metadata, followed by the added ones.
+	pub items: Vec<ItemCompatibility>,
+}
+
+impl CompatibilityReport {
+	/// The least compatible classification of all items.
+	pub fn compatibility(&self) -> Compatibility {
+		self.items
+			.iter()
+			.map(|item| item.compatibility)
+			.max()
+			.unwrap_or(Compatibility::Unchanged)
+	}
+
+	/// The items with breaking changes.
+	pub fn breaking(&self) -> impl Iterator<Item = &ItemCompatibility> {
+		self.items
+			.iter()
+			.filter(|item| item.compatibility == Compatibility::Breaking)
+	}
+
+	/// Find the classification of an item by its kind, parent and name.
+	pub fn item(&self, kind: ItemKind, parent: &str, name: &str) -> Option<&ItemCompatibility> {
+		self.items
+			.iter()
+			.find(|item| item.kind == kind && item.parent == parent && item.name == name)
+	}
+}
+
+/// Classify every call, event, storage entry and runtime API method of the old and the new
+/// metadata.
+///
+/// Changes to the pallet index, variant indexes, storage prefix, storage hashers or to how a
+/// type is encoded are breaking, as are removed items. Added items, renames and changed
+/// storage modifiers or defaults are backward compatible.
+pub fn check_compatibility(old: &Metadata, new: &Metadata) -> CompatibilityReport {
+	let diff = diff(old, new);
+	let mut items = Vec::new();
+
+	let pallet_changes = old
+		.pallets()
+		.map(|pallet| {
+			let change = diff.pallets.iter().find(|c| c.name() == pallet.name());
+			(pallet, change)
+		})
+		.chain(diff.pallets.iter().filter_map(|change| match change {
+			Change::Added(name) => Some((new.pallet_by_name(name)?, Some(change))),
+			_ => None,
+		}));
+	for (pallet, change) in pallet_changes {
+		push_pallet_items(&mut items, pallet, change);
+	}
+
+	let api_changes = old
+		.runtime_apis()
+		.map(|api| {
+			let change = diff.runtime_apis.iter().find(|c| c.name() == api.name());
+			(api, change)
+		})
+		.chain(diff.runtime_apis.iter().filter_map(|change| match change {
+			Change::Added(name) => Some((new.runtime_api_by_name(name)?, Some(change))),
+			_ => None,
+		}));
+	for (api, change) in api_changes {
+		let (shared, methods) = match change {
+			None => (vec![], &[][..]),
+			Some(Change::Added(_)) => (vec![Reason::Added], &[][..]),
+			Some(Change::Removed(_)) => (vec![Reason::Removed], &[][..]),
+			Some(Change::Changed(_, diff)) => (vec![], &diff.methods[..]),
+		};
+		push_items(
+			&mut items,
+			ItemKind::RuntimeApiMethod,
+			api.name(),
+			api.methods().map(|method| method.name),
+			methods,
+			&shared,
+			runtime_api_method_reasons,
+		);
+	}
+
+	CompatibilityReport { items }
+}
+
+/// Classify the items of a pallet of the old metadata, or of a pallet added in the new one.
+fn push_pallet_items<'a>(
+	items: &mut Vec<ItemCompatibility>,
+	pallet: Pallet<'a>,
+	change: Option<&Change<PalletDiff>>,
+) {
+	let name = pallet.name();
+	let variant_names = |variants: &'a [Variant<PortableForm>]| {
+		variants
+			.iter()
+			.map(|variant| AsRef::<str>::as_ref(&variant.name))
+	};
+
+	let unchanged = PalletDiff::default();
+	let (shared, diff) = match change {
+		None => (vec![], &unchanged),
+		Some(Change::Added(_)) => (vec![Reason::Added], &unchanged),
+		Some(Change::Removed(_)) => (vec![Reason::Removed], &unchanged),
+		Some(Change::Changed(_, diff)) => (vec![], diff),
+	};
+
+	let index_changed: Vec<_> = shared
+		.iter()
+		.copied()
+		.chain(diff.index.as_ref().map(|_| Reason::PalletIndexChanged))
+		.collect();
+	push_items(
+		items,
+		ItemKind::Call,
+		name,
+		variant_names(pallet.calls()),
+		&diff.calls,
+		&index_changed,
+		variant_reasons,
+	);
+	push_items(
+		items,
+		ItemKind::Event,
+		name,
+		variant_names(pallet.events()),
+		&diff.events,
+		&index_changed,
+		variant_reasons,
+	);
+
+	let prefix_changed: Vec<_> = shared
+		.iter()
+		.copied()
+		.chain(
+			diff.storage_prefix
+				.as_ref()
+				.map(|_| Reason::StoragePrefixChanged),
+		)
+		.collect();
+	push_items(
+		items,
+		ItemKind::StorageEntry,
+		name,
+		pallet.storage_entries().map(|entry| entry.name),
+		&diff.storage,
+		&prefix_changed,
+		storage_entry_reasons,
+	);
+}
+
+/// Classify the items named `names` of the old metadata, and the items added in `changes`.
+///
+/// The `shared` reasons apply to every item that is not removed, such as the changed index of
+/// the pallet of a call.
+fn push_items<'a, D>(
+	items: &mut Vec<ItemCompatibility>,
+	kind: ItemKind,
+	parent: &str,
+	names: impl Iterator<Item = &'a str>,
+	changes: &[Change<D>],
+	shared: &[Reason],
+	reasons: impl Fn(&D) -> Vec<Reason>,
+) {
+	let mut push = |name: &str, reasons: Vec<Reason>| {
+		items.push(ItemCompatibility {
+			kind,
+			parent: parent.into(),
+			name: name.into(),
+			compatibility: reasons
+				.iter()
+				.map(Reason::compatibility)
+				.max()
+				.unwrap_or(Compatibility::Unchanged),
+			reasons,
+		})
+	};
+
+	for name in names {
+		let reasons = match changes.iter().find(|change| change.name() == name) {
+			Some(Change::Removed(_)) => vec![Reason::Removed],
+			Some(Change::Changed(_, diff)) => shared.iter().copied().chain(reasons(diff)).collect(),
+			_ => shared.to_vec(),
+		};
+		push(name, reasons);
+	}
+	for change in changes {
+		if let Change::Added(name) = change {
+			push(name, vec![Reason::Added]);
+		}
+	}
+}
+
+fn variant_reasons(diff: &VariantDiff) -> Vec<Reason> {
+	let index = diff.index.as_ref().map(|_| Reason::VariantIndexChanged);
+	let fields = diff
+		.fields
+		.map(|change| Reason::type_change(Part::Fields, change));
+	index.into_iter().chain(fields).collect()
+}
+
+fn storage_entry_reasons(diff: &StorageEntryDiff) -> Vec<Reason> {
+	[
+		diff.hashers.as_ref().map(|_| Reason::HashersChanged),
+		diff.key
+			.map(|change| Reason::type_change(Part::Key, change)),
+		diff.value
+			.map(|change| Reason::type_change(Part::Value, change)),
+		diff.modifier.as_ref().map(|_| Reason::ModifierChanged),
+		diff.default.as_ref().map(|_| Reason::DefaultChanged),
+	]
+	.into_iter()
+	.flatten()
+	.collect()
+}
+
+fn runtime_api_method_reasons(diff: &RuntimeApiMethodDiff) -> Vec<Reason> {
+	[
+		diff.inputs
+			.map(|change| Reason::type_change(Part::Inputs, change)),
+		diff.output
+			.map(|change| Reason::type_change(Part::Output, change)),
+	]
+	.into_iter()
+	.flatten()
+	.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		test::{kusama_v14, kusama_v15},
+		v14, v15,
+	};
+	use scale_info::{TypeDef, TypeDefPrimitive};
+
+	fn primitive(types: &scale_info::PortableRegistry, primitive: TypeDefPrimitive) -> u32 {
+		types
+			.types
+			.iter()
+			.find(|ty| ty.ty.type_def == TypeDef::Primitive(primitive.clone()))
+			.unwrap()
+			.id
+	}
+
+	fn api(
+		name: &str,
+		methods: Vec<(&str, Vec<u32>, u32)>,
+	) -> v15::RuntimeApiMetadata<PortableForm> {
+		v15::RuntimeApiMetadata {
+			name: name.into(),
+			methods: methods
+				.into_iter()
+				.map(|(name, inputs, output)| v15::RuntimeApiMethodMetadata {
+					name: name.into(),
+					inputs: inputs
+						.into_iter()
+						.map(|ty| v15::RuntimeApiMethodParamMetadata {
+							name: "input".into(),
+							ty: ty.into(),
+						})
+						.collect(),
+					output: output.into(),
+					docs: Vec::new(),
+				})
+				.collect(),
+			docs: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn should_classify_identical_metadata_as_unchanged() {
+		let metadata = Metadata::from(kusama_v14());
+		let report = check_compatibility(&metadata, &metadata);
+
+		let expected: usize = metadata
+			.pallets()
+			.map(|p| p.calls().len() + p.events().len() + p.storage_entries().count())
+			.sum();
+		assert_eq!(report.items.len(), expected);
+		assert_eq!(report.compatibility(), Compatibility::Unchanged);
+		assert!(report.items.iter().all(|item| item.reasons.is_empty()));
+	}
+
+	#[test]
+	fn should_classify_pallet_changes() {
+		let old = kusama_v14();
+		let mut new = old.clone();
+
+		let balances = new
+			.pallets
+			.iter_mut()
+			.find(|p| p.name == "Balances")
+			.unwrap();
+		balances.index = 100;
+
+		let timestamp = new
+			.pallets
+			.iter_mut()
+			.find(|p| p.name == "Timestamp")
+			.unwrap();
+		timestamp.storage.as_mut().unwrap().prefix = "Time".into();
+
+		let system = new.pallets.iter_mut().find(|p| p.name == "System").unwrap();
+		let entries = &mut system.storage.as_mut().unwrap().entries;
+		let account = entries.iter_mut().find(|e| e.name == "Account").unwrap();
+		let v14::StorageEntryType::Map { hashers, .. } = &mut account.ty else {
+			panic!("System::Account should be a map")
+		};
+		hashers[0] = v14::StorageHasher::Twox64Concat;
+		let mut added = entries.iter().find(|e| e.name == "Number").unwrap().clone();
+		added.name = "Added".into();
+		added.default = vec![1, 0, 0, 0];
+		entries.push(added);
+		let number = entries.iter_mut().find(|e| e.name == "Number").unwrap();
+		number.default = vec![1, 0, 0, 0];
+
+		let call_ty = system.calls.as_ref().unwrap().ty.id;
+		let TypeDef::Variant(call) = &mut new.types.types[call_ty as usize].ty.type_def else {
+			panic!("System calls should be a variant type")
+		};
+		call.variants.retain(|variant| variant.name != "remark");
+
+		let report = check_compatibility(&old.into(), &new.into());
+		let item = |kind, parent, name| {
+			let item = report.item(kind, parent, name).unwrap();
+			(item.compatibility, &item.reasons[..])
+		};
+
+		assert_eq!(report.compatibility(), Compatibility::Breaking);
+		assert_eq!(
+			item(ItemKind::Call, "Balances", "transfer"),
+			(Compatibility::Breaking, &[Reason::PalletIndexChanged][..])
+		);
+		assert_eq!(
+			item(ItemKind::Event, "Balances", "Transfer"),
+			(Compatibility::Breaking, &[Reason::PalletIndexChanged][..])
+		);
+		// The storage of a pallet does not depend on its index.
+		assert_eq!(
+			item(ItemKind::StorageEntry, "Balances", "TotalIssuance"),
+			(Compatibility::Unchanged, &[][..])
+		);
+		assert_eq!(
+			item(ItemKind::StorageEntry, "Timestamp", "Now"),
+			(Compatibility::Breaking, &[Reason::StoragePrefixChanged][..])
+		);
+		assert_eq!(
+			item(ItemKind::StorageEntry, "System", "Account"),
+			(Compatibility::Breaking, &[Reason::HashersChanged][..])
+		);
+		assert_eq!(
+			item(ItemKind::StorageEntry, "System", "Number"),
+			(
+				Compatibility::BackwardCompatible,
+				&[Reason::DefaultChanged][..]
+			)
+		);
+		assert_eq!(
+			item(ItemKind::StorageEntry, "System", "Added"),
+			(Compatibility::BackwardCompatible, &[Reason::Added][..])
+		);
+		assert_eq!(
+			item(ItemKind::Call, "System", "remark"),
+			(Compatibility::Breaking, &[Reason::Removed][..])
+		);
+		assert_eq!(
+			item(ItemKind::Call, "Utility", "batch"),
+			(
+				Compatibility::Breaking,
+				&[Reason::EncodingChanged(Part::Fields)][..]
+			)
+		);
+		assert_eq!(
+			item(ItemKind::Call, "System", "set_heap_pages"),
+			(Compatibility::Unchanged, &[][..])
+		);
+
+		// Added items come after the items of the old metadata.
+		let last_system_entry = report
+			.items
+			.iter()
+			.rfind(|item| item.kind == ItemKind::StorageEntry && item.parent == "System");
+		assert_eq!(last_system_entry.unwrap().name, "Added");
+	}
+
+	#[test]
+	fn should_classify_runtime_api_methods() {
+		let mut old = kusama_v15();
+		let u32_ty = primitive(&old.types, TypeDefPrimitive::U32);
+		let u64_ty = primitive(&old.types, TypeDefPrimitive::U64);
+		let mut new = old.clone();
+
+		old.apis = vec![
+			api(
+				"Core",
+				vec![
+					("version", vec![], u32_ty),
+					("execute", vec![u32_ty], u32_ty),
+				],
+			),
+			api("Removed", vec![("method", vec![], u32_ty)]),
+		];
+		new.apis = vec![
+			api(
+				"Core",
+				vec![
+					("version", vec![], u64_ty),
+					("execute", vec![u32_ty], u32_ty),
+				],
+			),
+			api("Added", vec![("method", vec![u64_ty], u32_ty)]),
+		];
+
+		let report = check_compatibility(&old.into(), &new.into());
+		let apis: Vec<_> = report
+			.items
+			.iter()
+			.filter(|item| item.kind == ItemKind::RuntimeApiMethod)
+			.map(|item| (&item.parent[..], &item.name[..], &item.reasons[..]))
+			.collect();
+		assert_eq!(
+			apis,
+			[
+				(
+					"Core",
+					"version",
+					&[Reason::EncodingChanged(Part::Output)][..]
+				),
+				("Core", "execute", &[][..]),
+				("Removed", "method", &[Reason::Removed][..]),
+				("Added", "method", &[Reason::Added][..]),
+			]
+		);
+	}
+
+	#[cfg(feature = "serde_full")]
+	#[test]
+	fn should_serialize_report() {
+		let item = ItemCompatibility {
+			kind: ItemKind::Call,
+			parent: "Balances".into(),
+			name: "transfer".into(),
+			compatibility: Compatibility::Breaking,
+			reasons: vec![Reason::PalletIndexChanged, Reason::Renamed(Part::Fields)],
+		};
+		let report = CompatibilityReport { items: vec![item] };
+		assert_eq!(
+			serde_json::to_value(&report).unwrap(),
+			serde_json::json!({
+				"items": [{
+					"kind": "Call",
+					"parent": "Balances",
+					"name": "transfer",
+					"compatibility": "Breaking",
+					"reasons": ["PalletIndexChanged", { "Renamed": "Fields" }],
+				}]
+			})
+		);
+	}
+}
@@ -0,0 +1,849 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion of the legacy V8 to V13 metadata into V14.
+//!
+//! Legacy metadata only describes types by their Rust type names, such as
+//! `Vec<T::AccountId>`. These names are parsed and resolved through a user supplied
+//! [`LegacyTypes`] table, similar to the type bundles used by polkadot.js, with built-in
+//! support for the primitive and standard library types.
+
+mod modules;
+mod type_name;
+
+use super::{find_or_push_type, push_type, v15_to_v14::unchecked_extrinsic_type, ConversionError};
+use crate::{v14, RuntimeMetadata};
+use modules::Module;
+use scale_info::{
+	form::PortableForm, Field, Path, PortableRegistry, PortableType, Type, TypeDefArray,
+	TypeDefCompact, TypeDefComposite, TypeDefPrimitive, TypeDefSequence, TypeDefTuple,
+	TypeDefVariant, TypeParameter, Variant,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use type_name::TypeName;
+
+/// Type definitions used to resolve the type names of legacy metadata.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct LegacyTypes {
+	/// Type definitions by name.
+	///
+	/// A name is looked up first as written, after normalization (e.g. `BalanceOf<T>`), and
+	/// then without its generic arguments (e.g. `BalanceOf`). Path prefixes such as `T::`
+	/// are never part of the name.
+	pub types: BTreeMap<String, LegacyTypeDef>,
+	/// The types of the signed extensions, by identifier.
+	pub signed_extensions: BTreeMap<String, LegacySignedExtension>,
+}
+
+/// The definition of a type of legacy metadata.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LegacyTypeDef {
+	/// Another name for the given type, e.g. `Balance` for `u128`.
+	Alias(String),
+	/// A struct with the given field names and types.
+	Struct(Vec<(String, String)>),
+	/// An enum with the given variant names and field types, indexed in order.
+	Enum(Vec<(String, Vec<String>)>),
+}
+
+/// The types of a signed extension of legacy metadata.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LegacySignedExtension {
+	/// The type of the signed extension, as included in the extrinsic.
+	pub ty: String,
+	/// The type of the additional data included in the signed payload.
+	pub additional_signed: String,
+}
+
+/// Legacy metadata converted to V14, together with everything that could not be resolved.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LegacyConversion {
+	/// The converted metadata.
+	pub metadata: v14::RuntimeMetadataV14,
+	/// The type names that could not be resolved, and which are described as empty
+	/// structs named after them.
+	pub unresolved_types: BTreeSet<String>,
+	/// The signed extensions without a definition, which are described as `()`.
+	pub unresolved_signed_extensions: BTreeSet<String>,
+}
+
+/// Convert V8 to V13 metadata into V14, resolving type names through `types`.
+///
+/// Every pallet call, event and error enum is built from the legacy module metadata, along
+/// with the outer `Call` and `Event` enums, which the type names `Call` and `Event` resolve
+/// to. The `Address` and `Signature` type names are resolved to form the extrinsic type.
+/// V8 to V10 do not describe the extrinsic, which is assumed to be version 4 without signed
+/// extensions.
+///
+/// Before V12 modules had no explicit index: pallets are indexed by their position, while the
+/// outer call and event enums are indexed by position among the modules with calls or events.
+pub fn upgrade_legacy_to_v14(
+	metadata: &RuntimeMetadata,
+	types: &LegacyTypes,
+) -> Result<LegacyConversion, ConversionError> {
+	let legacy = modules::normalize(metadata)
+		.ok_or(ConversionError::UnsupportedVersion(metadata.version()))?;
+
+	let mut resolver = Resolver::new(types);
+	let call_enum_ty = resolver.reserve("Call");
+	let event_enum_ty = resolver.reserve("Event");
+
+	let mut call_variants = Vec::new();
+	let mut event_variants = Vec::new();
+	let mut pallets = Vec::new();
+	for module in &legacy.modules {
+		let calls = module
+			.calls
+			.as_ref()
+			.map(|calls| resolver.calls(module, calls));
+		let event = module
+			.event
+			.as_ref()
+			.map(|events| resolver.events(module, events));
+		let error = resolver.errors(module);
+
+		if let Some(ty) = calls {
+			let index = match legacy.positional_indexes {
+				true => call_variants.len() as u8,
+				false => module.index,
+			};
+			call_variants.push(outer_variant(&module.name, ty, index));
+		}
+		if let Some(ty) = event {
+			let index = match legacy.positional_indexes {
+				true => event_variants.len() as u8,
+				false => module.index,
+			};
+			event_variants.push(outer_variant(&module.name, ty, index));
+		}
+
+		let storage = module
+			.storage
+			.as_ref()
+			.map(|storage| v14::PalletStorageMetadata {
+				prefix: storage.prefix.clone(),
+				entries: storage
+					.entries
+					.iter()
+					.map(|entry| v14::StorageEntryMetadata {
+						name: entry.name.clone(),
+						modifier: entry.modifier.clone(),
+						ty: resolver.storage_type(&entry.ty),
+						default: entry.default.clone(),
+						docs: entry.docs.clone(),
+					})
+					.collect(),
+			});
+		let constants = module
+			.constants
+			.iter()
+			.map(|constant| v14::PalletConstantMetadata {
+				name: constant.name.clone(),
+				ty: resolver.resolve_name(&constant.ty).into(),
+				value: constant.value.clone(),
+				docs: constant.docs.clone(),
+			})
+			.collect();
+
+		pallets.push(v14::PalletMetadata {
+			name: module.name.clone(),
+			storage,
+			calls: calls.map(|ty| v14::PalletCallMetadata { ty: ty.into() }),
+			event: event.map(|ty| v14::PalletEventMetadata { ty: ty.into() }),
+			constants,
+			error: error.map(|ty| v14::PalletErrorMetadata { ty: ty.into() }),
+			index: module.index,
+		});
+	}
+
+	resolver.set(
+		call_enum_ty,
+		Type::new(
+			path(["Call"]),
+			[],
+			TypeDefVariant::new(call_variants),
+			Vec::new(),
+		),
+	);
+	resolver.set(
+		event_enum_ty,
+		Type::new(
+			path(["Event"]),
+			[],
+			TypeDefVariant::new(event_variants),
+			Vec::new(),
+		),
+	);
+
+	let (version, identifiers) = legacy.extrinsic.unwrap_or((4, Vec::new()));
+	let mut unresolved_signed_extensions = BTreeSet::new();
+	let signed_extensions: Vec<_> = identifiers
+		.into_iter()
+		.map(|identifier| {
+			let (ty, additional_signed) = match types.signed_extensions.get(&identifier) {
+				Some(ext) => (
+					resolver.resolve_name(&ext.ty),
+					resolver.resolve_name(&ext.additional_signed),
+				),
+				None => {
+					unresolved_signed_extensions.insert(identifier.clone());
+					let unit = resolver.resolve(&TypeName::Tuple(Vec::new()));
+					(unit, unit)
+				}
+			};
+			v14::SignedExtensionMetadata {
+				identifier,
+				ty: ty.into(),
+				additional_signed: additional_signed.into(),
+			}
+		})
+		.collect();
+
+	let address_ty = resolver.resolve_name("Address");
+	let signature_ty = resolver.resolve_name("Signature");
+	let Resolver {
+		types: mut registry,
+		unresolved: unresolved_types,
+		..
+	} = resolver;
+
+	let extra_ty = find_or_push_type(
+		&mut registry,
+		anonymous(TypeDefTuple::new_portable(
+			signed_extensions.iter().map(|ext| ext.ty),
+		)),
+	);
+	let extrinsic_ty = unchecked_extrinsic_type(
+		&mut registry,
+		[
+			("Address", address_ty),
+			("Call", call_enum_ty),
+			("Signature", signature_ty),
+			("Extra", extra_ty),
+		],
+	);
+	let runtime_ty = push_type(
+		&mut registry,
+		Type::new(path(["Runtime"]), [], TypeDefComposite::new([]), Vec::new()),
+	);
+
+	Ok(LegacyConversion {
+		metadata: v14::RuntimeMetadataV14 {
+			types: registry,
+			pallets,
+			extrinsic: v14::ExtrinsicMetadata {
+				ty: extrinsic_ty.into(),
+				version,
+				signed_extensions,
+			},
+			ty: runtime_ty.into(),
+		},
+		unresolved_types,
+		unresolved_signed_extensions,
+	})
+}
+
+fn path<const N: usize>(segments: [&str; N]) -> Path<PortableForm> {
+	Path::from_segments_unchecked(segments.map(Into::into))
+}
+
+fn anonymous(def: impl Into<scale_info::TypeDef<PortableForm>>) -> Type<PortableForm> {
+	Type::new(Path::default(), [], def, Vec::new())
+}
+
+fn outer_variant(name: &str, ty: u32, index: u8) -> Variant<PortableForm> {
+	Variant::new(name.into(), vec![unnamed(ty)], index, Vec::new())
+}
+
+/// Builds the type registry by resolving type names.
+struct Resolver<'a> {
+	table: &'a LegacyTypes,
+	types: PortableRegistry,
+	/// The type IDs of the type names resolved so far, by their normalized name.
+	cache: BTreeMap<String, u32>,
+	/// The aliases currently being resolved, to detect cycles.
+	aliases: BTreeSet<String>,
+	unresolved: BTreeSet<String>,
+}
+
+impl<'a> Resolver<'a> {
+	fn new(table: &'a LegacyTypes) -> Self {
+		Resolver {
+			table,
+			types: PortableRegistry { types: Vec::new() },
+			cache: BTreeMap::new(),
+			aliases: BTreeSet::new(),
+			unresolved: BTreeSet::new(),
+		}
+	}
+
+	/// Reserve a type ID for the type name `name`, to be defined later with [`Self::set`].
+	fn reserve(&mut self, name: &str) -> u32 {
+		let id = push_type(&mut self.types, anonymous(TypeDefComposite::new([])));
+		self.cache.insert(name.into(), id);
+		id
+	}
+
+	fn set(&mut self, id: u32, ty: Type<PortableForm>) {
+		self.types.types[id as usize] = PortableType::new(id, ty);
+	}
+
+	/// Record `name` as unresolved, describing it as an empty struct.
+	fn unresolved(&mut self, name: &str) -> u32 {
+		if let Some(&id) = self.cache.get(name) {
+			return id;
+		}
+		let id = self.reserve(name);
+		let ty = self.unresolved_type(name);
+		self.set(id, ty);
+		id
+	}
+
+	fn unresolved_type(&mut self, name: &str) -> Type<PortableForm> {
+		self.unresolved.insert(name.into());
+		Type::new(path([name]), [], TypeDefComposite::new([]), Vec::new())
+	}
+
+	fn lookup(&self, ty: &TypeName) -> Option<&'a LegacyTypeDef> {
+		let types = &self.table.types;
+		types.get(&ty.to_string()).or_else(|| match ty {
+			TypeName::Named { name, .. } => types.get(name),
+			_ => None,
+		})
+	}
+
+	fn resolve_name(&mut self, name: &str) -> u32 {
+		match TypeName::parse(name) {
+			Some(ty) => self.resolve(&ty),
+			None => self.unresolved(name.trim()),
+		}
+	}
+
+	fn resolve(&mut self, ty: &TypeName) -> u32 {
+		let key = ty.to_string();
+		if let Some(&id) = self.cache.get(&key) {
+			return id;
+		}
+
+		let name = match ty {
+			TypeName::Named { name, .. } => name.clone(),
+			_ => key.clone(),
+		};
+		match (ty, self.lookup(ty)) {
+			(_, Some(LegacyTypeDef::Alias(target))) => {
+				self.resolve_alias(key, |resolver| resolver.resolve_name(target))
+			}
+			(_, Some(LegacyTypeDef::Struct(fields))) => self.define(key, |resolver| {
+				let fields = fields
+					.iter()
+					.map(|(name, ty)| resolver.field(Some(name), ty))
+					.collect::<Vec<_>>();
+				Some(Type::new(
+					path([&name]),
+					[],
+					TypeDefComposite::new(fields),
+					Vec::new(),
+				))
+			}),
+			(_, Some(LegacyTypeDef::Enum(variants))) => self.define(key, |resolver| {
+				let variants = variants
+					.iter()
+					.enumerate()
+					.map(|(index, (name, fields))| {
+						let fields = fields.iter().map(|ty| resolver.field(None, ty)).collect();
+						Variant::new(name.clone(), fields, index as u8, Vec::new())
+					})
+					.collect::<Vec<_>>();
+				Some(Type::new(
+					path([&name]),
+					[],
+					TypeDefVariant::new(variants),
+					Vec::new(),
+				))
+			}),
+			// Smart pointers are encoded as the type they point to.
+			(TypeName::Named { name, generics }, None) => match (name.as_str(), &generics[..]) {
+				("Box" | "Cow" | "Arc" | "Rc", [inner]) => {
+					self.resolve_alias(key, |resolver| resolver.resolve(inner))
+				}
+				_ => self.define(key, |resolver| resolver.builtin(name, generics)),
+			},
+			(TypeName::Tuple(fields), None) => self.define(key, |resolver| {
+				let fields: Vec<_> = fields.iter().map(|ty| resolver.resolve(ty)).collect();
+				Some(anonymous(TypeDefTuple::new_portable(
+					fields.into_iter().map(Into::into),
+				)))
+			}),
+			(TypeName::Array(ty, len), None) => self.define(key, |resolver| {
+				Some(anonymous(TypeDefArray::new(
+					*len,
+					resolver.resolve(ty).into(),
+				)))
+			}),
+			(TypeName::Slice(ty), None) => self.define(key, |resolver| {
+				Some(anonymous(TypeDefSequence::new(resolver.resolve(ty).into())))
+			}),
+		}
+	}
+
+	/// Define the type named `key`, or record it as unresolved if `build` returns `None`.
+	///
+	/// The type ID is reserved before calling `build`, so that recursive types resolve.
+	fn define(
+		&mut self,
+		key: String,
+		build: impl FnOnce(&mut Self) -> Option<Type<PortableForm>>,
+	) -> u32 {
+		let id = self.reserve(&key);
+		let ty = match build(self) {
+			Some(ty) => ty,
+			None => self.unresolved_type(&key),
+		};
+		self.set(id, ty);
+		id
+	}
+
+	fn resolve_alias(&mut self, key: String, resolve: impl FnOnce(&mut Self) -> u32) -> u32 {
+		if !self.aliases.insert(key.clone()) {
+			return self.unresolved(&key);
+		}
+		let id = resolve(self);
+		self.aliases.remove(&key);
+		self.cache.insert(key, id);
+		id
+	}
+
+	/// A struct or enum field, keeping the type name as written.
+	fn field(&mut self, name: Option<&String>, ty: &str) -> Field<PortableForm> {
+		Field {
+			name: name.cloned(),
+			ty: self.resolve_name(ty).into(),
+			type_name: Some(ty.into()),
+			docs: Vec::new(),
+		}
+	}
+
+	/// Build one of the primitive or standard library types.
+	fn builtin(&mut self, name: &str, generics: &[TypeName]) -> Option<Type<PortableForm>> {
+		let primitive = match name {
+			"bool" => TypeDefPrimitive::Bool,
+			"char" => TypeDefPrimitive::Char,
+			"str" | "String" => TypeDefPrimitive::Str,
+			"u8" => TypeDefPrimitive::U8,
+			"u16" => TypeDefPrimitive::U16,
+			"u32" => TypeDefPrimitive::U32,
+			"u64" => TypeDefPrimitive::U64,
+			"u128" => TypeDefPrimitive::U128,
+			"u256" | "U256" => TypeDefPrimitive::U256,
+			"i8" => TypeDefPrimitive::I8,
+			"i16" => TypeDefPrimitive::I16,
+			"i32" => TypeDefPrimitive::I32,
+			"i64" => TypeDefPrimitive::I64,
+			"i128" => TypeDefPrimitive::I128,
+			"i256" => TypeDefPrimitive::I256,
+			_ => return self.generic_builtin(name, generics),
+		};
+		generics.is_empty().then(|| anonymous(primitive))
+	}
+
+	fn generic_builtin(&mut self, name: &str, generics: &[TypeName]) -> Option<Type<PortableForm>> {
+		let ty = match (name, generics) {
+			(
+				"Vec" | "VecDeque" | "BTreeSet" | "BoundedVec" | "WeakBoundedVec"
+				| "BoundedBTreeSet",
+				[ty, ..],
+			) => anonymous(TypeDefSequence::new(self.resolve(ty).into())),
+			("BTreeMap" | "BoundedBTreeMap", [key, value, ..]) => {
+				let entry = TypeName::Tuple(vec![key.clone(), value.clone()]);
+				anonymous(TypeDefSequence::new(self.resolve(&entry).into()))
+			}
+			("Compact", [ty]) => anonymous(TypeDefCompact::new(self.resolve(ty).into())),
+			("PhantomData", _) => anonymous(TypeDefTuple::new_portable([])),
+			("Option", [ty]) => {
+				let ty = self.resolve(ty);
+				let variants = [
+					Variant::new("None".into(), Vec::new(), 0, Vec::new()),
+					Variant::new("Some".into(), vec![unnamed(ty)], 1, Vec::new()),
+				];
+				Type::new(
+					path(["Option"]),
+					[TypeParameter::new_portable("T".into(), Some(ty.into()))],
+					TypeDefVariant::new(variants),
+					Vec::new(),
+				)
+			}
+			// The keys of the neighbours of a linked map entry, stored next to its value.
+			("Linkage", [key]) => {
+				let option = TypeName::Named {
+					name: "Option".into(),
+					generics: vec![key.clone()],
+				};
+				let option = self.resolve(&option);
+				let link = |name: &str| Field {
+					name: Some(name.into()),
+					ty: option.into(),
+					type_name: Some("Option<Key>".into()),
+					docs: Vec::new(),
+				};
+				Type::new(
+					path(["Linkage"]),
+					[TypeParameter::new_portable(
+						"Key".into(),
+						Some(self.resolve(key).into()),
+					)],
+					TypeDefComposite::new([link("previous"), link("next")]),
+					Vec::new(),
+				)
+			}
+			("Result", [ok, err]) => {
+				let ok = self.resolve(ok);
+				let err = self.resolve(err);
+				let variants = [
+					Variant::new("Ok".into(), vec![unnamed(ok)], 0, Vec::new()),
+					Variant::new("Err".into(), vec![unnamed(err)], 1, Vec::new()),
+				];
+				Type::new(
+					path(["Result"]),
+					[
+						TypeParameter::new_portable("T".into(), Some(ok.into())),
+						TypeParameter::new_portable("E".into(), Some(err.into())),
+					],
+					TypeDefVariant::new(variants),
+					Vec::new(),
+				)
+			}
+			_ => return None,
+		};
+		Some(ty)
+	}
+
+	fn storage_type(&mut self, ty: &modules::StorageType) -> v14::StorageEntryType<PortableForm> {
+		match ty {
+			modules::StorageType::Plain(ty) => {
+				v14::StorageEntryType::Plain(self.resolve_name(ty).into())
+			}
+			modules::StorageType::Map {
+				hashers,
+				keys,
+				value,
+			} => {
+				let keys: Vec<_> = keys.iter().map(|key| self.resolve_name(key)).collect();
+				let key = match keys[..] {
+					[key] => key,
+					_ => find_or_push_type(
+						&mut self.types,
+						anonymous(TypeDefTuple::new_portable(keys.into_iter().map(Into::into))),
+					),
+				};
+				v14::StorageEntryType::Map {
+					hashers: hashers.clone(),
+					key: key.into(),
+					value: self.resolve_name(value).into(),
+				}
+			}
+		}
+	}
+
+	fn calls(&mut self, module: &Module, calls: &[modules::Call]) -> u32 {
+		let variants = calls
+			.iter()
+			.enumerate()
+			.map(|(index, call)| {
+				let fields = call
+					.arguments
+					.iter()
+					.map(|(name, ty)| self.field(Some(name), ty))
+					.collect();
+				Variant::new(call.name.clone(), fields, index as u8, call.docs.clone())
+			})
+			.collect::<Vec<_>>();
+		self.pallet_enum(module, "Call", variants)
+	}
+
+	fn events(&mut self, module: &Module, events: &[modules::Event]) -> u32 {
+		let variants = events
+			.iter()
+			.enumerate()
+			.map(|(index, event)| {
+				let fields = event
+					.arguments
+					.iter()
+					.map(|ty| self.field(None, ty))
+					.collect();
+				Variant::new(event.name.clone(), fields, index as u8, event.docs.clone())
+			})
+			.collect::<Vec<_>>();
+		self.pallet_enum(module, "Event", variants)
+	}
+
+	fn errors(&mut self, module: &Module) -> Option<u32> {
+		if module.errors.is_empty() {
+			return None;
+		}
+		let variants = module
+			.errors
+			.iter()
+			.enumerate()
+			.map(|(index, error)| {
+				Variant::new(
+					error.name.clone(),
+					Vec::new(),
+					index as u8,
+					error.docs.clone(),
+				)
+			})
+			.collect();
+		Some(self.pallet_enum(module, "Error", variants))
+	}
+
+	fn pallet_enum(
+		&mut self,
+		module: &Module,
+		name: &str,
+		variants: Vec<Variant<PortableForm>>,
+	) -> u32 {
+		push_type(
+			&mut self.types,
+			Type::new(
+				path([&module.name, name]),
+				[],
+				TypeDefVariant::new(variants),
+				Vec::new(),
+			),
+		)
+	}
+}
+
+fn unnamed(ty: u32) -> Field<PortableForm> {
+	Field {
+		name: None,
+		ty: ty.into(),
+		type_name: None,
+		docs: Vec::new(),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{test::load_metadata, RuntimeMetadataPrefixed};
+	use codec::Decode;
+	use scale_info::TypeDef;
+
+	fn kusama(version: u32) -> RuntimeMetadata {
+		RuntimeMetadataPrefixed::decode(&mut load_metadata(version).as_slice())
+			.unwrap()
+			.1
+	}
+
+	fn alias(name: &str, target: &str) -> (String, LegacyTypeDef) {
+		(name.into(), LegacyTypeDef::Alias(target.into()))
+	}
+
+	fn kusama_types() -> LegacyTypes {
+		LegacyTypes {
+			types: BTreeMap::from([
+				alias("AccountId", "[u8; 32]"),
+				alias("Address", "AccountId"),
+				alias("Balance", "u128"),
+				alias("BalanceOf", "Balance"),
+				alias("Index", "u32"),
+				(
+					"Signature".into(),
+					LegacyTypeDef::Enum(vec![
+						("Ed25519".into(), vec!["[u8; 64]".into()]),
+						("Sr25519".into(), vec!["[u8; 64]".into()]),
+						("Ecdsa".into(), vec!["[u8; 65]".into()]),
+					]),
+				),
+			]),
+			signed_extensions: BTreeMap::from([(
+				"CheckNonce".into(),
+				LegacySignedExtension {
+					ty: "Compact<Index>".into(),
+					additional_signed: "()".into(),
+				},
+			)]),
+		}
+	}
+
+	fn variants(types: &PortableRegistry, id: u32) -> &[Variant<PortableForm>] {
+		match &types.resolve(id).unwrap().type_def {
+			TypeDef::Variant(def) => &def.variants,
+			_ => panic!("type {id} should be a variant type"),
+		}
+	}
+
+	#[test]
+	fn should_convert_kusama_v13() {
+		let conversion = upgrade_legacy_to_v14(&kusama(13), &kusama_types()).unwrap();
+		let metadata = conversion.metadata;
+
+		for (i, ty) in metadata.types.types.iter().enumerate() {
+			assert_eq!(ty.id as usize, i);
+		}
+
+		let balances = metadata
+			.pallets
+			.iter()
+			.find(|p| p.name == "Balances")
+			.unwrap();
+		let transfer = &variants(&metadata.types, balances.calls.as_ref().unwrap().ty.id)[0];
+		assert_eq!(transfer.name, "transfer");
+		let value = &transfer.fields[1];
+		assert_eq!(value.name.as_deref(), Some("value"));
+		assert_eq!(value.type_name.as_deref(), Some("Compact<T::Balance>"));
+		let TypeDef::Compact(value) = &metadata.types.resolve(value.ty.id).unwrap().type_def else {
+			panic!("transfer value should be compact")
+		};
+		assert_eq!(
+			metadata
+				.types
+				.resolve(value.type_param.id)
+				.unwrap()
+				.type_def,
+			TypeDef::Primitive(TypeDefPrimitive::U128)
+		);
+
+		for name in ["AccountId", "Balance", "BalanceOf<T>", "Signature"] {
+			assert!(!conversion.unresolved_types.contains(name), "{name}");
+		}
+		assert!(conversion.unresolved_types.contains("Weight"));
+		assert!(!conversion
+			.unresolved_signed_extensions
+			.contains("CheckNonce"));
+		assert!(conversion
+			.unresolved_signed_extensions
+			.contains("CheckWeight"));
+
+		// The outer call enum is indexed by the module index since V12.
+		let v15 = crate::v15::RuntimeMetadataV15::try_from(metadata.clone()).unwrap();
+		let call_enum = variants(&v15.types, v15.outer_enums.call_enum_ty.id);
+		let pallets_with_calls = metadata.pallets.iter().filter(|p| p.calls.is_some());
+		assert_eq!(call_enum.len(), pallets_with_calls.clone().count());
+		for (variant, pallet) in call_enum.iter().zip(pallets_with_calls) {
+			assert_eq!(variant.name, pallet.name);
+			assert_eq!(variant.index, pallet.index);
+		}
+	}
+
+	#[test]
+	fn should_index_outer_enums_by_position_before_v12() {
+		let conversion = upgrade_legacy_to_v14(&kusama(11), &kusama_types()).unwrap();
+		let metadata = conversion.metadata;
+
+		for (i, pallet) in metadata.pallets.iter().enumerate() {
+			assert_eq!(pallet.index as usize, i);
+		}
+		let call_ty =
+			crate::convert::extrinsic_type_param(&metadata.types, metadata.extrinsic.ty.id, "Call")
+				.unwrap();
+		for (i, variant) in variants(&metadata.types, call_ty).iter().enumerate() {
+			assert_eq!(variant.index as usize, i);
+		}
+		assert!(metadata.pallets.iter().any(|p| p.calls.is_none()));
+	}
+
+	#[test]
+	fn should_assume_extrinsic_v4_before_v11() {
+		let conversion = upgrade_legacy_to_v14(&kusama(9), &kusama_types()).unwrap();
+		assert_eq!(conversion.metadata.extrinsic.version, 4);
+		assert!(conversion.metadata.extrinsic.signed_extensions.is_empty());
+		assert!(conversion.unresolved_signed_extensions.is_empty());
+	}
+
+	#[test]
+	fn should_store_linked_map_values_with_their_linkage() {
+		let conversion = upgrade_legacy_to_v14(&kusama(10), &kusama_types()).unwrap();
+		let types = &conversion.metadata.types;
+		let staking = conversion
+			.metadata
+			.pallets
+			.iter()
+			.find(|p| p.name == "Staking")
+			.unwrap();
+		let entry = |name: &str| {
+			let entries = &staking.storage.as_ref().unwrap().entries;
+			match &entries.iter().find(|e| e.name == name).unwrap().ty {
+				crate::v14::StorageEntryType::Map { key, value, .. } => (key.id, value.id),
+				_ => panic!("{name} should be a map"),
+			}
+		};
+
+		let (key, value) = entry("Validators");
+		let TypeDef::Tuple(value) = &types.resolve(value).unwrap().type_def else {
+			panic!("linked map values should be stored with their linkage")
+		};
+		let linkage = types.resolve(value.fields[1].id).unwrap();
+		assert_eq!(linkage.path.segments, ["Linkage"]);
+		let TypeDef::Composite(linkage) = &linkage.type_def else {
+			panic!("linkage should be a composite type")
+		};
+		for (field, name) in linkage.fields.iter().zip(["previous", "next"]) {
+			assert_eq!(field.name.as_deref(), Some(name));
+			let option = types.resolve(field.ty.id).unwrap();
+			assert_eq!(option.path.segments, ["Option"]);
+			assert_eq!(option.type_params[0].ty.unwrap().id, key);
+		}
+
+		// Only linked maps have a linkage.
+		let (_, value) = entry("Bonded");
+		assert!(!matches!(
+			types.resolve(value).unwrap().type_def,
+			TypeDef::Tuple(_)
+		));
+	}
+
+	#[test]
+	fn should_reject_non_legacy_metadata() {
+		assert_eq!(
+			upgrade_legacy_to_v14(&kusama(14), &LegacyTypes::default()),
+			Err(ConversionError::UnsupportedVersion(14))
+		);
+	}
+
+	#[test]
+	fn should_resolve_recursive_types_and_report_alias_cycles() {
+		let table = LegacyTypes {
+			types: BTreeMap::from([
+				alias("A", "B"),
+				alias("B", "A"),
+				(
+					"Node".into(),
+					LegacyTypeDef::Struct(vec![("children".into(), "Vec<Box<Node>>".into())]),
+				),
+			]),
+			..Default::default()
+		};
+		let mut resolver = Resolver::new(&table);
+
+		let node = resolver.resolve_name("Node");
+		let TypeDef::Composite(def) = &resolver.types.resolve(node).unwrap().type_def else {
+			panic!("node should be a composite type")
+		};
+		let TypeDef::Sequence(children) = &resolver
+			.types
+			.resolve(def.fields[0].ty.id)
+			.unwrap()
+			.type_def
+		else {
+			panic!("children should be a sequence")
+		};
+		assert_eq!(children.type_param.id, node);
+
+		resolver.resolve_name("Vec<A>");
+		assert_eq!(resolver.unresolved, BTreeSet::from(["A".into()]));
+	}
+}
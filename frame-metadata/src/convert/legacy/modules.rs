@@ -0,0 +1,400 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A version independent view of the V8 to V13 metadata.
+//!
+//! The legacy metadata versions only differ in a few details, but each version has its own
+//! set of types. These are normalized into the types of this module before conversion.
+
+use crate::{
+	decode_different::{DecodeDifferent, DecodeDifferentArray, DecodeDifferentStr, FnEncode},
+	v14, RuntimeMetadata,
+};
+use codec::Encode;
+
+/// The modules and extrinsic information of legacy metadata.
+pub(super) struct Metadata {
+	pub modules: Vec<Module>,
+	/// The extrinsic version and signed extension identifiers, which V8 to V10 do not have.
+	pub extrinsic: Option<(u8, Vec<String>)>,
+	/// Whether the call and event enums only have variants for the modules with calls or
+	/// events, rather than being indexed by the module index.
+	pub positional_indexes: bool,
+}
+
+pub(super) struct Module {
+	pub name: String,
+	pub index: u8,
+	pub storage: Option<Storage>,
+	pub calls: Option<Vec<Call>>,
+	pub event: Option<Vec<Event>>,
+	pub constants: Vec<Constant>,
+	pub errors: Vec<Error>,
+}
+
+pub(super) struct Storage {
+	pub prefix: String,
+	pub entries: Vec<StorageEntry>,
+}
+
+pub(super) struct StorageEntry {
+	pub name: String,
+	pub modifier: v14::StorageEntryModifier,
+	pub ty: StorageType,
+	pub default: Vec<u8>,
+	pub docs: Vec<String>,
+}
+
+pub(super) enum StorageType {
+	Plain(String),
+	/// A map with one hasher per key.
+	Map {
+		hashers: Vec<v14::StorageHasher>,
+		keys: Vec<String>,
+		value: String,
+	},
+}
+
+pub(super) struct Call {
+	pub name: String,
+	/// The argument names and types.
+	pub arguments: Vec<(String, String)>,
+	pub docs: Vec<String>,
+}
+
+pub(super) struct Event {
+	pub name: String,
+	pub arguments: Vec<String>,
+	pub docs: Vec<String>,
+}
+
+pub(super) struct Constant {
+	pub name: String,
+	pub ty: String,
+	pub value: Vec<u8>,
+	pub docs: Vec<String>,
+}
+
+pub(super) struct Error {
+	pub name: String,
+	pub docs: Vec<String>,
+}
+
+/// Normalize legacy metadata, returning `None` if it is not one of V8 to V13.
+pub(super) fn normalize(metadata: &RuntimeMetadata) -> Option<Metadata> {
+	fn positional<M>(modules: &[M], module: fn(&M, u8) -> Module) -> Vec<Module> {
+		modules
+			.iter()
+			.enumerate()
+			.map(|(index, m)| module(m, index as u8))
+			.collect()
+	}
+
+	let metadata = match metadata {
+		RuntimeMetadata::V8(m) => Metadata {
+			modules: positional(array(&m.modules), v8::module),
+			extrinsic: None,
+			positional_indexes: true,
+		},
+		RuntimeMetadata::V9(m) => Metadata {
+			modules: positional(array(&m.modules), v9::module),
+			extrinsic: None,
+			positional_indexes: true,
+		},
+		RuntimeMetadata::V10(m) => Metadata {
+			modules: positional(array(&m.modules), v10::module),
+			extrinsic: None,
+			positional_indexes: true,
+		},
+		RuntimeMetadata::V11(m) => Metadata {
+			modules: positional(array(&m.modules), v11::module),
+			extrinsic: Some((
+				m.extrinsic.version,
+				m.extrinsic.signed_extensions.iter().map(string).collect(),
+			)),
+			positional_indexes: true,
+		},
+		RuntimeMetadata::V12(m) => Metadata {
+			modules: array(&m.modules)
+				.iter()
+				.map(|m| v12::module(m, m.index))
+				.collect(),
+			extrinsic: Some((
+				m.extrinsic.version,
+				m.extrinsic.signed_extensions.iter().map(string).collect(),
+			)),
+			positional_indexes: false,
+		},
+		RuntimeMetadata::V13(m) => Metadata {
+			modules: array(&m.modules)
+				.iter()
+				.map(|m| v13::module(m, m.index))
+				.collect(),
+			extrinsic: Some((
+				m.extrinsic.version,
+				m.extrinsic.signed_extensions.iter().map(string).collect(),
+			)),
+			positional_indexes: false,
+		},
+		_ => return None,
+	};
+	Some(metadata)
+}
+
+fn string(s: &DecodeDifferentStr) -> String {
+	match s {
+		DecodeDifferent::Encode(s) => (*s).into(),
+		DecodeDifferent::Decoded(s) => s.clone(),
+	}
+}
+
+fn strings(s: &DecodeDifferentArray<&'static str, String>) -> Vec<String> {
+	match s {
+		DecodeDifferent::Encode(s) => s.iter().map(|s| (*s).into()).collect(),
+		DecodeDifferent::Decoded(s) => s.clone(),
+	}
+}
+
+fn array<T>(array: &DecodeDifferentArray<T>) -> &[T] {
+	match array {
+		DecodeDifferent::Encode(array) => array,
+		DecodeDifferent::Decoded(array) => array,
+	}
+}
+
+fn fn_array<'a, T: Encode>(array: &'a DecodeDifferent<FnEncode<&'static [T]>, Vec<T>>) -> &'a [T] {
+	match array {
+		DecodeDifferent::Encode(f) => f.0(),
+		DecodeDifferent::Decoded(array) => array,
+	}
+}
+
+/// The value type name of a linked map entry, which is stored together with the keys of the
+/// previous and next entries.
+fn linked_value(value: &str, key: &str) -> String {
+	format!("({value}, Linkage<{key}>)")
+}
+
+/// Implement the normalization of the modules of a legacy metadata version, given the
+/// storage hashers it knows about and either the name of its flag of linked maps or the name
+/// of its N-map storage type.
+macro_rules! normalize_modules {
+	($version:ident, [$($hasher:ident),*] $(, linked = $linked:ident)? $(, nmap = $nmap:ident)?) => {
+		mod $version {
+			use super::*;
+			use crate::$version::*;
+
+			fn hasher(hasher: &StorageHasher) -> v14::StorageHasher {
+				match hasher {
+					$(StorageHasher::$hasher => v14::StorageHasher::$hasher,)*
+				}
+			}
+
+			fn bytes(bytes: &ByteGetter) -> Vec<u8> {
+				match bytes {
+					DecodeDifferent::Encode(getter) => getter.0.default_byte(),
+					DecodeDifferent::Decoded(bytes) => bytes.clone(),
+				}
+			}
+
+			fn storage_type(ty: &StorageEntryType) -> StorageType {
+				match ty {
+					StorageEntryType::Plain(ty) => StorageType::Plain(string(ty)),
+					StorageEntryType::Map {
+						hasher: h,
+						key,
+						value,
+						$($linked,)?
+						..
+					} => {
+						let value = string(value);
+						$(let value = match $linked {
+							true => linked_value(&value, &string(key)),
+							false => value,
+						};)?
+						StorageType::Map {
+							hashers: vec![hasher(h)],
+							keys: vec![string(key)],
+							value,
+						}
+					}
+					StorageEntryType::DoubleMap {
+						hasher: h,
+						key1,
+						key2,
+						value,
+						key2_hasher,
+					} => StorageType::Map {
+						hashers: vec![hasher(h), hasher(key2_hasher)],
+						keys: vec![string(key1), string(key2)],
+						value: string(value),
+					},
+					$(StorageEntryType::$nmap {
+						keys,
+						hashers,
+						value,
+					} => StorageType::Map {
+						hashers: array(hashers).iter().map(hasher).collect(),
+						keys: strings(keys),
+						value: string(value),
+					},)?
+				}
+			}
+
+			fn storage(storage: StorageMetadata) -> Storage {
+				let entries = array(&storage.entries)
+					.iter()
+					.map(|entry| StorageEntry {
+						name: string(&entry.name),
+						modifier: match entry.modifier {
+							StorageEntryModifier::Optional => v14::StorageEntryModifier::Optional,
+							StorageEntryModifier::Default => v14::StorageEntryModifier::Default,
+						},
+						ty: storage_type(&entry.ty),
+						default: bytes(&entry.default),
+						docs: strings(&entry.documentation),
+					})
+					.collect();
+				Storage {
+					prefix: string(&storage.prefix),
+					entries,
+				}
+			}
+
+			pub(super) fn module(module: &ModuleMetadata, index: u8) -> Module {
+				let storage = module.storage.as_ref().map(|s| match s {
+					DecodeDifferent::Encode(f) => storage(f.0()),
+					DecodeDifferent::Decoded(s) => storage(s.clone()),
+				});
+				let calls = module.calls.as_ref().map(|calls| {
+					fn_array(calls)
+						.iter()
+						.map(|call| Call {
+							name: string(&call.name),
+							arguments: array(&call.arguments)
+								.iter()
+								.map(|arg| (string(&arg.name), string(&arg.ty)))
+								.collect(),
+							docs: strings(&call.documentation),
+						})
+						.collect()
+				});
+				let event = module.event.as_ref().map(|event| {
+					fn_array(event)
+						.iter()
+						.map(|event| Event {
+							name: string(&event.name),
+							arguments: strings(&event.arguments),
+							docs: strings(&event.documentation),
+						})
+						.collect()
+				});
+				let constants = fn_array(&module.constants)
+					.iter()
+					.map(|constant| Constant {
+						name: string(&constant.name),
+						ty: string(&constant.ty),
+						value: bytes(&constant.value),
+						docs: strings(&constant.documentation),
+					})
+					.collect();
+				let errors = fn_array(&module.errors)
+					.iter()
+					.map(|error| Error {
+						name: string(&error.name),
+						docs: strings(&error.documentation),
+					})
+					.collect();
+
+				Module {
+					name: string(&module.name),
+					index,
+					storage,
+					calls,
+					event,
+					constants,
+					errors,
+				}
+			}
+		}
+	};
+}
+
+normalize_modules!(
+	v8,
+	[Blake2_128, Blake2_256, Twox128, Twox256, Twox64Concat],
+	linked = is_linked
+);
+normalize_modules!(
+	v9,
+	[
+		Blake2_128,
+		Blake2_256,
+		Blake2_128Concat,
+		Twox128,
+		Twox256,
+		Twox64Concat
+	],
+	linked = is_linked
+);
+normalize_modules!(
+	v10,
+	[
+		Blake2_128,
+		Blake2_256,
+		Blake2_128Concat,
+		Twox128,
+		Twox256,
+		Twox64Concat
+	],
+	linked = is_linked
+);
+normalize_modules!(
+	v11,
+	[
+		Blake2_128,
+		Blake2_256,
+		Blake2_128Concat,
+		Twox128,
+		Twox256,
+		Twox64Concat,
+		Identity
+	]
+);
+normalize_modules!(
+	v12,
+	[
+		Blake2_128,
+		Blake2_256,
+		Blake2_128Concat,
+		Twox128,
+		Twox256,
+		Twox64Concat,
+		Identity
+	]
+);
+normalize_modules!(
+	v13,
+	[
+		Blake2_128,
+		Blake2_256,
+		Blake2_128Concat,
+		Twox128,
+		Twox256,
+		Twox64Concat,
+		Identity
+	],
+	nmap = NMap
+);
@@ -0,0 +1,269 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing of the Rust type names found in legacy metadata.
+
+use core::fmt;
+
+/// A Rust type name, as written in the source of a legacy runtime.
+///
+/// Only the last segment of a path is kept, so `T::AccountId`, `<T as Trait>::AccountId`
+/// and `frame_system::AccountId` are all the type `AccountId`. References, lifetimes and
+/// trait qualifications are dropped.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(super) enum TypeName {
+	/// A named type and its generic arguments, e.g. `Vec<u8>`.
+	Named {
+		name: String,
+		generics: Vec<TypeName>,
+	},
+	/// A tuple, e.g. `(u32, bool)`.
+	Tuple(Vec<TypeName>),
+	/// A fixed size array, e.g. `[u8; 32]`.
+	Array(Box<TypeName>, u32),
+	/// A slice, e.g. `&[u8]`.
+	Slice(Box<TypeName>),
+}
+
+impl TypeName {
+	/// Parse a type name, returning `None` if it is not a valid Rust type.
+	pub(super) fn parse(input: &str) -> Option<TypeName> {
+		let mut parser = Parser { input, pos: 0 };
+		let ty = parser.ty()?;
+		parser.peek().is_none().then_some(ty)
+	}
+}
+
+impl fmt::Display for TypeName {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fn list(f: &mut fmt::Formatter<'_>, items: &[TypeName]) -> fmt::Result {
+			for (i, item) in items.iter().enumerate() {
+				if i > 0 {
+					f.write_str(", ")?;
+				}
+				write!(f, "{item}")?;
+			}
+			Ok(())
+		}
+
+		match self {
+			TypeName::Named { name, generics } => {
+				f.write_str(name)?;
+				if !generics.is_empty() {
+					f.write_str("<")?;
+					list(f, generics)?;
+					f.write_str(">")?;
+				}
+				Ok(())
+			}
+			TypeName::Tuple(fields) => {
+				f.write_str("(")?;
+				list(f, fields)?;
+				if fields.len() == 1 {
+					f.write_str(",")?;
+				}
+				f.write_str(")")
+			}
+			TypeName::Array(ty, len) => write!(f, "[{ty}; {len}]"),
+			TypeName::Slice(ty) => write!(f, "[{ty}]"),
+		}
+	}
+}
+
+struct Parser<'a> {
+	input: &'a str,
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn rest(&self) -> &'a str {
+		&self.input[self.pos..]
+	}
+
+	fn peek(&mut self) -> Option<char> {
+		let rest = self.rest();
+		self.pos += rest.len() - rest.trim_start().len();
+		self.rest().chars().next()
+	}
+
+	fn eat(&mut self, token: &str) -> bool {
+		self.peek();
+		if self.rest().starts_with(token) {
+			self.pos += token.len();
+			true
+		} else {
+			false
+		}
+	}
+
+	fn expect(&mut self, token: &str) -> Option<()> {
+		self.eat(token).then_some(())
+	}
+
+	fn ident(&mut self) -> Option<&'a str> {
+		self.peek();
+		let rest = self.rest();
+		let len = rest
+			.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+			.unwrap_or(rest.len());
+		self.pos += len;
+		(len > 0).then(|| &rest[..len])
+	}
+
+	fn lifetime(&mut self) -> bool {
+		self.eat("'") && self.ident().is_some()
+	}
+
+	fn ty(&mut self) -> Option<TypeName> {
+		match self.peek()? {
+			'&' => {
+				self.eat("&");
+				self.lifetime();
+				// `mut` is followed by whitespace, unlike a type name starting with "mut".
+				if self.rest().starts_with("mut ") {
+					self.eat("mut");
+				}
+				self.ty()
+			}
+			'(' => {
+				self.eat("(");
+				let mut fields = Vec::new();
+				let mut trailing_comma = false;
+				while !self.eat(")") {
+					fields.push(self.ty()?);
+					trailing_comma = self.eat(",");
+					if !trailing_comma {
+						self.expect(")")?;
+						break;
+					}
+				}
+				if fields.len() == 1 && !trailing_comma {
+					return fields.pop();
+				}
+				Some(TypeName::Tuple(fields))
+			}
+			'[' => {
+				self.eat("[");
+				let ty = Box::new(self.ty()?);
+				if self.eat(";") {
+					let len = self.ident()?.parse().ok()?;
+					self.expect("]")?;
+					Some(TypeName::Array(ty, len))
+				} else {
+					self.expect("]")?;
+					Some(TypeName::Slice(ty))
+				}
+			}
+			'<' => {
+				// A qualified path such as `<T as Trait<I>>::Balance`.
+				self.eat("<");
+				self.ty()?;
+				if self.ident()? != "as" {
+					return None;
+				}
+				self.path()?;
+				self.expect(">")?;
+				self.expect("::")?;
+				self.path()
+			}
+			_ => self.path(),
+		}
+	}
+
+	fn path(&mut self) -> Option<TypeName> {
+		self.eat("::");
+		loop {
+			let name = self.ident()?;
+			let generics = if self.eat("<") {
+				self.generics()?
+			} else {
+				Vec::new()
+			};
+			if !self.eat("::") {
+				return Some(TypeName::Named {
+					name: name.into(),
+					generics,
+				});
+			}
+		}
+	}
+
+	/// Parse generic arguments after the opening `<`, skipping lifetimes.
+	fn generics(&mut self) -> Option<Vec<TypeName>> {
+		let mut generics = Vec::new();
+		while !self.eat(">") {
+			if !self.lifetime() {
+				generics.push(self.ty()?);
+			}
+			if !self.eat(",") {
+				self.expect(">")?;
+				break;
+			}
+		}
+		Some(generics)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn canonical(input: &str) -> Option<String> {
+		TypeName::parse(input).map(|ty| ty.to_string())
+	}
+
+	#[test]
+	fn should_parse_legacy_type_names() {
+		let cases = [
+			("u32", "u32"),
+			("T::AccountId", "AccountId"),
+			("<T as Trait>::Balance", "Balance"),
+			("<T as Trait<I>>::Balance", "Balance"),
+			("<T as frame_system::Config>::BlockNumber", "BlockNumber"),
+			("BalanceOf<T>", "BalanceOf<T>"),
+			(
+				"Vec<(T::AccountId, BalanceOf<T>)>",
+				"Vec<(AccountId, BalanceOf<T>)>",
+			),
+			("Box<<T as Trait>::Call>", "Box<Call>"),
+			("&'static [u8]", "[u8]"),
+			("[u8; 32]", "[u8; 32]"),
+			("Compact<T::Balance>", "Compact<Balance>"),
+			("Cow<'static, str>", "Cow<str>"),
+			("()", "()"),
+			("(u32,)", "(u32,)"),
+			("(u32)", "u32"),
+			("Vec<\n\tT::AccountId\n>", "Vec<AccountId>"),
+			(
+				"BoundedVec<u8, ConstU32<32>>",
+				"BoundedVec<u8, ConstU32<32>>",
+			),
+		];
+		for (input, expected) in cases {
+			assert_eq!(
+				canonical(input).as_deref(),
+				Some(expected),
+				"parsing {input:?}"
+			);
+		}
+	}
+
+	#[test]
+	fn should_reject_invalid_type_names() {
+		for input in ["", "Vec<u8", "[u8; n]", "<T>::Balance", "u32 u32", "(u32"] {
+			assert_eq!(canonical(input), None, "parsing {input:?}");
+		}
+	}
+}
@@ -0,0 +1,234 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversions between the different metadata versions.
+//!
+//! Upgrading metadata keeps everything that the newer version can describe, while
+//! downgrading reports what was dropped in a [`Downgraded`](crate::convert::Downgraded).
+//! Some fields of the target version have to be derived from the type registry, and where
+//! the target version needs a type that the source does not describe, a new type is
+//! appended to the [`PortableRegistry`](scale_info::PortableRegistry).
+
+#[cfg(all(feature = "legacy", feature = "decode"))]
+mod legacy;
+mod v14_to_v15;
+mod v15_to_v14;
+mod v15_to_v16;
+mod v16_to_v15;
+
+#[cfg(all(feature = "legacy", feature = "decode"))]
+pub use legacy::{
+	upgrade_legacy_to_v14, LegacyConversion, LegacySignedExtension, LegacyTypeDef, LegacyTypes,
+};
+pub use v15_to_v14::downgrade_v15_to_v14;
+pub use v16_to_v15::{downgrade_v16_to_v14, downgrade_v16_to_v15};
+
+use scale_info::{
+	form::{Form, PortableForm},
+	prelude::{string::String, vec::Vec},
+	Path, PortableRegistry, PortableType, Type, TypeDef, TypeDefVariant, Variant,
+};
+
+/// An error that can occur when converting metadata between versions.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ConversionError {
+	/// A type ID referenced by the metadata was not found in the type registry.
+	TypeNotFound(u32),
+	/// The extrinsic type does not have the expected generic type parameter.
+	MissingExtrinsicTypeParam(&'static str),
+	/// The metadata does not support the extrinsic version required by the target version.
+	UnsupportedExtrinsicVersion(u8),
+	/// A transaction extension index is out of range.
+	TransactionExtensionNotFound(u32),
+	/// The metadata does not describe the transaction extensions of the given extension
+	/// version.
+	UnsupportedExtensionVersion(u8),
+	/// The metadata version cannot be converted.
+	UnsupportedVersion(u32),
+}
+
+/// Metadata downgraded to an older version, together with the information that the older
+/// version could not represent.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Downgraded<M> {
+	/// The downgraded metadata.
+	pub metadata: M,
+	/// Everything that was dropped during the downgrade.
+	pub lost: Vec<LostInformation>,
+}
+
+/// A piece of information dropped when downgrading metadata.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LostInformation {
+	/// An extrinsic version other than 4.
+	ExtrinsicVersion(u8),
+	/// A transaction extension that is not used by version 4 extrinsics.
+	TransactionExtension(String),
+	/// A pallet view function.
+	ViewFunction {
+		/// The pallet name.
+		pallet: String,
+		/// The view function name.
+		name: String,
+	},
+	/// An associated type of a pallet's `Config` trait.
+	AssociatedType {
+		/// The pallet name.
+		pallet: String,
+		/// The associated type name.
+		name: String,
+	},
+	/// The version of a runtime API.
+	RuntimeApiVersion {
+		/// The runtime API trait name.
+		api: String,
+		/// The runtime API version.
+		version: u32,
+	},
+	/// Deprecation information of an item, given by its `::` separated path such as
+	/// `System::Account` for a storage entry or `Balances::Call[0]` for a call variant.
+	Deprecation(String),
+	/// A runtime API, with all of its methods.
+	RuntimeApi(String),
+	/// The outer call, event and error enum types.
+	OuterEnums,
+	/// A custom value.
+	CustomValue(String),
+	/// The documentation of a pallet.
+	PalletDocs(String),
+}
+
+impl core::fmt::Display for ConversionError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			ConversionError::TypeNotFound(id) => {
+				write!(f, "type with ID {id} not found in the type registry")
+			}
+			ConversionError::MissingExtrinsicTypeParam(name) => {
+				write!(f, "extrinsic type has no `{name}` type parameter")
+			}
+			ConversionError::UnsupportedExtrinsicVersion(version) => {
+				write!(
+					f,
+					"extrinsic version {version} is not supported by the metadata"
+				)
+			}
+			ConversionError::TransactionExtensionNotFound(index) => {
+				write!(f, "transaction extension with index {index} not found")
+			}
+			ConversionError::UnsupportedExtensionVersion(version) => {
+				write!(
+					f,
+					"transaction extension version {version} is not supported by the metadata"
+				)
+			}
+			ConversionError::UnsupportedVersion(version) => {
+				write!(f, "metadata version {version} cannot be converted")
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConversionError {}
+
+/// Find the type ID of the generic parameter `name` of the extrinsic type `extrinsic_ty`.
+///
+/// In V14 the extrinsic type is the runtime's `UncheckedExtrinsic<Address, Call, Signature, Extra>`,
+/// and the address, call, signature and extra types are only available as its type parameters.
+pub(crate) fn extrinsic_type_param(
+	types: &PortableRegistry,
+	extrinsic_ty: u32,
+	name: &'static str,
+) -> Result<u32, ConversionError> {
+	let ty = types
+		.resolve(extrinsic_ty)
+		.ok_or(ConversionError::TypeNotFound(extrinsic_ty))?;
+
+	ty.type_params
+		.iter()
+		.find(|param| AsRef::<str>::as_ref(&param.name) == name)
+		.and_then(|param| param.ty.as_ref())
+		.map(|ty| ty.id)
+		.ok_or(ConversionError::MissingExtrinsicTypeParam(name))
+}
+
+/// Convert a `T::String` of any form into an owned string.
+pub(crate) fn to_string(s: &impl AsRef<str>) -> String {
+	s.as_ref().into()
+}
+
+/// Convert a static string into a portable `T::String`, which is only an owned string with the
+/// `decode` feature.
+#[allow(clippy::useless_conversion)]
+pub(crate) fn portable_string(s: &'static str) -> <PortableForm as Form>::String {
+	s.into()
+}
+
+/// Append a new type to the registry, returning its ID.
+pub(crate) fn push_type(types: &mut PortableRegistry, ty: Type<PortableForm>) -> u32 {
+	let id = types.types.len() as u32;
+	types.types.push(PortableType::new(id, ty));
+	id
+}
+
+/// Find a type equal to `ty` in the registry, or append it if there is none.
+pub(crate) fn find_or_push_type(types: &mut PortableRegistry, ty: Type<PortableForm>) -> u32 {
+	match types.types.iter().find(|existing| existing.ty == ty) {
+		Some(existing) => existing.id,
+		None => push_type(types, ty),
+	}
+}
+
+/// Find an existing variant type whose variants have the same names, indexes and field types
+/// as `variants`, or append a new variant type with the given path if there is none.
+///
+/// This is used to recover the outer enums of a runtime, which are often already in the
+/// registry but which older metadata versions do not point to.
+pub(crate) fn find_or_push_variant_type(
+	types: &mut PortableRegistry,
+	path: Path<PortableForm>,
+	variants: Vec<Variant<PortableForm>>,
+) -> u32 {
+	let same_shape = |a: &Variant<PortableForm>, b: &Variant<PortableForm>| {
+		a.name == b.name
+			&& a.index == b.index
+			&& a.fields.len() == b.fields.len()
+			&& a.fields
+				.iter()
+				.zip(&b.fields)
+				.all(|(a, b)| a.ty.id == b.ty.id)
+	};
+
+	let existing = types.types.iter().find(|ty| match &ty.ty.type_def {
+		TypeDef::Variant(def) => {
+			def.variants.len() == variants.len()
+				&& def
+					.variants
+					.iter()
+					.zip(&variants)
+					.all(|(a, b)| same_shape(a, b))
+		}
+		_ => false,
+	});
+
+	match existing {
+		Some(ty) => ty.id,
+		None => push_type(
+			types,
+			Type::new(path, [], TypeDefVariant::new(variants), Vec::new()),
+		),
+	}
+}
@@ -0,0 +1,222 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{extrinsic_type_param, find_or_push_variant_type, portable_string, ConversionError};
+use crate::{v14, v15};
+use scale_info::{
+	form::PortableForm,
+	prelude::{collections::BTreeMap, vec, vec::Vec},
+	Field, Path, PortableRegistry, Variant,
+};
+
+impl TryFrom<v14::RuntimeMetadataV14> for v15::RuntimeMetadataV15 {
+	type Error = ConversionError;
+
+	/// Upgrade V14 metadata to V15.
+	///
+	/// The extrinsic address, call, signature and extra types are taken from the type
+	/// parameters of the V14 extrinsic type. The outer enums are derived from the pallet
+	/// call, event and error types, reusing matching enums already in the registry. Runtime
+	/// APIs, custom values and pallet documentation are not part of V14 and are left empty.
+	fn try_from(metadata: v14::RuntimeMetadataV14) -> Result<Self, Self::Error> {
+		let mut types = metadata.types;
+		let extrinsic_ty = metadata.extrinsic.ty.id;
+
+		let address_ty = extrinsic_type_param(&types, extrinsic_ty, "Address")?;
+		let call_ty = extrinsic_type_param(&types, extrinsic_ty, "Call")?;
+		let signature_ty = extrinsic_type_param(&types, extrinsic_ty, "Signature")?;
+		let extra_ty = extrinsic_type_param(&types, extrinsic_ty, "Extra")?;
+
+		let runtime_ty = metadata.ty.id;
+		let event_enum_ty = outer_enum(
+			&mut types,
+			runtime_ty,
+			"RuntimeEvent",
+			&metadata.pallets,
+			|pallet| pallet.event.as_ref().map(|event| event.ty.id),
+		)?;
+		let error_enum_ty = outer_enum(
+			&mut types,
+			runtime_ty,
+			"RuntimeError",
+			&metadata.pallets,
+			|pallet| pallet.error.as_ref().map(|error| error.ty.id),
+		)?;
+
+		let extrinsic = v15::ExtrinsicMetadata {
+			version: metadata.extrinsic.version,
+			address_ty: address_ty.into(),
+			call_ty: call_ty.into(),
+			signature_ty: signature_ty.into(),
+			extra_ty: extra_ty.into(),
+			signed_extensions: metadata
+				.extrinsic
+				.signed_extensions
+				.into_iter()
+				.map(|ext| v15::SignedExtensionMetadata {
+					identifier: ext.identifier,
+					ty: ext.ty,
+					additional_signed: ext.additional_signed,
+				})
+				.collect(),
+		};
+
+		let pallets = metadata
+			.pallets
+			.into_iter()
+			.map(|pallet| v15::PalletMetadata {
+				name: pallet.name,
+				storage: pallet.storage,
+				calls: pallet.calls,
+				event: pallet.event,
+				constants: pallet.constants,
+				error: pallet.error,
+				index: pallet.index,
+				docs: Vec::new(),
+			})
+			.collect();
+
+		Ok(v15::RuntimeMetadataV15 {
+			types,
+			pallets,
+			extrinsic,
+			ty: metadata.ty,
+			apis: Vec::new(),
+			outer_enums: v15::OuterEnums {
+				call_enum_ty: call_ty.into(),
+				event_enum_ty: event_enum_ty.into(),
+				error_enum_ty: error_enum_ty.into(),
+			},
+			custom: v15::CustomMetadata {
+				map: BTreeMap::new(),
+			},
+		})
+	}
+}
+
+/// Find or construct the outer enum `name` of the runtime, which has one variant per pallet
+/// wrapping the pallet type returned by `pallet_ty`.
+///
+/// The enum is placed next to the runtime type, e.g. `kusama_runtime::RuntimeEvent`.
+fn outer_enum(
+	types: &mut PortableRegistry,
+	runtime_ty: u32,
+	name: &'static str,
+	pallets: &[v14::PalletMetadata<PortableForm>],
+	pallet_ty: impl Fn(&v14::PalletMetadata<PortableForm>) -> Option<u32>,
+) -> Result<u32, ConversionError> {
+	let runtime = types
+		.resolve(runtime_ty)
+		.ok_or(ConversionError::TypeNotFound(runtime_ty))?;
+	let path = Path::from_segments_unchecked(
+		runtime
+			.path
+			.namespace()
+			.iter()
+			.cloned()
+			.chain([portable_string(name)]),
+	);
+
+	let variants = pallets
+		.iter()
+		.filter_map(|pallet| {
+			let ty = pallet_ty(pallet)?;
+			let field = Field {
+				name: None,
+				ty: ty.into(),
+				type_name: None,
+				docs: Vec::new(),
+			};
+			// Without the `decode` feature the portable string is a `&'static str`, where a
+			// `.clone()` would be a needless clone of a reference.
+			Some(Variant::new(
+				Clone::clone(&pallet.name),
+				vec![field],
+				pallet.index,
+				Vec::new(),
+			))
+		})
+		.collect();
+
+	Ok(find_or_push_variant_type(types, path, variants))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test::kusama_v14;
+	use scale_info::TypeDef;
+
+	#[test]
+	fn should_take_extrinsic_types_from_type_params() {
+		let v14 = kusama_v14();
+		let v15 = v15::RuntimeMetadataV15::try_from(v14.clone()).unwrap();
+
+		let call = v15.types.resolve(v15.extrinsic.call_ty.id).unwrap();
+		assert_eq!(call.path.ident().as_deref(), Some("Call"));
+		assert_eq!(v15.outer_enums.call_enum_ty.id, v15.extrinsic.call_ty.id);
+
+		let extra = v15.types.resolve(v15.extrinsic.extra_ty.id).unwrap();
+		let TypeDef::Tuple(extra) = &extra.type_def else {
+			panic!("extra type should be a tuple")
+		};
+		let extension_tys: Vec<_> = v14
+			.extrinsic
+			.signed_extensions
+			.iter()
+			.map(|ext| ext.ty)
+			.collect();
+		assert_eq!(extra.fields, extension_tys);
+	}
+
+	#[test]
+	fn should_derive_outer_enums_from_pallets() {
+		let v14 = kusama_v14();
+		let v15 = v15::RuntimeMetadataV15::try_from(v14.clone()).unwrap();
+
+		// The event enum is already in the registry and reused; the error enum is new.
+		assert!((v15.outer_enums.event_enum_ty.id as usize) < v14.types.types.len());
+		assert_eq!(v15.types.types.len(), v14.types.types.len() + 1);
+
+		let error_enum = v15.types.resolve(v15.outer_enums.error_enum_ty.id).unwrap();
+		assert_eq!(error_enum.path.segments, ["kusama_runtime", "RuntimeError"]);
+		let TypeDef::Variant(error_enum) = &error_enum.type_def else {
+			panic!("error enum should be a variant type")
+		};
+		let pallets_with_errors = v14.pallets.iter().filter(|p| p.error.is_some());
+		assert_eq!(
+			error_enum.variants.len(),
+			pallets_with_errors.clone().count()
+		);
+		for (variant, pallet) in error_enum.variants.iter().zip(pallets_with_errors) {
+			assert_eq!(variant.name, pallet.name);
+			assert_eq!(variant.index, pallet.index);
+			assert_eq!(variant.fields[0].ty, pallet.error.as_ref().unwrap().ty);
+		}
+
+		assert!(v15.apis.is_empty());
+		assert!(v15.custom.map.is_empty());
+	}
+
+	#[test]
+	fn should_fail_without_extrinsic_type_params() {
+		let mut v14 = kusama_v14();
+		v14.extrinsic.ty = v14.ty;
+		assert_eq!(
+			v15::RuntimeMetadataV15::try_from(v14),
+			Err(ConversionError::MissingExtrinsicTypeParam("Address"))
+		);
+	}
+}
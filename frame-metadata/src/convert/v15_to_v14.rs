@@ -0,0 +1,211 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{
+	find_or_push_type, portable_string, push_type, to_string, ConversionError, Downgraded,
+	LostInformation,
+};
+use crate::{v14, v15};
+use scale_info::{
+	form::PortableForm,
+	prelude::{vec, vec::Vec},
+	Field, Path, PortableRegistry, Type, TypeDefComposite, TypeDefPrimitive, TypeDefSequence,
+	TypeParameter,
+};
+
+/// Downgrade V15 metadata to V14, reporting everything that V14 cannot represent.
+///
+/// V14 describes the extrinsic as an `UncheckedExtrinsic<Address, Call, Signature, Extra>`
+/// type. A matching type is looked up in the registry, and added if not found. Runtime APIs,
+/// outer enums, custom values and pallet documentation are dropped.
+pub fn downgrade_v15_to_v14(
+	metadata: v15::RuntimeMetadataV15,
+) -> Result<Downgraded<v14::RuntimeMetadataV14>, ConversionError> {
+	let mut types = metadata.types;
+	let mut lost = Vec::new();
+
+	let extrinsic = metadata.extrinsic;
+	let extrinsic_ty = unchecked_extrinsic_type(
+		&mut types,
+		[
+			("Address", extrinsic.address_ty.id),
+			("Call", extrinsic.call_ty.id),
+			("Signature", extrinsic.signature_ty.id),
+			("Extra", extrinsic.extra_ty.id),
+		],
+	);
+	let extrinsic = v14::ExtrinsicMetadata {
+		ty: extrinsic_ty.into(),
+		version: extrinsic.version,
+		signed_extensions: extrinsic
+			.signed_extensions
+			.into_iter()
+			.map(|ext| v14::SignedExtensionMetadata {
+				identifier: ext.identifier,
+				ty: ext.ty,
+				additional_signed: ext.additional_signed,
+			})
+			.collect(),
+	};
+
+	let pallets = metadata
+		.pallets
+		.into_iter()
+		.map(|pallet| {
+			if !pallet.docs.is_empty() {
+				lost.push(LostInformation::PalletDocs(to_string(&pallet.name)));
+			}
+			v14::PalletMetadata {
+				name: pallet.name,
+				storage: pallet.storage,
+				calls: pallet.calls,
+				event: pallet.event,
+				constants: pallet.constants,
+				error: pallet.error,
+				index: pallet.index,
+			}
+		})
+		.collect();
+
+	lost.extend(
+		metadata
+			.apis
+			.iter()
+			.map(|api| LostInformation::RuntimeApi(to_string(&api.name))),
+	);
+	lost.push(LostInformation::OuterEnums);
+	lost.extend(
+		metadata
+			.custom
+			.map
+			.keys()
+			.map(|key| LostInformation::CustomValue(to_string(key))),
+	);
+
+	let metadata = v14::RuntimeMetadataV14 {
+		types,
+		pallets,
+		extrinsic,
+		ty: metadata.ty,
+	};
+	Ok(Downgraded { metadata, lost })
+}
+
+impl TryFrom<v15::RuntimeMetadataV15> for v14::RuntimeMetadataV14 {
+	type Error = ConversionError;
+
+	/// Downgrade V15 metadata to V14. See [`downgrade_v15_to_v14`] to also find out
+	/// which information was dropped.
+	fn try_from(metadata: v15::RuntimeMetadataV15) -> Result<Self, Self::Error> {
+		downgrade_v15_to_v14(metadata).map(|downgraded| downgraded.metadata)
+	}
+}
+
+/// Find the `UncheckedExtrinsic` type with the given type parameters, adding it if there
+/// is no such type.
+pub(super) fn unchecked_extrinsic_type(
+	types: &mut PortableRegistry,
+	params: [(&'static str, u32); 4],
+) -> u32 {
+	let type_params: Vec<TypeParameter<PortableForm>> = params
+		.iter()
+		.map(|(name, id)| TypeParameter::new_portable(portable_string(name), Some((*id).into())))
+		.collect();
+
+	let existing = types.types.iter().find(|ty| {
+		ty.ty.path.ident().as_ref().map(AsRef::as_ref) == Some("UncheckedExtrinsic")
+			&& ty.ty.type_params == type_params
+	});
+	if let Some(existing) = existing {
+		return existing.id;
+	}
+
+	// The runtime encodes the extrinsic as SCALE encoded bytes, so that is how it is described.
+	let u8_ty = find_or_push_type(
+		types,
+		Type::new(Path::default(), [], TypeDefPrimitive::U8, Vec::new()),
+	);
+	let bytes_ty = find_or_push_type(
+		types,
+		Type::new(
+			Path::default(),
+			[],
+			TypeDefSequence::new(u8_ty.into()),
+			Vec::new(),
+		),
+	);
+	let field = Field {
+		name: None,
+		ty: bytes_ty.into(),
+		type_name: None,
+		docs: Vec::new(),
+	};
+	let path = Path::from_segments_unchecked(
+		[
+			"sp_runtime",
+			"generic",
+			"unchecked_extrinsic",
+			"UncheckedExtrinsic",
+		]
+		.map(Into::into),
+	);
+	push_type(
+		types,
+		Type::new(
+			path,
+			type_params,
+			TypeDefComposite::new(vec![field]),
+			Vec::new(),
+		),
+	)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test::{kusama_v14, kusama_v15};
+
+	#[test]
+	fn should_reuse_unchecked_extrinsic_type() {
+		let v14 = kusama_v14();
+		let v15 = v15::RuntimeMetadataV15::try_from(v14.clone()).unwrap();
+		let downgraded = downgrade_v15_to_v14(v15).unwrap();
+		assert_eq!(downgraded.metadata.extrinsic, v14.extrinsic);
+		assert_eq!(downgraded.metadata.pallets, v14.pallets);
+	}
+
+	#[test]
+	fn should_add_unchecked_extrinsic_type() {
+		let mut v15 = kusama_v15();
+		// Point the extrinsic at types which no `UncheckedExtrinsic` is defined with.
+		v15.extrinsic.address_ty = v15.extrinsic.call_ty;
+		v15.pallets[0].docs = vec!["docs".into()];
+		let types_len = v15.types.types.len() as u32;
+
+		let downgraded = downgrade_v15_to_v14(v15.clone()).unwrap();
+		let v14 = downgraded.metadata;
+		assert_eq!(v14.extrinsic.ty.id, types_len);
+		let address_ty = crate::convert::extrinsic_type_param(&v14.types, types_len, "Address");
+		assert_eq!(address_ty, Ok(v15.extrinsic.call_ty.id));
+
+		assert_eq!(
+			downgraded.lost,
+			[
+				LostInformation::PalletDocs(v15.pallets[0].name.clone()),
+				LostInformation::OuterEnums
+			]
+		);
+	}
+}
@@ -0,0 +1,207 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{metadata::V4_EXTENSION_VERSION, v15, v16};
+use codec::Compact;
+use scale_info::{
+	form::PortableForm,
+	prelude::{collections::BTreeMap, vec, vec::Vec},
+};
+
+impl From<v15::RuntimeMetadataV15> for v16::RuntimeMetadataV16 {
+	/// Upgrade V15 metadata to V16.
+	///
+	/// The signed extensions become the transaction extensions of extension version `0`, which
+	/// signed extrinsics of the single version described by V15 use, and nothing is marked as deprecated. V16 has no pallet
+	/// associated types or view functions to take from V15, and V15 does not record runtime
+	/// API versions, so these are left empty and `0` respectively. The `Runtime` type is
+	/// not part of V16 and is dropped.
+	fn from(metadata: v15::RuntimeMetadataV15) -> Self {
+		let version = metadata.extrinsic.version;
+		let extension_indexes = (0..metadata.extrinsic.signed_extensions.len() as u32)
+			.map(Compact)
+			.collect();
+
+		let extrinsic = v16::ExtrinsicMetadata {
+			versions: vec![version],
+			address_ty: metadata.extrinsic.address_ty,
+			call_ty: metadata.extrinsic.call_ty,
+			signature_ty: metadata.extrinsic.signature_ty,
+			transaction_extensions_by_version: BTreeMap::from([(
+				V4_EXTENSION_VERSION,
+				extension_indexes,
+			)]),
+			transaction_extensions: metadata
+				.extrinsic
+				.signed_extensions
+				.into_iter()
+				.map(|ext| v16::TransactionExtensionMetadata {
+					identifier: ext.identifier,
+					ty: ext.ty,
+					implicit: ext.additional_signed,
+				})
+				.collect(),
+		};
+
+		let pallets = metadata.pallets.into_iter().map(pallet_from_v15).collect();
+
+		let apis = metadata
+			.apis
+			.into_iter()
+			.map(|api| v16::RuntimeApiMetadata {
+				name: api.name,
+				methods: api
+					.methods
+					.into_iter()
+					.map(|method| v16::RuntimeApiMethodMetadata {
+						name: method.name,
+						inputs: method.inputs,
+						output: method.output,
+						docs: method.docs,
+						deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+					})
+					.collect(),
+				docs: api.docs,
+				version: Compact(0),
+				deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+			})
+			.collect();
+
+		v16::RuntimeMetadataV16 {
+			types: metadata.types,
+			pallets,
+			extrinsic,
+			apis,
+			outer_enums: metadata.outer_enums,
+			custom: metadata.custom,
+		}
+	}
+}
+
+fn pallet_from_v15(pallet: v15::PalletMetadata<PortableForm>) -> v16::PalletMetadata<PortableForm> {
+	let storage = pallet.storage.map(|storage| v16::PalletStorageMetadata {
+		prefix: storage.prefix,
+		entries: storage
+			.entries
+			.into_iter()
+			.map(|entry| v16::StorageEntryMetadata {
+				name: entry.name,
+				modifier: entry.modifier,
+				ty: entry.ty,
+				default: entry.default,
+				docs: entry.docs,
+				deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+			})
+			.collect(),
+	});
+
+	let constants = pallet
+		.constants
+		.into_iter()
+		.map(|constant| v16::PalletConstantMetadata {
+			name: constant.name,
+			ty: constant.ty,
+			value: constant.value,
+			docs: constant.docs,
+			deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+		})
+		.collect();
+
+	v16::PalletMetadata {
+		name: pallet.name,
+		storage,
+		calls: pallet.calls.map(|calls| v16::PalletCallMetadata {
+			ty: calls.ty,
+			deprecation_info: v16::EnumDeprecationInfo::nothing_deprecated(),
+		}),
+		event: pallet.event.map(|event| v16::PalletEventMetadata {
+			ty: event.ty,
+			deprecation_info: v16::EnumDeprecationInfo::nothing_deprecated(),
+		}),
+		constants,
+		error: pallet.error.map(|error| v16::PalletErrorMetadata {
+			ty: error.ty,
+			deprecation_info: v16::EnumDeprecationInfo::nothing_deprecated(),
+		}),
+		associated_types: Vec::new(),
+		view_functions: Vec::new(),
+		index: pallet.index,
+		docs: pallet.docs,
+		deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		test::{kusama_v14, kusama_v16},
+		RuntimeMetadata, RuntimeMetadataPrefixed,
+	};
+	use codec::{Decode, Encode};
+
+	#[test]
+	fn should_map_signed_extensions_to_extension_version_0() {
+		let v14 = kusama_v14();
+		let v16 = kusama_v16();
+
+		assert_eq!(v16.extrinsic.versions, [4]);
+		let indexes = &v16.extrinsic.transaction_extensions_by_version[&0];
+		assert_eq!(indexes.len(), v14.extrinsic.signed_extensions.len());
+		for (i, index) in indexes.iter().enumerate() {
+			assert_eq!(index.0 as usize, i);
+		}
+
+		for (ext, v14_ext) in v16
+			.extrinsic
+			.transaction_extensions
+			.iter()
+			.zip(&v14.extrinsic.signed_extensions)
+		{
+			assert_eq!(ext.identifier, v14_ext.identifier);
+			assert_eq!(ext.ty, v14_ext.ty);
+			assert_eq!(ext.implicit, v14_ext.additional_signed);
+		}
+	}
+
+	#[test]
+	fn should_not_mark_anything_deprecated() {
+		for pallet in kusama_v16().pallets {
+			assert_eq!(
+				pallet.deprecation_info,
+				v16::ItemDeprecationInfo::NotDeprecated
+			);
+			if let Some(calls) = &pallet.calls {
+				assert!(!calls.deprecation_info.has_deprecated_variants());
+			}
+			for entry in pallet.storage.iter().flat_map(|s| &s.entries) {
+				assert_eq!(
+					entry.deprecation_info,
+					v16::ItemDeprecationInfo::NotDeprecated
+				);
+			}
+			assert!(pallet.view_functions.is_empty());
+			assert!(pallet.associated_types.is_empty());
+		}
+	}
+
+	#[test]
+	fn should_encode_and_decode_as_v16() {
+		let v16 = kusama_v16();
+		let encoded = RuntimeMetadataPrefixed::from(v16.clone()).encode();
+		let decoded = RuntimeMetadataPrefixed::decode(&mut &encoded[..]).unwrap();
+		assert_eq!(decoded.1, RuntimeMetadata::V16(v16));
+	}
+}
@@ -0,0 +1,437 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{
+	downgrade_v15_to_v14, find_or_push_type, portable_string, push_type, to_string,
+	ConversionError, Downgraded, LostInformation,
+};
+use crate::{metadata::V4_EXTENSION_VERSION, v14, v15, v16};
+use scale_info::{
+	form::PortableForm,
+	prelude::{format, vec::Vec},
+	Path, PortableRegistry, Type, TypeDefComposite, TypeDefTuple,
+};
+
+/// The only extrinsic version that V14 and V15 metadata can describe.
+const EXTRINSIC_VERSION: u8 = 4;
+
+/// Downgrade V16 metadata to V15, reporting everything that V15 cannot represent.
+///
+/// The signed extensions are the transaction extensions used by version 4 extrinsics, and
+/// the extra type is a tuple of their types, added to the registry if not already present.
+/// V16 does not describe the `Runtime` type, so it is looked up next to the call type and
+/// added as an empty struct if not found.
+///
+/// Fails if the metadata does not support version 4 extrinsics.
+pub fn downgrade_v16_to_v15(
+	metadata: v16::RuntimeMetadataV16,
+) -> Result<Downgraded<v15::RuntimeMetadataV15>, ConversionError> {
+	let mut types = metadata.types;
+	let mut lost = Vec::new();
+
+	let extrinsic = extrinsic_from_v16(&mut types, metadata.extrinsic, &mut lost)?;
+	let ty = runtime_type(&mut types, extrinsic.call_ty.id)?;
+
+	let pallets = metadata
+		.pallets
+		.into_iter()
+		.map(|pallet| pallet_from_v16(pallet, &mut lost))
+		.collect();
+
+	let apis = metadata
+		.apis
+		.into_iter()
+		.map(|api| {
+			let api_name = to_string(&api.name);
+			lost.push(LostInformation::RuntimeApiVersion {
+				api: api_name.clone(),
+				version: api.version.0,
+			});
+			if api.deprecation_info != v16::ItemDeprecationInfo::NotDeprecated {
+				lost.push(LostInformation::Deprecation(api_name.clone()));
+			}
+
+			let methods = api
+				.methods
+				.into_iter()
+				.map(|method| {
+					if method.deprecation_info != v16::ItemDeprecationInfo::NotDeprecated {
+						let path = format!("{api_name}::{}", to_string(&method.name));
+						lost.push(LostInformation::Deprecation(path));
+					}
+					v15::RuntimeApiMethodMetadata {
+						name: method.name,
+						inputs: method.inputs,
+						output: method.output,
+						docs: method.docs,
+					}
+				})
+				.collect();
+
+			v15::RuntimeApiMetadata {
+				name: api.name,
+				methods,
+				docs: api.docs,
+			}
+		})
+		.collect();
+
+	let metadata = v15::RuntimeMetadataV15 {
+		types,
+		pallets,
+		extrinsic,
+		ty: ty.into(),
+		apis,
+		outer_enums: metadata.outer_enums,
+		custom: metadata.custom,
+	};
+	Ok(Downgraded { metadata, lost })
+}
+
+/// Downgrade V16 metadata to V14, reporting everything that V14 cannot represent.
+///
+/// This is [`downgrade_v16_to_v15`] followed by [`downgrade_v15_to_v14`].
+pub fn downgrade_v16_to_v14(
+	metadata: v16::RuntimeMetadataV16,
+) -> Result<Downgraded<v14::RuntimeMetadataV14>, ConversionError> {
+	let v15 = downgrade_v16_to_v15(metadata)?;
+	let mut v14 = downgrade_v15_to_v14(v15.metadata)?;
+	v14.lost.splice(0..0, v15.lost);
+	Ok(v14)
+}
+
+impl TryFrom<v16::RuntimeMetadataV16> for v15::RuntimeMetadataV15 {
+	type Error = ConversionError;
+
+	/// Downgrade V16 metadata to V15. See [`downgrade_v16_to_v15`] to also find out
+	/// which information was dropped.
+	fn try_from(metadata: v16::RuntimeMetadataV16) -> Result<Self, Self::Error> {
+		downgrade_v16_to_v15(metadata).map(|downgraded| downgraded.metadata)
+	}
+}
+
+impl TryFrom<v16::RuntimeMetadataV16> for v14::RuntimeMetadataV14 {
+	type Error = ConversionError;
+
+	/// Downgrade V16 metadata to V14. See [`downgrade_v16_to_v14`] to also find out
+	/// which information was dropped.
+	fn try_from(metadata: v16::RuntimeMetadataV16) -> Result<Self, Self::Error> {
+		downgrade_v16_to_v14(metadata).map(|downgraded| downgraded.metadata)
+	}
+}
+
+fn extrinsic_from_v16(
+	types: &mut PortableRegistry,
+	extrinsic: v16::ExtrinsicMetadata<PortableForm>,
+	lost: &mut Vec<LostInformation>,
+) -> Result<v15::ExtrinsicMetadata<PortableForm>, ConversionError> {
+	if !extrinsic.versions.contains(&EXTRINSIC_VERSION) {
+		return Err(ConversionError::UnsupportedExtrinsicVersion(
+			EXTRINSIC_VERSION,
+		));
+	}
+	lost.extend(
+		extrinsic
+			.versions
+			.iter()
+			.filter(|version| **version != EXTRINSIC_VERSION)
+			.map(|version| LostInformation::ExtrinsicVersion(*version)),
+	);
+
+	let indexes: Vec<u32> = extrinsic
+		.transaction_extensions_by_version
+		.get(&V4_EXTENSION_VERSION)
+		.ok_or(ConversionError::UnsupportedExtensionVersion(
+			V4_EXTENSION_VERSION,
+		))?
+		.iter()
+		.map(|index| index.0)
+		.collect();
+
+	let mut signed_extensions = Vec::with_capacity(indexes.len());
+	for index in &indexes {
+		let ext = extrinsic
+			.transaction_extensions
+			.get(*index as usize)
+			.ok_or(ConversionError::TransactionExtensionNotFound(*index))?;
+		signed_extensions.push(v15::SignedExtensionMetadata {
+			identifier: Clone::clone(&ext.identifier),
+			ty: ext.ty,
+			additional_signed: ext.implicit,
+		});
+	}
+	lost.extend(
+		extrinsic
+			.transaction_extensions
+			.iter()
+			.enumerate()
+			.filter(|(index, _)| !indexes.contains(&(*index as u32)))
+			.map(|(_, ext)| LostInformation::TransactionExtension(to_string(&ext.identifier))),
+	);
+
+	let extra = Type::new(
+		Path::default(),
+		[],
+		TypeDefTuple::new_portable(signed_extensions.iter().map(|ext| ext.ty)),
+		Vec::new(),
+	);
+	let extra_ty = find_or_push_type(types, extra);
+
+	Ok(v15::ExtrinsicMetadata {
+		version: EXTRINSIC_VERSION,
+		address_ty: extrinsic.address_ty,
+		call_ty: extrinsic.call_ty,
+		signature_ty: extrinsic.signature_ty,
+		extra_ty: extra_ty.into(),
+		signed_extensions,
+	})
+}
+
+/// Find the `Runtime` type next to the outer call type, e.g. `kusama_runtime::Runtime`,
+/// adding it as an empty struct if there is no such type.
+fn runtime_type(types: &mut PortableRegistry, call_ty: u32) -> Result<u32, ConversionError> {
+	let call = types
+		.resolve(call_ty)
+		.ok_or(ConversionError::TypeNotFound(call_ty))?;
+	let path = Path::from_segments_unchecked(
+		call.path
+			.namespace()
+			.iter()
+			.cloned()
+			.chain([portable_string("Runtime")]),
+	);
+
+	match types.types.iter().find(|ty| ty.ty.path == path) {
+		Some(ty) => Ok(ty.id),
+		None => Ok(push_type(
+			types,
+			Type::new(path, [], TypeDefComposite::new([]), Vec::new()),
+		)),
+	}
+}
+
+fn pallet_from_v16(
+	pallet: v16::PalletMetadata<PortableForm>,
+	lost: &mut Vec<LostInformation>,
+) -> v15::PalletMetadata<PortableForm> {
+	let pallet_name = to_string(&pallet.name);
+	let mut deprecated = |path: &str, deprecation_info: &v16::ItemDeprecationInfo<PortableForm>| {
+		if *deprecation_info != v16::ItemDeprecationInfo::NotDeprecated {
+			lost.push(LostInformation::Deprecation(path.into()));
+		}
+	};
+	deprecated(&pallet_name, &pallet.deprecation_info);
+
+	let storage = pallet.storage.map(|storage| v15::PalletStorageMetadata {
+		prefix: storage.prefix,
+		entries: storage
+			.entries
+			.into_iter()
+			.map(|entry| {
+				let path = format!("{pallet_name}::{}", to_string(&entry.name));
+				deprecated(&path, &entry.deprecation_info);
+				v15::StorageEntryMetadata {
+					name: entry.name,
+					modifier: entry.modifier,
+					ty: entry.ty,
+					default: entry.default,
+					docs: entry.docs,
+				}
+			})
+			.collect(),
+	});
+
+	let constants = pallet
+		.constants
+		.into_iter()
+		.map(|constant| {
+			let path = format!("{pallet_name}::{}", to_string(&constant.name));
+			deprecated(&path, &constant.deprecation_info);
+			v15::PalletConstantMetadata {
+				name: constant.name,
+				ty: constant.ty,
+				value: constant.value,
+				docs: constant.docs,
+			}
+		})
+		.collect();
+
+	let mut deprecated_variants =
+		|kind: &str, deprecation_info: &v16::EnumDeprecationInfo<PortableForm>| {
+			lost.extend(deprecation_info.0.keys().map(|index| {
+				LostInformation::Deprecation(format!("{pallet_name}::{kind}[{index}]"))
+			}));
+		};
+	let calls = pallet.calls.map(|calls| {
+		deprecated_variants("Call", &calls.deprecation_info);
+		v15::PalletCallMetadata { ty: calls.ty }
+	});
+	let event = pallet.event.map(|event| {
+		deprecated_variants("Event", &event.deprecation_info);
+		v15::PalletEventMetadata { ty: event.ty }
+	});
+	let error = pallet.error.map(|error| {
+		deprecated_variants("Error", &error.deprecation_info);
+		v15::PalletErrorMetadata { ty: error.ty }
+	});
+
+	lost.extend(pallet.associated_types.iter().map(|associated_type| {
+		LostInformation::AssociatedType {
+			pallet: pallet_name.clone(),
+			name: to_string(&associated_type.name),
+		}
+	}));
+	lost.extend(
+		pallet
+			.view_functions
+			.iter()
+			.map(|view_function| LostInformation::ViewFunction {
+				pallet: pallet_name.clone(),
+				name: to_string(&view_function.name),
+			}),
+	);
+
+	v15::PalletMetadata {
+		name: pallet.name,
+		storage,
+		calls,
+		event,
+		constants,
+		error,
+		index: pallet.index,
+		docs: pallet.docs,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test::{kusama_v14, kusama_v15, kusama_v16};
+	use codec::Compact;
+	use scale_info::{prelude::collections::BTreeMap, TypeDef};
+
+	#[test]
+	fn should_round_trip_through_v16() {
+		let v15 = kusama_v15();
+		let downgraded = downgrade_v16_to_v15(v15.clone().into()).unwrap();
+		assert_eq!(downgraded.metadata, v15);
+		assert!(downgraded.lost.is_empty());
+	}
+
+	#[test]
+	fn should_pick_version_4_extensions() {
+		let mut v16 = kusama_v16();
+		let all_extensions = v16.extrinsic.transaction_extensions.len() as u32;
+		v16.extrinsic.versions.push(5);
+		v16.extrinsic
+			.transaction_extensions_by_version
+			.insert(0, vec![Compact(2), Compact(0)]);
+		v16.pallets[0]
+			.view_functions
+			.push(v16::PalletViewFunctionMetadata {
+				id: [0; 32],
+				name: "view".into(),
+				inputs: Vec::new(),
+				output: 0.into(),
+				docs: Vec::new(),
+				deprecation_info: v16::ItemDeprecationInfo::DeprecatedWithoutNote,
+			});
+		let types_len = v16.types.types.len();
+
+		let downgraded = downgrade_v16_to_v15(v16.clone()).unwrap();
+		let extrinsic = &downgraded.metadata.extrinsic;
+		let identifiers: Vec<_> = extrinsic
+			.signed_extensions
+			.iter()
+			.map(|ext| ext.identifier.as_str())
+			.collect();
+		assert_eq!(
+			identifiers,
+			[
+				v16.extrinsic.transaction_extensions[2].identifier.as_str(),
+				v16.extrinsic.transaction_extensions[0].identifier.as_str(),
+			]
+		);
+
+		// The extra type is a new tuple of the two extension types.
+		assert_eq!(extrinsic.extra_ty.id as usize, types_len);
+		let extra = downgraded
+			.metadata
+			.types
+			.resolve(extrinsic.extra_ty.id)
+			.unwrap();
+		let TypeDef::Tuple(extra) = &extra.type_def else {
+			panic!("extra type should be a tuple")
+		};
+		assert_eq!(
+			extra.fields,
+			[
+				extrinsic.signed_extensions[0].ty,
+				extrinsic.signed_extensions[1].ty
+			]
+		);
+
+		assert!(downgraded
+			.lost
+			.contains(&LostInformation::ExtrinsicVersion(5)));
+		let dropped_extensions = downgraded
+			.lost
+			.iter()
+			.filter(|lost| matches!(lost, LostInformation::TransactionExtension(_)))
+			.count();
+		assert_eq!(dropped_extensions as u32, all_extensions - 2);
+		let pallet = v16.pallets[0].name.clone();
+		assert!(downgraded.lost.contains(&LostInformation::ViewFunction {
+			pallet,
+			name: "view".into()
+		}));
+	}
+
+	#[test]
+	fn should_fail_without_version_4() {
+		let mut v16 = kusama_v16();
+		v16.extrinsic.versions = vec![5];
+		v16.extrinsic.transaction_extensions_by_version = BTreeMap::new();
+		assert_eq!(
+			downgrade_v16_to_v15(v16),
+			Err(ConversionError::UnsupportedExtrinsicVersion(4))
+		);
+	}
+
+	#[test]
+	fn should_fail_without_extension_version_0() {
+		let mut v16 = kusama_v16();
+		let indexes = v16.extrinsic.transaction_extensions_by_version.remove(&0);
+		v16.extrinsic
+			.transaction_extensions_by_version
+			.insert(1, indexes.unwrap());
+		assert_eq!(
+			downgrade_v16_to_v15(v16),
+			Err(ConversionError::UnsupportedExtensionVersion(0))
+		);
+	}
+
+	#[test]
+	fn should_downgrade_to_v14() {
+		let v14 = kusama_v14();
+		let v16 = kusama_v16();
+
+		let downgraded = downgrade_v16_to_v14(v16).unwrap();
+		assert_eq!(downgraded.metadata.pallets, v14.pallets);
+		assert_eq!(downgraded.metadata.extrinsic, v14.extrinsic);
+		assert_eq!(downgraded.metadata.ty, v14.ty);
+		assert_eq!(downgraded.lost, [LostInformation::OuterEnums]);
+	}
+}
@@ -124,6 +124,20 @@ where
 	}
 }
 
+#[cfg(feature = "std")]
+impl<'de, B, O> serde::Deserialize<'de> for DecodeDifferent<B, O>
+where
+	B: 'static,
+	O: serde::Deserialize<'de> + 'static,
+{
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		O::deserialize(deserializer).map(DecodeDifferent::Decoded)
+	}
+}
+
 /// An array type that decodes as a `Vec`.
 pub type DecodeDifferentArray<B, O = B> = DecodeDifferent<&'static [B], Vec<O>>;
 
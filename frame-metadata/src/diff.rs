@@ -0,0 +1,681 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structural differences between two versions of the metadata of a runtime.
+//!
+//! Items are matched by name, and their types are compared by structure rather than by type
+//! ID, so metadata with differently numbered type registries can be compared. Documentation
+//! is ignored. See [`diff`](crate::diff::diff).
+
+use crate::{
+	metadata::{Metadata, Pallet, RuntimeApiMethod, StorageEntry, TransactionExtension},
+	v14,
+};
+use scale_info::{
+	form::PortableForm,
+	prelude::{
+		collections::{BTreeMap, BTreeSet},
+		string::String,
+		vec::Vec,
+	},
+	Field, PortableRegistry, TypeDef, Variant,
+};
+
+/// The differences between two versions of the metadata.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MetadataDiff {
+	/// The changed pallets.
+	pub pallets: Vec<Change<PalletDiff>>,
+	/// The changed runtime APIs.
+	pub runtime_apis: Vec<Change<RuntimeApiDiff>>,
+	/// The changed transaction extensions.
+	pub transaction_extensions: Vec<Change<TransactionExtensionDiff>>,
+}
+
+impl MetadataDiff {
+	/// Whether the two versions of the metadata are the same.
+	pub fn is_empty(&self) -> bool {
+		self.pallets.is_empty()
+			&& self.runtime_apis.is_empty()
+			&& self.transaction_extensions.is_empty()
+	}
+}
+
+/// A named item that was added, removed or changed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Change<D> {
+	/// The item only exists in the new metadata.
+	Added(String),
+	/// The item only exists in the old metadata.
+	Removed(String),
+	/// The item exists in both, with the given differences.
+	Changed(String, D),
+}
+
+impl<D> Change<D> {
+	/// The name of the item.
+	pub fn name(&self) -> &str {
+		match self {
+			Change::Added(name) | Change::Removed(name) | Change::Changed(name, _) => name,
+		}
+	}
+}
+
+/// An old and a new value.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Modified<T> {
+	/// The value in the old metadata.
+	pub old: T,
+	/// The value in the new metadata.
+	pub new: T,
+}
+
+/// How a type differs between the old and the new metadata.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TypeChange {
+	/// The type is encoded the same way, but the name of the type or of some of its fields or
+	/// variants changed.
+	Renamed,
+	/// The type is encoded differently.
+	Encoding,
+}
+
+/// The differences of a pallet.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PalletDiff {
+	/// The changed pallet index.
+	pub index: Option<Modified<u8>>,
+	/// The changed prefix of the storage entries, if the pallet has storage in both versions.
+	pub storage_prefix: Option<Modified<String>>,
+	/// The changed calls.
+	pub calls: Vec<Change<VariantDiff>>,
+	/// The changed events.
+	pub events: Vec<Change<VariantDiff>>,
+	/// The changed errors.
+	pub errors: Vec<Change<VariantDiff>>,
+	/// The changed storage entries.
+	pub storage: Vec<Change<StorageEntryDiff>>,
+	/// The changed constants.
+	pub constants: Vec<Change<ConstantDiff>>,
+}
+
+/// The differences of a call, event or error variant.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VariantDiff {
+	/// The changed variant index.
+	pub index: Option<Modified<u8>>,
+	/// How the fields of the variant changed.
+	pub fields: Option<TypeChange>,
+}
+
+/// The differences of a storage entry.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StorageEntryDiff {
+	/// The changed modifier.
+	pub modifier: Option<Modified<v14::StorageEntryModifier>>,
+	/// The changed key hashers, which are empty for plain entries.
+	pub hashers: Option<Modified<Vec<v14::StorageHasher>>>,
+	/// How the key type changed, including an entry becoming a map or a plain value.
+	pub key: Option<TypeChange>,
+	/// How the value type changed.
+	pub value: Option<TypeChange>,
+	/// The changed SCALE encoded default value.
+	pub default: Option<Modified<Vec<u8>>>,
+}
+
+/// The differences of a pallet constant.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ConstantDiff {
+	/// How the type changed.
+	pub ty: Option<TypeChange>,
+	/// The changed SCALE encoded value.
+	pub value: Option<Modified<Vec<u8>>>,
+}
+
+/// The differences of a runtime API.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RuntimeApiDiff {
+	/// The changed methods.
+	pub methods: Vec<Change<RuntimeApiMethodDiff>>,
+}
+
+/// The differences of a runtime API method.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RuntimeApiMethodDiff {
+	/// How the parameters changed, in number, name or type.
+	pub inputs: Option<TypeChange>,
+	/// How the output type changed.
+	pub output: Option<TypeChange>,
+}
+
+/// The differences of a transaction extension.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TransactionExtensionDiff {
+	/// The changed position among the transaction extensions.
+	pub position: Option<Modified<usize>>,
+	/// How the type of the data included in the extrinsic changed.
+	pub ty: Option<TypeChange>,
+	/// How the type of the implicit data changed.
+	pub implicit: Option<TypeChange>,
+}
+
+/// Compute the differences between two versions of the metadata.
+pub fn diff(old: &Metadata, new: &Metadata) -> MetadataDiff {
+	let mut types = TypeComparer::new(old.types(), new.types());
+
+	let pallets = diff_items(
+		old.pallets(),
+		new.pallets(),
+		|pallet| pallet.name(),
+		|old, new| diff_pallet(&mut types, old, new),
+	);
+
+	let runtime_apis = diff_items(
+		old.runtime_apis(),
+		new.runtime_apis(),
+		|api| api.name(),
+		|old, new| {
+			let methods = diff_items(
+				old.methods(),
+				new.methods(),
+				|method| method.name,
+				|old, new| diff_runtime_api_method(&mut types, old, new),
+			);
+			(!methods.is_empty()).then_some(RuntimeApiDiff { methods })
+		},
+	);
+
+	let old_extensions: Vec<_> = old
+		.extrinsic()
+		.transaction_extensions()
+		.enumerate()
+		.collect();
+	let new_extensions: Vec<_> = new
+		.extrinsic()
+		.transaction_extensions()
+		.enumerate()
+		.collect();
+	let transaction_extensions = diff_items(
+		old_extensions.into_iter(),
+		new_extensions.into_iter(),
+		|(_, ext)| ext.identifier,
+		|old, new| diff_transaction_extension(&mut types, old, new),
+	);
+
+	MetadataDiff {
+		pallets,
+		runtime_apis,
+		transaction_extensions,
+	}
+}
+
+/// Match the items of the old and new metadata by name and diff the ones in both.
+///
+/// Removed and changed items are listed in the order of the old metadata, followed by the
+/// added items in the order of the new metadata.
+fn diff_items<'a, T, D>(
+	old: impl Iterator<Item = T>,
+	new: impl Iterator<Item = T>,
+	name: impl Fn(&T) -> &'a str,
+	mut diff: impl FnMut(&T, &T) -> Option<D>,
+) -> Vec<Change<D>> {
+	let new: Vec<T> = new.collect();
+	let mut new_by_name: BTreeMap<&str, &T> = new.iter().map(|item| (name(item), item)).collect();
+
+	let mut changes = Vec::new();
+	for old in old {
+		match new_by_name.remove(name(&old)) {
+			Some(new) => {
+				if let Some(diff) = diff(&old, new) {
+					changes.push(Change::Changed(name(&old).into(), diff));
+				}
+			}
+			None => changes.push(Change::Removed(name(&old).into())),
+		}
+	}
+	changes.extend(
+		new.iter()
+			.filter(|item| new_by_name.contains_key(name(item)))
+			.map(|item| Change::Added(name(item).into())),
+	);
+	changes
+}
+
+/// `Some` if the values differ.
+fn modified<T: PartialEq + Clone>(old: &T, new: &T) -> Option<Modified<T>> {
+	(old != new).then(|| Modified {
+		old: old.clone(),
+		new: new.clone(),
+	})
+}
+
+fn diff_pallet(types: &mut TypeComparer, old: &Pallet, new: &Pallet) -> Option<PalletDiff> {
+	let mut diff_variants = |old: &[Variant<PortableForm>], new: &[Variant<PortableForm>]| {
+		diff_items(
+			old.iter(),
+			new.iter(),
+			|variant| AsRef::<str>::as_ref(&variant.name),
+			|old, new| {
+				let diff = VariantDiff {
+					index: modified(&old.index, &new.index),
+					fields: types.fields(&old.fields, &new.fields),
+				};
+				(diff.index.is_some() || diff.fields.is_some()).then_some(diff)
+			},
+		)
+	};
+	let calls = diff_variants(old.calls(), new.calls());
+	let events = diff_variants(old.events(), new.events());
+	let errors = diff_variants(old.errors(), new.errors());
+
+	let storage = diff_items(
+		old.storage_entries(),
+		new.storage_entries(),
+		|entry| entry.name,
+		|old, new| diff_storage_entry(types, old, new),
+	);
+
+	let constants = diff_items(
+		old.constants(),
+		new.constants(),
+		|constant| constant.name,
+		|old, new| {
+			let diff = ConstantDiff {
+				ty: types.compare(old.ty, new.ty),
+				value: modified(&old.value, &new.value).map(|value| Modified {
+					old: value.old.to_vec(),
+					new: value.new.to_vec(),
+				}),
+			};
+			(diff.ty.is_some() || diff.value.is_some()).then_some(diff)
+		},
+	);
+
+	let storage_prefix = match (old.storage_prefix(), new.storage_prefix()) {
+		(Some(old), Some(new)) if old != new => Some(Modified {
+			old: old.into(),
+			new: new.into(),
+		}),
+		_ => None,
+	};
+
+	let diff = PalletDiff {
+		index: modified(&old.index(), &new.index()),
+		storage_prefix,
+		calls,
+		events,
+		errors,
+		storage,
+		constants,
+	};
+	(diff != PalletDiff::default()).then_some(diff)
+}
+
+fn diff_storage_entry(
+	types: &mut TypeComparer,
+	old: &StorageEntry,
+	new: &StorageEntry,
+) -> Option<StorageEntryDiff> {
+	let hashers = |ty: &v14::StorageEntryType<PortableForm>| match ty {
+		v14::StorageEntryType::Plain(_) => Vec::new(),
+		v14::StorageEntryType::Map { hashers, .. } => hashers.clone(),
+	};
+	let key = match (old.ty, new.ty) {
+		(v14::StorageEntryType::Plain(_), v14::StorageEntryType::Plain(_)) => None,
+		(
+			v14::StorageEntryType::Map { key: old, .. },
+			v14::StorageEntryType::Map { key: new, .. },
+		) => types.compare(old.id, new.id),
+		_ => Some(TypeChange::Encoding),
+	};
+	let value = |ty: &v14::StorageEntryType<PortableForm>| match ty {
+		v14::StorageEntryType::Plain(value) | v14::StorageEntryType::Map { value, .. } => value.id,
+	};
+
+	let diff = StorageEntryDiff {
+		modifier: modified(old.modifier, new.modifier),
+		hashers: modified(&hashers(old.ty), &hashers(new.ty)),
+		key,
+		value: types.compare(value(old.ty), value(new.ty)),
+		default: modified(&old.default, &new.default).map(|default| Modified {
+			old: default.old.to_vec(),
+			new: default.new.to_vec(),
+		}),
+	};
+	let changed = diff.modifier.is_some()
+		|| diff.hashers.is_some()
+		|| diff.key.is_some()
+		|| diff.value.is_some()
+		|| diff.default.is_some();
+	changed.then_some(diff)
+}
+
+fn diff_runtime_api_method(
+	types: &mut TypeComparer,
+	old: &RuntimeApiMethod,
+	new: &RuntimeApiMethod,
+) -> Option<RuntimeApiMethodDiff> {
+	let inputs = if old.inputs.len() != new.inputs.len() {
+		Some(TypeChange::Encoding)
+	} else {
+		old.inputs
+			.iter()
+			.zip(&new.inputs)
+			.map(|((old_name, old_ty), (new_name, new_ty))| {
+				let renamed = (old_name != new_name).then_some(TypeChange::Renamed);
+				renamed.max(types.compare(*old_ty, *new_ty))
+			})
+			.max()
+			.flatten()
+	};
+	let diff = RuntimeApiMethodDiff {
+		inputs,
+		output: types.compare(old.output, new.output),
+	};
+	(diff.inputs.is_some() || diff.output.is_some()).then_some(diff)
+}
+
+fn diff_transaction_extension(
+	types: &mut TypeComparer,
+	(old_position, old): &(usize, TransactionExtension),
+	(new_position, new): &(usize, TransactionExtension),
+) -> Option<TransactionExtensionDiff> {
+	let diff = TransactionExtensionDiff {
+		position: modified(old_position, new_position),
+		ty: types.compare(old.ty, new.ty),
+		implicit: types.compare(old.implicit, new.implicit),
+	};
+	(diff.position.is_some() || diff.ty.is_some() || diff.implicit.is_some()).then_some(diff)
+}
+
+/// Compares types of two different registries by their structure.
+///
+/// Paths, field names and variant names are compared as well, but differences there are only
+/// reported as [`TypeChange::Renamed`]. Type parameters and documentation are ignored.
+pub(crate) struct TypeComparer<'a> {
+	old: &'a PortableRegistry,
+	new: &'a PortableRegistry,
+	/// The comparisons that are known to be exact.
+	cache: BTreeMap<(u32, u32), Option<TypeChange>>,
+	/// The comparisons in progress, which are assumed to find no change when reached again
+	/// through a recursive type.
+	in_progress: BTreeSet<(u32, u32)>,
+	/// The comparisons done since the outermost comparison started.
+	visited: BTreeMap<(u32, u32), Option<TypeChange>>,
+}
+
+impl<'a> TypeComparer<'a> {
+	pub(crate) fn new(old: &'a PortableRegistry, new: &'a PortableRegistry) -> Self {
+		TypeComparer {
+			old,
+			new,
+			cache: BTreeMap::new(),
+			in_progress: BTreeSet::new(),
+			visited: BTreeMap::new(),
+		}
+	}
+
+	/// Compare the type `old` of the old registry with the type `new` of the new registry.
+	pub(crate) fn compare(&mut self, old: u32, new: u32) -> Option<TypeChange> {
+		let outermost = self.in_progress.is_empty();
+		let change = self.compare_inner(old, new);
+
+		if outermost {
+			// Comparisons inside a cycle may have relied on the assumption that an outer
+			// comparison finds no change, so their results may be too small. The outermost
+			// result is exact, and so is any inner result that is as large, since the outer
+			// result covers all inner ones.
+			for (pair, inner) in core::mem::take(&mut self.visited) {
+				if inner == change {
+					self.cache.insert(pair, inner);
+				}
+			}
+		}
+		change
+	}
+
+	/// Compare the fields of two composites or variants.
+	pub(crate) fn fields(
+		&mut self,
+		old: &[Field<PortableForm>],
+		new: &[Field<PortableForm>],
+	) -> Option<TypeChange> {
+		if old.len() != new.len() {
+			return Some(TypeChange::Encoding);
+		}
+		old.iter()
+			.zip(new)
+			.map(|(old, new)| {
+				let renamed = (old.name != new.name).then_some(TypeChange::Renamed);
+				renamed.max(self.compare(old.ty.id, new.ty.id))
+			})
+			.max()
+			.flatten()
+	}
+
+	fn compare_inner(&mut self, old: u32, new: u32) -> Option<TypeChange> {
+		if let Some(change) = self
+			.cache
+			.get(&(old, new))
+			.or(self.visited.get(&(old, new)))
+		{
+			return *change;
+		}
+		if !self.in_progress.insert((old, new)) {
+			return None;
+		}
+		let change = self.compare_types(old, new);
+		self.in_progress.remove(&(old, new));
+		self.visited.insert((old, new), change);
+		change
+	}
+
+	fn compare_types(&mut self, old: u32, new: u32) -> Option<TypeChange> {
+		let (Some(old), Some(new)) = (self.old.resolve(old), self.new.resolve(new)) else {
+			return Some(TypeChange::Encoding);
+		};
+		let renamed = (old.path != new.path).then_some(TypeChange::Renamed);
+
+		let change = match (&old.type_def, &new.type_def) {
+			(TypeDef::Composite(old), TypeDef::Composite(new)) => {
+				self.fields(&old.fields, &new.fields)
+			}
+			(TypeDef::Variant(old), TypeDef::Variant(new)) => {
+				if old.variants.len() != new.variants.len() {
+					return Some(TypeChange::Encoding);
+				}
+				let mut change = None;
+				for old in &old.variants {
+					let Some(new) = new.variants.iter().find(|new| new.index == old.index) else {
+						return Some(TypeChange::Encoding);
+					};
+					let renamed = (old.name != new.name).then_some(TypeChange::Renamed);
+					change = change
+						.max(renamed)
+						.max(self.fields(&old.fields, &new.fields));
+				}
+				change
+			}
+			(TypeDef::Sequence(old), TypeDef::Sequence(new)) => {
+				self.compare_inner(old.type_param.id, new.type_param.id)
+			}
+			(TypeDef::Array(old), TypeDef::Array(new)) if old.len == new.len => {
+				self.compare_inner(old.type_param.id, new.type_param.id)
+			}
+			(TypeDef::Tuple(old), TypeDef::Tuple(new)) if old.fields.len() == new.fields.len() => {
+				old.fields
+					.iter()
+					.zip(&new.fields)
+					.map(|(old, new)| self.compare_inner(old.id, new.id))
+					.max()
+					.flatten()
+			}
+			(TypeDef::Primitive(old), TypeDef::Primitive(new)) if old == new => None,
+			(TypeDef::Compact(old), TypeDef::Compact(new)) => {
+				self.compare_inner(old.type_param.id, new.type_param.id)
+			}
+			(TypeDef::BitSequence(old), TypeDef::BitSequence(new)) => self
+				.compare_inner(old.bit_store_type.id, new.bit_store_type.id)
+				.max(self.compare_inner(old.bit_order_type.id, new.bit_order_type.id)),
+			_ => Some(TypeChange::Encoding),
+		};
+		renamed.max(change)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test::{kusama_v14, kusama_v15, kusama_v16, metadata_with_calls, Tree};
+	use scale_info::{meta_type, TypeInfo};
+
+	#[allow(dead_code)]
+	#[derive(TypeInfo)]
+	enum CallA {
+		Transfer(u32, Tree),
+	}
+
+	#[allow(dead_code)]
+	#[derive(TypeInfo)]
+	enum CallB {
+		Toggle { flag: bool },
+	}
+
+	#[allow(dead_code)]
+	#[derive(TypeInfo)]
+	enum RenamedCallB {
+		Toggle { enabled: bool },
+	}
+
+	#[test]
+	fn should_find_no_differences_between_versions() {
+		let v14 = kusama_v14();
+		let v15 = kusama_v15();
+		let v16 = kusama_v16();
+		let old = Metadata::from(v14);
+
+		for new in [old.clone(), v15.into(), v16.into()] {
+			assert_eq!(diff(&old, &new), MetadataDiff::default());
+		}
+	}
+
+	#[test]
+	fn should_compare_types_across_registries() {
+		let a = ("A", 0, meta_type::<CallA>());
+		let b = ("B", 1, meta_type::<CallB>());
+		let old = metadata_with_calls(&[a, b]);
+		let new = metadata_with_calls(&[b, a]);
+
+		let call_ty = |metadata: &Metadata| metadata.pallet_by_name("A").unwrap().call_ty();
+		assert_ne!(call_ty(&old), call_ty(&new));
+		assert!(diff(&old, &new).is_empty());
+
+		let renamed = metadata_with_calls(&[a, ("B", 1, meta_type::<RenamedCallB>())]);
+		let diff = diff(&old, &renamed);
+		let [Change::Changed(name, pallet)] = &diff.pallets[..] else {
+			panic!("expected a single changed pallet, got {diff:?}")
+		};
+		assert_eq!(name, "B");
+		assert_eq!(
+			pallet.calls,
+			[Change::Changed(
+				"Toggle".into(),
+				VariantDiff {
+					index: None,
+					fields: Some(TypeChange::Renamed),
+				}
+			)]
+		);
+	}
+
+	#[test]
+	fn should_report_changed_pallet_items() {
+		let old = kusama_v14();
+		let mut new = old.clone();
+
+		let balances = new
+			.pallets
+			.iter_mut()
+			.find(|p| p.name == "Balances")
+			.unwrap();
+		balances.index = 100;
+
+		let system = new.pallets.iter_mut().find(|p| p.name == "System").unwrap();
+		let storage = system.storage.as_mut().unwrap();
+		let account = storage
+			.entries
+			.iter_mut()
+			.find(|e| e.name == "Account")
+			.unwrap();
+		let v14::StorageEntryType::Map { hashers, .. } = &mut account.ty else {
+			panic!("System::Account should be a map")
+		};
+		hashers[0] = v14::StorageHasher::Twox64Concat;
+		let prefix = system
+			.constants
+			.iter_mut()
+			.find(|c| c.name == "SS58Prefix")
+			.unwrap();
+		prefix.value = vec![42, 0];
+
+		let call_ty = system.calls.as_ref().unwrap().ty.id;
+		let call = &mut new.types.types[call_ty as usize].ty.type_def;
+		let TypeDef::Variant(call) = call else {
+			panic!("System calls should be a variant type")
+		};
+		call.variants.retain(|variant| variant.name != "remark");
+
+		let diff = diff(&old.into(), &new.into());
+		let names: Vec<_> = diff.pallets.iter().map(Change::name).collect();
+		assert_eq!(names[..2], ["System", "Balances"]);
+
+		// Calls taking a runtime call changed too, since the `System` calls are part of it.
+		let utility = diff.pallets.iter().find(|p| p.name() == "Utility").unwrap();
+		let Change::Changed(_, utility) = utility else {
+			panic!("Utility should have changed")
+		};
+		assert!(utility.calls.contains(&Change::Changed(
+			"batch".into(),
+			VariantDiff {
+				index: None,
+				fields: Some(TypeChange::Encoding),
+			}
+		)));
+
+		let Change::Changed(_, balances) = &diff.pallets[1] else {
+			panic!("Balances should have changed")
+		};
+		assert_eq!(balances.index, Some(Modified { old: 4, new: 100 }));
+
+		let Change::Changed(_, system) = &diff.pallets[0] else {
+			panic!("System should have changed")
+		};
+		assert_eq!(system.calls, [Change::Removed("remark".into())]);
+		let [Change::Changed(name, account)] = &system.storage[..] else {
+			panic!("only System::Account should have changed")
+		};
+		assert_eq!(name, "Account");
+		assert_eq!(
+			account.hashers.as_ref().unwrap().new,
+			[v14::StorageHasher::Twox64Concat]
+		);
+		assert_eq!((account.key, account.value), (None, None));
+		let [Change::Changed(_, prefix)] = &system.constants[..] else {
+			panic!("only System::SS58Prefix should have changed")
+		};
+		assert_eq!(prefix.value.as_ref().unwrap().new, [42, 0]);
+		assert_eq!(prefix.ty, None);
+	}
+}
@@ -0,0 +1,263 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding the errors of pallets from a `DispatchError`.
+//!
+//! A `DispatchError::Module` holds the index of the pallet that failed, followed by the
+//! error bytes, which are the encoded variant of the pallet's error enum padded to four
+//! bytes. See [`decode_module_error`](crate::dispatch_error::decode_module_error) to decode
+//! them, and [`decode_dispatch_error`](crate::dispatch_error::decode_dispatch_error) to decode
+//! a whole `DispatchError`.
+
+use crate::{
+	metadata::{Metadata, Pallet},
+	value::{decode_value, decode_value_from, Composite, DecodeError, DecodeErrorKind, Value},
+};
+use scale_info::{form::PortableForm, prelude::string::String, Variant};
+
+/// An error that can occur when decoding a dispatch error.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DispatchErrorDecodeError {
+	/// The type registry has no `sp_runtime::DispatchError` type.
+	MissingDispatchErrorType,
+	/// The bytes do not decode as a `DispatchError`.
+	InvalidDispatchError(DecodeError),
+	/// The module error has no pallet index.
+	NotEnoughData,
+	/// There is no pallet with the given index.
+	UnknownPallet(u8),
+	/// The pallet with the given name has no error enum.
+	NoErrors(String),
+	/// The error type of the pallet with the given name is not an enum.
+	ErrorsNotAnEnum(String),
+	/// The pallet has no error with the given index.
+	UnknownError {
+		/// The pallet name.
+		pallet: String,
+		/// The error index.
+		index: u8,
+	},
+	/// The error bytes do not decode as the pallet's error enum.
+	InvalidError(DecodeError),
+}
+
+impl core::fmt::Display for DispatchErrorDecodeError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			DispatchErrorDecodeError::MissingDispatchErrorType => {
+				write!(f, "the type registry has no `DispatchError` type")
+			}
+			DispatchErrorDecodeError::InvalidDispatchError(error) => {
+				write!(f, "invalid dispatch error: {error}")
+			}
+			DispatchErrorDecodeError::NotEnoughData => write!(f, "missing pallet index"),
+			DispatchErrorDecodeError::UnknownPallet(index) => {
+				write!(f, "no pallet with index {index}")
+			}
+			DispatchErrorDecodeError::NoErrors(pallet) => {
+				write!(f, "pallet `{pallet}` has no error enum")
+			}
+			DispatchErrorDecodeError::ErrorsNotAnEnum(pallet) => {
+				write!(f, "the error type of pallet `{pallet}` is not an enum")
+			}
+			DispatchErrorDecodeError::UnknownError { pallet, index } => {
+				write!(f, "pallet `{pallet}` has no error with index {index}")
+			}
+			DispatchErrorDecodeError::InvalidError(error) => {
+				write!(f, "invalid pallet error: {error}")
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DispatchErrorDecodeError {}
+
+/// A decoded error of a pallet, borrowing from the metadata.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ModuleError<'a> {
+	/// The pallet that failed.
+	pub pallet: Pallet<'a>,
+	/// The error variant of the pallet's error enum, with its name and documentation.
+	pub error: &'a Variant<PortableForm>,
+	/// The decoded fields of the error.
+	pub fields: Composite,
+}
+
+/// A decoded `DispatchError`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DispatchError<'a> {
+	/// An error of a pallet.
+	Module(ModuleError<'a>),
+	/// Any other variant of the `DispatchError`, such as `BadOrigin` or `Token`.
+	Other(Value),
+}
+
+/// Decode the bytes of a `DispatchError::Module`, which start with the pallet index and are
+/// followed by the error bytes.
+///
+/// The error bytes are decoded as the error enum of the pallet, which works for V14 metadata
+/// too, unlike [`error_enum_ty`](crate::v15::OuterEnums::error_enum_ty). The error may not
+/// use all of the bytes.
+pub fn decode_module_error<'a>(
+	metadata: &'a Metadata,
+	bytes: &[u8],
+) -> Result<ModuleError<'a>, DispatchErrorDecodeError> {
+	let (&pallet_index, mut error_bytes) = bytes
+		.split_first()
+		.ok_or(DispatchErrorDecodeError::NotEnoughData)?;
+	let pallet = metadata
+		.pallet_by_index(pallet_index)
+		.ok_or(DispatchErrorDecodeError::UnknownPallet(pallet_index))?;
+	let error_ty = pallet
+		.error_ty()
+		.ok_or_else(|| DispatchErrorDecodeError::NoErrors(pallet.name().into()))?;
+
+	let unknown_error = |index| DispatchErrorDecodeError::UnknownError {
+		pallet: pallet.name().into(),
+		index,
+	};
+	let value = decode_value_from(metadata.types(), error_ty, &mut error_bytes).map_err(
+		|error| match error.kind {
+			DecodeErrorKind::InvalidVariantIndex(index) if error.path.is_empty() => {
+				unknown_error(index)
+			}
+			_ => DispatchErrorDecodeError::InvalidError(error),
+		},
+	)?;
+	let Value::Variant { index, fields, .. } = value else {
+		return Err(DispatchErrorDecodeError::ErrorsNotAnEnum(
+			pallet.name().into(),
+		));
+	};
+	let error = pallet
+		.error_by_index(index)
+		.ok_or_else(|| unknown_error(index))?;
+	Ok(ModuleError {
+		pallet,
+		error,
+		fields,
+	})
+}
+
+/// Decode an encoded `sp_runtime::DispatchError`, as found in the type registry of the
+/// metadata, resolving the pallet error of a `DispatchError::Module`.
+pub fn decode_dispatch_error<'a>(
+	metadata: &'a Metadata,
+	bytes: &[u8],
+) -> Result<DispatchError<'a>, DispatchErrorDecodeError> {
+	let ty = metadata
+		.types()
+		.types
+		.iter()
+		.find(|ty| {
+			let segments = ty.ty.path.segments.iter().map(AsRef::<str>::as_ref);
+			segments.eq(["sp_runtime", "DispatchError"])
+		})
+		.ok_or(DispatchErrorDecodeError::MissingDispatchErrorType)?;
+
+	let value = decode_value(metadata.types(), ty.id, bytes)
+		.map_err(DispatchErrorDecodeError::InvalidDispatchError)?;
+	match &value {
+		// The fields of the module error are the pallet index and the error bytes.
+		Value::Variant { name, .. } if name == "Module" => {
+			decode_module_error(metadata, &bytes[1..]).map(DispatchError::Module)
+		}
+		_ => Ok(DispatchError::Other(value)),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test::{kusama_v14, kusama_v15};
+	use scale_info::{TypeDef, TypeDefPrimitive};
+
+	#[test]
+	fn should_decode_module_errors() {
+		let v14 = kusama_v14();
+		let v15 = kusama_v15();
+		for metadata in [Metadata::from(v14), Metadata::from(v15)] {
+			// `Balances::InsufficientBalance`, padded to four bytes.
+			let error = decode_module_error(&metadata, &[4, 2, 0, 0, 0]).unwrap();
+			assert_eq!(error.pallet.name(), "Balances");
+			assert_eq!(error.error.name, "InsufficientBalance");
+			assert_eq!(error.error.docs, ["Balance too low to send value"]);
+			assert_eq!(error.fields, Composite::Unnamed(Vec::new()));
+		}
+	}
+
+	#[test]
+	fn should_decode_dispatch_errors() {
+		let metadata = Metadata::from(kusama_v14());
+
+		// Kusama still encodes the module error as a pallet index and a single error byte.
+		let DispatchError::Module(error) = decode_dispatch_error(&metadata, &[3, 4, 2]).unwrap()
+		else {
+			panic!("the error should be a module error")
+		};
+		assert_eq!(error.error.name, "InsufficientBalance");
+
+		let DispatchError::Other(Value::Variant { name, .. }) =
+			decode_dispatch_error(&metadata, &[2]).unwrap()
+		else {
+			panic!("the error should be another variant")
+		};
+		assert_eq!(name, "BadOrigin");
+
+		assert!(matches!(
+			decode_dispatch_error(&metadata, &[3, 4]),
+			Err(DispatchErrorDecodeError::InvalidDispatchError(_))
+		));
+		assert_eq!(
+			decode_module_error(&metadata, &[255, 0]),
+			Err(DispatchErrorDecodeError::UnknownPallet(255))
+		);
+		assert_eq!(
+			decode_module_error(&metadata, &[4, 200]),
+			Err(DispatchErrorDecodeError::UnknownError {
+				pallet: "Balances".into(),
+				index: 200
+			})
+		);
+		assert!(matches!(
+			decode_module_error(&metadata, &[4]),
+			Err(DispatchErrorDecodeError::InvalidError(_))
+		));
+		assert_eq!(
+			decode_module_error(&metadata, &[]),
+			Err(DispatchErrorDecodeError::NotEnoughData)
+		);
+	}
+
+	#[test]
+	fn should_reject_error_types_that_are_not_enums() {
+		let mut v14 = kusama_v14();
+		let u8_ty = v14
+			.types
+			.types
+			.iter()
+			.find(|ty| ty.ty.type_def == TypeDef::Primitive(TypeDefPrimitive::U8))
+			.unwrap()
+			.id;
+		v14.pallets[4].error.as_mut().unwrap().ty = u8_ty.into();
+		let metadata = Metadata::from(v14);
+
+		assert_eq!(
+			decode_module_error(&metadata, &[4, 2, 0, 0, 0]),
+			Err(DispatchErrorDecodeError::ErrorsNotAnEnum("Balances".into()))
+		);
+	}
+}
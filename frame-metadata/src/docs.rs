@@ -0,0 +1,331 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Removing the documentation from the metadata, and attaching it again.
+//!
+//! Documentation makes up a large part of the encoded metadata, and is of no use to clients
+//! that never show it. `strip_docs` on
+//! [`RuntimeMetadataV14`](crate::v14::RuntimeMetadataV14),
+//! [`RuntimeMetadataV15`](crate::v15::RuntimeMetadataV15) and
+//! [`RuntimeMetadataV16`](crate::v16::RuntimeMetadataV16) removes the docs of every item and
+//! type, and returns them as [`Docs`](crate::docs::Docs) so that `attach_docs` can put them
+//! back later.
+//!
+//! Docs are keyed by the `::` separated path of their item:
+//! - `pallets::Balances` for a pallet,
+//! - `pallets::Balances::storage::Account`, `pallets::Balances::constants::ExistentialDeposit`,
+//!   `pallets::Balances::associated_types::Balance` and
+//!   `pallets::Balances::view_functions::balance` for pallet items,
+//! - `apis::Core` and `apis::Core::version` for runtime APIs and their methods,
+//! - `types::12` for the type with ID 12, `types::12::fields::0` for its first field, and
+//!   `types::12::variants::3` and `types::12::variants::3::fields::0` for its variant with
+//!   index 3 and the first field of that variant.
+//!
+//! Calls, events and errors are variants of the pallet's enum types, so their docs are those
+//! of the variants. As type IDs are part of the paths, docs can only be attached to metadata
+//! whose types have not been renumbered since the docs were stripped.
+
+use crate::{
+	named::{Named, RuntimeApi},
+	v14, v15, v16,
+};
+use codec::Encode;
+use scale_info::{
+	form::{Form, PortableForm},
+	prelude::{collections::BTreeMap, format, string::String, vec::Vec},
+	PortableRegistry, TypeDef,
+};
+
+#[cfg(feature = "decode")]
+use codec::Decode;
+#[cfg(all(feature = "serde_full", feature = "decode"))]
+use serde::Deserialize;
+#[cfg(feature = "serde_full")]
+use serde::Serialize;
+
+/// The string type of the docs, which depends on the enabled features of `scale-info`.
+type DocString = <PortableForm as Form>::String;
+
+/// Documentation removed from the metadata, keyed by the path of the documented item.
+#[derive(Clone, Default, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+#[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
+pub struct Docs(BTreeMap<String, Vec<DocString>>);
+
+impl Docs {
+	/// The docs of the item with the given path.
+	pub fn get(&self, path: &str) -> Option<&[DocString]> {
+		self.0.get(path).map(Vec::as_slice)
+	}
+
+	/// The paths of all documented items and their docs, ordered by path.
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &[DocString])> {
+		self.0
+			.iter()
+			.map(|(path, docs)| (path.as_str(), docs.as_slice()))
+	}
+
+	/// Whether no item had any docs.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Move the non-empty `docs` of the item at `path` into the table.
+	fn take(&mut self, path: String, docs: &mut Vec<DocString>) {
+		if !docs.is_empty() {
+			self.0.insert(path, core::mem::take(docs));
+		}
+	}
+
+	/// Replace the `docs` of the item at `path` with those in the table, if there are any.
+	fn attach(&self, path: String, docs: &mut Vec<DocString>) {
+		if let Some(stored) = self.0.get(&path) {
+			docs.clone_from(stored);
+		}
+	}
+}
+
+impl v14::RuntimeMetadataV14 {
+	/// Remove the docs of all items and types, returning them.
+	pub fn strip_docs(&mut self) -> Docs {
+		let mut docs = Docs::default();
+		self.visit_docs(&mut |path, item_docs| docs.take(path, item_docs));
+		docs
+	}
+
+	/// Attach docs removed by [`strip_docs`](Self::strip_docs). Items without docs in
+	/// `docs` keep their current docs.
+	pub fn attach_docs(&mut self, docs: &Docs) {
+		self.visit_docs(&mut |path, item_docs| docs.attach(path, item_docs));
+	}
+
+	fn visit_docs(&mut self, f: &mut impl FnMut(String, &mut Vec<DocString>)) {
+		visit_type_docs(&mut self.types, f);
+		for pallet in &mut self.pallets {
+			let pallet_path = format!("pallets::{}", AsRef::<str>::as_ref(&pallet.name));
+			let entries = pallet.storage.iter_mut().flat_map(|s| &mut s.entries);
+			visit_pallet_item_docs(&pallet_path, entries, &mut pallet.constants, f);
+		}
+	}
+}
+
+impl v15::RuntimeMetadataV15 {
+	/// Remove the docs of all items and types, returning them.
+	pub fn strip_docs(&mut self) -> Docs {
+		let mut docs = Docs::default();
+		self.visit_docs(&mut |path, item_docs| docs.take(path, item_docs));
+		docs
+	}
+
+	/// Attach docs removed by [`strip_docs`](Self::strip_docs). Items without docs in
+	/// `docs` keep their current docs.
+	pub fn attach_docs(&mut self, docs: &Docs) {
+		self.visit_docs(&mut |path, item_docs| docs.attach(path, item_docs));
+	}
+
+	fn visit_docs(&mut self, f: &mut impl FnMut(String, &mut Vec<DocString>)) {
+		visit_type_docs(&mut self.types, f);
+		for pallet in &mut self.pallets {
+			let pallet_path = format!("pallets::{}", AsRef::<str>::as_ref(&pallet.name));
+			let entries = pallet.storage.iter_mut().flat_map(|s| &mut s.entries);
+			visit_pallet_item_docs(&pallet_path, entries, &mut pallet.constants, f);
+			f(pallet_path, &mut pallet.docs);
+		}
+		visit_api_docs(&mut self.apis, f);
+	}
+}
+
+impl v16::RuntimeMetadataV16 {
+	/// Remove the docs of all items and types, returning them.
+	pub fn strip_docs(&mut self) -> Docs {
+		let mut docs = Docs::default();
+		self.visit_docs(&mut |path, item_docs| docs.take(path, item_docs));
+		docs
+	}
+
+	/// Attach docs removed by [`strip_docs`](Self::strip_docs). Items without docs in
+	/// `docs` keep their current docs.
+	pub fn attach_docs(&mut self, docs: &Docs) {
+		self.visit_docs(&mut |path, item_docs| docs.attach(path, item_docs));
+	}
+
+	fn visit_docs(&mut self, f: &mut impl FnMut(String, &mut Vec<DocString>)) {
+		visit_type_docs(&mut self.types, f);
+		for pallet in &mut self.pallets {
+			let pallet_path = format!("pallets::{}", AsRef::<str>::as_ref(&pallet.name));
+			let entries = pallet.storage.iter_mut().flat_map(|s| &mut s.entries);
+			visit_pallet_item_docs(&pallet_path, entries, &mut pallet.constants, f);
+			let types = &mut pallet.associated_types;
+			visit_item_docs(&format!("{pallet_path}::associated_types"), types, f);
+			let view_functions = &mut pallet.view_functions;
+			visit_item_docs(&format!("{pallet_path}::view_functions"), view_functions, f);
+			f(pallet_path, &mut pallet.docs);
+		}
+		visit_api_docs(&mut self.apis, f);
+	}
+}
+
+/// Visit the docs of the storage entries and constants of the pallet at `pallet_path`.
+fn visit_pallet_item_docs<'a, E: Named + 'a>(
+	pallet_path: &str,
+	entries: impl IntoIterator<Item = &'a mut E>,
+	constants: &mut [impl Named],
+	f: &mut impl FnMut(String, &mut Vec<DocString>),
+) {
+	visit_item_docs(&format!("{pallet_path}::storage"), entries, f);
+	visit_item_docs(&format!("{pallet_path}::constants"), constants, f);
+}
+
+/// Visit the docs of the named `items` of the item at `parent`.
+fn visit_item_docs<'a, T: Named + 'a>(
+	parent: &str,
+	items: impl IntoIterator<Item = &'a mut T>,
+	f: &mut impl FnMut(String, &mut Vec<DocString>),
+) {
+	for item in items {
+		let (name, docs) = item.name_and_docs();
+		f(format!("{parent}::{name}"), docs);
+	}
+}
+
+/// Visit the docs of the runtime APIs and their methods.
+fn visit_api_docs(apis: &mut [impl RuntimeApi], f: &mut impl FnMut(String, &mut Vec<DocString>)) {
+	for api in apis {
+		let (name, methods) = api.name_and_methods();
+		let api_path = format!("apis::{name}");
+		visit_item_docs(&api_path, methods, f);
+		f(api_path, api.name_and_docs().1);
+	}
+}
+
+/// Visit the docs of every type of the registry, and of their fields and variants.
+fn visit_type_docs(types: &mut PortableRegistry, f: &mut impl FnMut(String, &mut Vec<DocString>)) {
+	for ty in &mut types.types {
+		let type_path = format!("types::{}", ty.id);
+		match &mut ty.ty.type_def {
+			TypeDef::Composite(composite) => {
+				for (position, field) in composite.fields.iter_mut().enumerate() {
+					f(format!("{type_path}::fields::{position}"), &mut field.docs);
+				}
+			}
+			TypeDef::Variant(variant) => {
+				for variant in &mut variant.variants {
+					let variant_path = format!("{type_path}::variants::{}", variant.index);
+					for (position, field) in variant.fields.iter_mut().enumerate() {
+						f(
+							format!("{variant_path}::fields::{position}"),
+							&mut field.docs,
+						);
+					}
+					f(variant_path, &mut variant.docs);
+				}
+			}
+			_ => {}
+		}
+		f(type_path, &mut ty.ty.docs);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test::{kusama_v14, kusama_v16};
+
+	#[test]
+	fn should_strip_and_attach_docs() {
+		let original = kusama_v14();
+		let mut v14 = original.clone();
+		let docs = v14.strip_docs();
+
+		assert!(v14.encoded_size() < original.encoded_size() / 2);
+		assert!(v14.types.types.iter().all(|ty| ty.ty.docs.is_empty()));
+		let balances = v14.pallets.iter().find(|p| p.name == "Balances").unwrap();
+		assert!(balances.constants.iter().all(|c| c.docs.is_empty()));
+		assert!(docs
+			.get("pallets::Balances::constants::ExistentialDeposit")
+			.is_some_and(|docs| !docs.is_empty()));
+		assert!(docs.iter().all(|(_, docs)| !docs.is_empty()));
+
+		let call_ty = balances.calls.as_ref().unwrap().ty.id;
+		let TypeDef::Variant(calls) = &original.types.resolve(call_ty).unwrap().type_def else {
+			panic!("Balances::Call should be a variant type")
+		};
+		let path = format!("types::{call_ty}::variants::{}", calls.variants[0].index);
+		assert_eq!(docs.get(&path), Some(&calls.variants[0].docs[..]));
+
+		v14.attach_docs(&docs);
+		assert_eq!(v14, original);
+	}
+
+	#[test]
+	fn should_strip_docs_of_every_v16_item() {
+		let mut original = kusama_v16();
+		let balances = &mut original.pallets[4];
+		assert_eq!(balances.name, "Balances");
+		balances.docs = vec!["The balances pallet.".into()];
+		balances
+			.associated_types
+			.push(v16::PalletAssociatedTypeMetadata {
+				name: "Balance".into(),
+				ty: 6.into(),
+				docs: vec!["The balance type.".into()],
+			});
+		original.apis.push(v16::RuntimeApiMetadata {
+			name: "Core".into(),
+			methods: vec![v16::RuntimeApiMethodMetadata {
+				name: "version".into(),
+				inputs: Vec::new(),
+				output: 0.into(),
+				docs: vec!["The runtime version.".into()],
+				deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+			}],
+			docs: vec!["The core runtime API.".into()],
+			version: codec::Compact(5),
+			deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+		});
+
+		let mut v16 = original.clone();
+		let docs = v16.strip_docs();
+		for (path, expected) in [
+			("pallets::Balances", "The balances pallet."),
+			(
+				"pallets::Balances::associated_types::Balance",
+				"The balance type.",
+			),
+			("apis::Core", "The core runtime API."),
+			("apis::Core::version", "The runtime version."),
+		] {
+			assert_eq!(
+				docs.get(path),
+				Some(&[expected.into()][..]),
+				"docs of {path}"
+			);
+		}
+		assert!(v16.pallets[4].docs.is_empty());
+		assert!(v16.apis[0].methods[0].docs.is_empty());
+
+		v16.attach_docs(&docs);
+		assert_eq!(v16, original);
+	}
+
+	#[cfg(feature = "decode")]
+	#[test]
+	fn should_encode_and_decode_docs() {
+		let docs = kusama_v14().strip_docs();
+		let decoded = Docs::decode(&mut &docs.encode()[..]).unwrap();
+		assert_eq!(decoded, docs);
+	}
+}
@@ -0,0 +1,404 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding the events of a block, as stored in the `System::Events` storage entry.
+//!
+//! The entry holds a `Vec<EventRecord<RuntimeEvent, Hash>>`, where every record has the
+//! phase of the block in which the event was deposited, the event itself as a variant of the
+//! outer event enum, and the topics of the event. See
+//! [`decode_events`](crate::events::decode_events).
+
+use crate::{
+	metadata::{Metadata, Pallet},
+	v14,
+	value::{decode_value_from, Composite, DecodeError, DecodeErrorKind, PathSegment, Value},
+};
+use codec::{Compact, Decode};
+use scale_info::{
+	form::PortableForm,
+	prelude::{string::String, vec, vec::Vec},
+	TypeDef, Variant,
+};
+
+/// An error that can occur when decoding events.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EventsError {
+	/// The metadata has no `System::Events` storage entry.
+	MissingEventsEntry,
+	/// The type with the given ID is not a sequence of event records.
+	UnexpectedRecordType(u32),
+	/// The length prefix of the event records is invalid.
+	InvalidLength,
+	/// The record at the given position does not decode.
+	InvalidRecord {
+		/// The position of the record.
+		index: usize,
+		/// The error, with a path starting at the field of the record.
+		error: DecodeError,
+	},
+	/// The phase with the given name is not known.
+	UnknownPhase(String),
+	/// There is no pallet with the given index.
+	UnknownPallet(u8),
+	/// The pallet has no event with the given index.
+	UnknownEvent {
+		/// The pallet name.
+		pallet: String,
+		/// The event index.
+		index: u8,
+	},
+	/// The given number of bytes are left after the last record.
+	TrailingBytes(usize),
+}
+
+impl core::fmt::Display for EventsError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			EventsError::MissingEventsEntry => {
+				write!(f, "the metadata has no `System::Events` storage entry")
+			}
+			EventsError::UnexpectedRecordType(id) => {
+				write!(f, "type with ID {id} is not a sequence of event records")
+			}
+			EventsError::InvalidLength => write!(f, "invalid number of event records"),
+			EventsError::InvalidRecord { index, error } => {
+				write!(f, "invalid event record {index}: {error}")
+			}
+			EventsError::UnknownPhase(name) => write!(f, "unknown phase `{name}`"),
+			EventsError::UnknownPallet(index) => write!(f, "no pallet with index {index}"),
+			EventsError::UnknownEvent { pallet, index } => {
+				write!(f, "pallet `{pallet}` has no event with index {index}")
+			}
+			EventsError::TrailingBytes(len) => {
+				write!(f, "{len} bytes left after the last event record")
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EventsError {}
+
+/// The phase of the block in which an event was deposited.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Phase {
+	/// While applying the extrinsic with the given index.
+	ApplyExtrinsic(u32),
+	/// While finalizing the block.
+	Finalization,
+	/// While initializing the block.
+	Initialization,
+}
+
+/// A decoded event record, borrowing from the metadata and the encoded records.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EventRecord<'a, 'b> {
+	/// The phase of the block in which the event was deposited.
+	pub phase: Phase,
+	/// The pallet that deposited the event.
+	pub pallet: Pallet<'a>,
+	/// The event variant of the pallet's event enum.
+	pub event: &'a Variant<PortableForm>,
+	/// The decoded fields of the event.
+	pub fields: Composite,
+	/// The SCALE encoded topics of the event.
+	pub topics: Vec<&'b [u8]>,
+}
+
+/// An iterator over the event records of a block, created by [`decode_events`].
+///
+/// Decoding stops at the first invalid record, and the iterator ends after returning the
+/// error.
+#[derive(Clone, Debug)]
+pub struct Events<'a, 'b> {
+	metadata: &'a Metadata,
+	types: RecordTypes,
+	input: &'b [u8],
+	index: usize,
+	len: usize,
+	done: bool,
+}
+
+/// The types of the fields of an event record.
+#[derive(Clone, Copy, Debug)]
+struct RecordTypes {
+	phase: u32,
+	event: u32,
+	topic: u32,
+}
+
+/// Decode the value of the `System::Events` storage entry, one record at a time.
+///
+/// The events are decoded as the outer event enum of the metadata, or as the `event` field
+/// of the records for V14 metadata, which has no outer enums.
+pub fn decode_events<'a, 'b>(
+	metadata: &'a Metadata,
+	bytes: &'b [u8],
+) -> Result<Events<'a, 'b>, EventsError> {
+	let types = record_types(metadata)?;
+	let mut input = bytes;
+	let len = Compact::<u32>::decode(&mut input).map_err(|_| EventsError::InvalidLength)?;
+	Ok(Events {
+		metadata,
+		types,
+		input,
+		index: 0,
+		len: len.0 as usize,
+		done: false,
+	})
+}
+
+fn record_types(metadata: &Metadata) -> Result<RecordTypes, EventsError> {
+	let entry = metadata
+		.pallet_by_name("System")
+		.and_then(|system| system.storage_entry("Events"))
+		.ok_or(EventsError::MissingEventsEntry)?;
+	let v14::StorageEntryType::Plain(records) = entry.ty else {
+		return Err(EventsError::MissingEventsEntry);
+	};
+	let unexpected = EventsError::UnexpectedRecordType(records.id);
+
+	let types = metadata.types();
+	let record = match types.resolve(records.id).map(|ty| &ty.type_def) {
+		Some(TypeDef::Sequence(sequence)) => types.resolve(sequence.type_param.id),
+		_ => None,
+	};
+	let Some(TypeDef::Composite(record)) = record.map(|ty| &ty.type_def) else {
+		return Err(unexpected);
+	};
+	let field_ty = |name: &str| {
+		record
+			.fields
+			.iter()
+			.find(|field| field.name.as_ref().map(AsRef::<str>::as_ref) == Some(name))
+			.map(|field| field.ty.id)
+	};
+	let topics = field_ty("topics").and_then(|topics| types.resolve(topics));
+	let (Some(phase), Some(event), Some(TypeDef::Sequence(topics))) = (
+		field_ty("phase"),
+		field_ty("event"),
+		topics.map(|ty| &ty.type_def),
+	) else {
+		return Err(unexpected);
+	};
+	let event = metadata
+		.outer_enums()
+		.map_or(event, |outer_enums| outer_enums.event_enum_ty.id);
+	Ok(RecordTypes {
+		phase,
+		event,
+		topic: topics.type_param.id,
+	})
+}
+
+impl<'a, 'b> Events<'a, 'b> {
+	/// The number of records, including those already decoded.
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Whether there are no records.
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	fn invalid(&self, mut path: Vec<PathSegment>, error: DecodeError) -> EventsError {
+		path.extend(error.path);
+		EventsError::InvalidRecord {
+			index: self.index,
+			error: DecodeError {
+				path,
+				kind: error.kind,
+			},
+		}
+	}
+
+	/// Decode the value at `path` of the current record, which has the type `ty`.
+	fn value(&mut self, path: Vec<PathSegment>, ty: u32) -> Result<Value, EventsError> {
+		decode_value_from(self.metadata.types(), ty, &mut self.input)
+			.map_err(|error| self.invalid(path, error))
+	}
+
+	fn record(&mut self) -> Result<EventRecord<'a, 'b>, EventsError> {
+		let field = |name: &str| vec![PathSegment::Field(name.into())];
+		let phase = match self.value(field("phase"), self.types.phase)? {
+			Value::Variant { name, fields, .. } => match (name.as_str(), fields) {
+				("ApplyExtrinsic", Composite::Unnamed(fields)) => {
+					let index = fields.first().and_then(Value::as_u128);
+					let index = index.and_then(|index| index.try_into().ok());
+					Phase::ApplyExtrinsic(index.ok_or(EventsError::UnknownPhase(name))?)
+				}
+				("Finalization", _) => Phase::Finalization,
+				("Initialization", _) => Phase::Initialization,
+				_ => return Err(EventsError::UnknownPhase(name)),
+			},
+			_ => return Err(EventsError::UnexpectedRecordType(self.types.phase)),
+		};
+
+		let unexpected = EventsError::UnexpectedRecordType(self.types.event);
+		let (pallet_index, event) = match self.value(field("event"), self.types.event)? {
+			Value::Variant {
+				index,
+				fields: Composite::Unnamed(mut fields),
+				..
+			} if fields.len() == 1 => (index, fields.remove(0)),
+			_ => return Err(unexpected),
+		};
+		let (event_index, fields) = match event {
+			Value::Variant { index, fields, .. } => (index, fields),
+			_ => return Err(unexpected),
+		};
+		let pallet = self
+			.metadata
+			.pallet_by_index(pallet_index)
+			.ok_or(EventsError::UnknownPallet(pallet_index))?;
+		let event =
+			pallet
+				.event_by_index(event_index)
+				.ok_or_else(|| EventsError::UnknownEvent {
+					pallet: pallet.name().into(),
+					index: event_index,
+				})?;
+
+		// The topics are kept encoded, so they are only decoded to find where they end.
+		let len = Compact::<u32>::decode(&mut self.input).map_err(|_| {
+			let error = DecodeError {
+				path: Vec::new(),
+				kind: DecodeErrorKind::InvalidCompact,
+			};
+			self.invalid(field("topics"), error)
+		})?;
+		let mut topics = Vec::new();
+		for index in 0..len.0 as usize {
+			let start = self.input;
+			let mut path = field("topics");
+			path.push(PathSegment::Index(index));
+			self.value(path, self.types.topic)?;
+			topics.push(&start[..start.len() - self.input.len()]);
+		}
+
+		Ok(EventRecord {
+			phase,
+			pallet,
+			event,
+			fields,
+			topics,
+		})
+	}
+}
+
+impl<'a, 'b> Iterator for Events<'a, 'b> {
+	type Item = Result<EventRecord<'a, 'b>, EventsError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+		if self.index == self.len {
+			self.done = true;
+			return (!self.input.is_empty())
+				.then_some(Err(EventsError::TrailingBytes(self.input.len())));
+		}
+		let record = self.record();
+		self.index += 1;
+		self.done = record.is_err();
+		Some(record)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let remaining = if self.done { 0 } else { self.len - self.index };
+		(0, Some(remaining + 1))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test::{kusama_v14, kusama_v15};
+	use codec::Encode;
+
+	/// A `Balances::Transfer` of 1000 while applying the first extrinsic, with a topic, and
+	/// a `Balances::Deposit` of 5 while finalizing the block.
+	fn events() -> Vec<u8> {
+		let transfer = [
+			&[0][..],
+			&1u32.encode(),
+			&[4, 2],
+			&[1; 32],
+			&[2; 32],
+			&1000u128.encode(),
+			&Compact(1u32).encode(),
+			&[9; 32],
+		]
+		.concat();
+		let deposit = [&[1, 4, 4][..], &[2; 32], &5u128.encode(), &[0]].concat();
+		[&Compact(2u32).encode()[..], &transfer, &deposit].concat()
+	}
+
+	#[test]
+	fn should_decode_event_records() {
+		let v14 = kusama_v14();
+		let v15 = kusama_v15();
+		for metadata in [Metadata::from(v14), Metadata::from(v15)] {
+			let bytes = events();
+			let events = decode_events(&metadata, &bytes).unwrap();
+			assert_eq!(events.len(), 2);
+			let records: Vec<_> = events.collect::<Result<_, _>>().unwrap();
+
+			assert_eq!(records[0].phase, Phase::ApplyExtrinsic(1));
+			assert_eq!(records[0].pallet.name(), "Balances");
+			assert_eq!(records[0].event.name, "Transfer");
+			let Composite::Unnamed(fields) = &records[0].fields else {
+				panic!("the fields of `Transfer` should be unnamed")
+			};
+			assert_eq!(fields[2].as_u128(), Some(1000));
+			assert_eq!(records[0].topics, [&[9; 32]]);
+
+			assert_eq!(records[1].phase, Phase::Finalization);
+			assert_eq!(records[1].event.name, "Deposit");
+			assert!(records[1].topics.is_empty());
+		}
+	}
+
+	#[test]
+	fn should_stop_at_the_first_invalid_record() {
+		let metadata = Metadata::from(kusama_v14());
+
+		let mut bytes = events();
+		bytes.push(0);
+		let results: Vec<_> = decode_events(&metadata, &bytes).unwrap().collect();
+		assert_eq!(results.len(), 3);
+		assert_eq!(results[2], Err(EventsError::TrailingBytes(1)));
+
+		// The first record is cut short in its topic.
+		let bytes = events();
+		let results: Vec<_> = decode_events(&metadata, &bytes[..100]).unwrap().collect();
+		let [Err(EventsError::InvalidRecord { index: 0, error })] = &results[..] else {
+			panic!("the first record should be invalid")
+		};
+		assert_eq!(error.to_string(), "at `.topics[0][0][11]`: not enough data");
+
+		// An event of a pallet that does not exist.
+		let mut bytes = events();
+		bytes[6] = 255;
+		let mut events = decode_events(&metadata, &bytes).unwrap();
+		let error = events.next().unwrap().unwrap_err();
+		assert_eq!(
+			error.to_string(),
+			"invalid event record 0: at `.event`: no variant with index 255"
+		);
+		assert!(events.next().is_none());
+	}
+}
@@ -0,0 +1,907 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding and encoding extrinsics with the types described by the metadata.
+//!
+//! An extrinsic is encoded as its length, followed by a byte with its format version and
+//! type, the data of its type, and the call. Version 4 extrinsics are either bare, with no
+//! data, or signed, with an address, a signature and the data of every transaction extension.
+//! Version 5 extrinsics are either bare or general, with the version of their transaction
+//! extensions and the data of every extension. See
+//! [`decode_extrinsic`](crate::extrinsic::decode_extrinsic).
+//!
+//! To build an extrinsic, the call is encoded with
+//! [`encode_call`](crate::extrinsic::encode_call) and the transaction extensions with
+//! [`encode_extensions`](crate::extrinsic::encode_extensions), which both take
+//! [`Value`](crate::value::Value)s of the types described by the metadata. Signed extrinsics
+//! sign the `signer_payload` of the two, and
+//! [`encode_extrinsic`](crate::extrinsic::encode_extrinsic) puts everything together.
+
+use crate::{
+	metadata::{Metadata, Pallet, TransactionExtension, V4_EXTENSION_VERSION},
+	value::{decode_value_from, encode_value_to, Composite, DecodeError, EncodeError, Value},
+};
+use codec::{Compact, Decode, Encode};
+use scale_info::{
+	form::PortableForm,
+	prelude::{collections::BTreeMap, string::String, vec, vec::Vec},
+	PortableRegistry, Variant,
+};
+
+/// The bits of the version byte that hold the extrinsic type.
+const TYPE_MASK: u8 = 0b1100_0000;
+/// The type bits of a bare extrinsic, known as unsigned in version 4.
+const BARE: u8 = 0b0000_0000;
+/// The type bits of a signed extrinsic, which only exist in version 4.
+const SIGNED: u8 = 0b1000_0000;
+/// The type bits of a general extrinsic, which only exist from version 5.
+const GENERAL: u8 = 0b0100_0000;
+
+/// A part of an extrinsic.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ExtrinsicPart {
+	/// The address of a signed extrinsic.
+	Address,
+	/// The signature of a signed extrinsic.
+	Signature,
+	/// The version of the transaction extensions of a general extrinsic.
+	ExtensionVersion,
+	/// The data of the transaction extension with the given identifier.
+	Extension(String),
+	/// The implicit data of the transaction extension with the given identifier.
+	ExtensionImplicit(String),
+	/// The call.
+	Call,
+}
+
+/// An error that can occur when decoding an extrinsic.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ExtrinsicError {
+	/// The length prefix is invalid or does not match the length of the extrinsic.
+	InvalidLength,
+	/// The version byte, given in full, has an unknown extrinsic type or a version that the
+	/// runtime does not support.
+	UnsupportedVersion(u8),
+	/// The metadata does not describe the type of the given part of the extrinsic.
+	MissingType(ExtrinsicPart),
+	/// The extrinsic ended before the given part did.
+	NotEnoughData(ExtrinsicPart),
+	/// There is no pallet with the given index.
+	UnknownPallet(u8),
+	/// The metadata does not describe the transaction extensions of the given extension
+	/// version.
+	UnsupportedExtensionVersion(u8),
+	/// The pallet has no call with the given index.
+	UnknownCall {
+		/// The pallet name.
+		pallet: String,
+		/// The call index.
+		index: u8,
+	},
+	/// There is no pallet with the given name.
+	PalletNotFound(String),
+	/// The pallet has no call with the given name.
+	CallNotFound {
+		/// The pallet name.
+		pallet: String,
+		/// The call name.
+		call: String,
+	},
+	/// A part of the extrinsic does not decode as its type.
+	InvalidPart(ExtrinsicPart, DecodeError),
+	/// The value of a part of the extrinsic does not encode as its type.
+	InvalidValue(ExtrinsicPart, EncodeError),
+	/// The extrinsic has the given number of bytes left after the call.
+	TrailingBytes(usize),
+}
+
+impl core::fmt::Display for ExtrinsicPart {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			ExtrinsicPart::Address => write!(f, "address"),
+			ExtrinsicPart::Signature => write!(f, "signature"),
+			ExtrinsicPart::ExtensionVersion => write!(f, "transaction extension version"),
+			ExtrinsicPart::Extension(identifier) => {
+				write!(f, "transaction extension `{identifier}`")
+			}
+			ExtrinsicPart::ExtensionImplicit(identifier) => {
+				write!(f, "implicit data of transaction extension `{identifier}`")
+			}
+			ExtrinsicPart::Call => write!(f, "call"),
+		}
+	}
+}
+
+impl core::fmt::Display for ExtrinsicError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			ExtrinsicError::InvalidLength => write!(f, "invalid extrinsic length"),
+			ExtrinsicError::UnsupportedVersion(version) => {
+				write!(f, "unsupported extrinsic version byte {version:#04x}")
+			}
+			ExtrinsicError::MissingType(part) => {
+				write!(f, "the metadata does not describe the type of the {part}")
+			}
+			ExtrinsicError::NotEnoughData(part) => write!(f, "not enough data for the {part}"),
+			ExtrinsicError::UnsupportedExtensionVersion(version) => {
+				write!(f, "unsupported transaction extension version {version}")
+			}
+			ExtrinsicError::UnknownPallet(index) => write!(f, "no pallet with index {index}"),
+			ExtrinsicError::UnknownCall { pallet, index } => {
+				write!(f, "pallet `{pallet}` has no call with index {index}")
+			}
+			ExtrinsicError::PalletNotFound(name) => write!(f, "no pallet named `{name}`"),
+			ExtrinsicError::CallNotFound { pallet, call } => {
+				write!(f, "pallet `{pallet}` has no call named `{call}`")
+			}
+			ExtrinsicError::InvalidPart(part, error) => write!(f, "invalid {part}: {error}"),
+			ExtrinsicError::InvalidValue(part, error) => {
+				write!(f, "invalid value of the {part}: {error}")
+			}
+			ExtrinsicError::TrailingBytes(len) => {
+				write!(f, "{len} bytes left after the call")
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ExtrinsicError {}
+
+/// A decoded extrinsic, borrowing from the metadata and the encoded extrinsic.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DecodedExtrinsic<'a, 'b> {
+	/// The format version of the extrinsic.
+	pub version: u8,
+	/// The type of the extrinsic, with its address and signature if it is signed.
+	pub kind: ExtrinsicKind<'b>,
+	/// The transaction extensions in the order they are encoded, which are empty for bare
+	/// extrinsics.
+	pub extensions: Vec<DecodedExtension<'a, 'b>>,
+	/// The pallet of the call.
+	pub pallet: Pallet<'a>,
+	/// The call variant of the pallet's call enum.
+	pub call: &'a Variant<PortableForm>,
+	/// The SCALE encoded call, starting with the pallet index and the call index.
+	pub call_data: &'b [u8],
+	/// The decoded fields of the call.
+	pub call_fields: Composite,
+}
+
+/// The type of an extrinsic.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExtrinsicKind<'b> {
+	/// An extrinsic without any data besides the call, known as unsigned in version 4.
+	Bare,
+	/// A signed version 4 extrinsic.
+	Signed {
+		/// The SCALE encoded address of the signer.
+		address: &'b [u8],
+		/// The SCALE encoded signature.
+		signature: &'b [u8],
+	},
+	/// A general extrinsic of version 5 or later, whose transaction extensions do the
+	/// authorization.
+	General {
+		/// The version of the transaction extensions.
+		extension_version: u8,
+	},
+}
+
+/// The data of a transaction extension included in an extrinsic.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DecodedExtension<'a, 'b> {
+	/// The transaction extension.
+	pub extension: TransactionExtension<'a>,
+	/// The SCALE encoded data of the extension, of its `ty`.
+	pub data: &'b [u8],
+}
+
+/// Decode an extrinsic, prefixed by its compact encoded length as in a block body.
+///
+/// The transaction extensions are those that
+/// [`transaction_extensions_by_version`](crate::metadata::Extrinsic::transaction_extensions_by_version)
+/// returns for the extension version of a general extrinsic, or for
+/// [`V4_EXTENSION_VERSION`] if the extrinsic is signed.
+pub fn decode_extrinsic<'a, 'b>(
+	metadata: &'a Metadata,
+	bytes: &'b [u8],
+) -> Result<DecodedExtrinsic<'a, 'b>, ExtrinsicError> {
+	let mut input = bytes;
+	let len = Compact::<u32>::decode(&mut input).map_err(|_| ExtrinsicError::InvalidLength)?;
+	if len.0 as usize != input.len() {
+		return Err(ExtrinsicError::InvalidLength);
+	}
+
+	let types = metadata.types();
+	let extrinsic = metadata.extrinsic();
+	let version_byte = byte(&mut input).ok_or(ExtrinsicError::InvalidLength)?;
+	let version = version_byte & !TYPE_MASK;
+	let unsupported = ExtrinsicError::UnsupportedVersion(version_byte);
+	if !extrinsic.versions().contains(&version) {
+		return Err(unsupported);
+	}
+
+	let (kind, extensions) = match (version_byte & TYPE_MASK, version) {
+		(BARE, _) => (ExtrinsicKind::Bare, Vec::new()),
+		(SIGNED, 4) => {
+			let address_ty = extrinsic.address_ty();
+			let address = skip_part(types, address_ty, &mut input, ExtrinsicPart::Address)?;
+			let signature_ty = extrinsic.signature_ty();
+			let signature = skip_part(types, signature_ty, &mut input, ExtrinsicPart::Signature)?;
+			let extensions = extensions(metadata, V4_EXTENSION_VERSION, &mut input)?;
+			(ExtrinsicKind::Signed { address, signature }, extensions)
+		}
+		(GENERAL, 5..) => {
+			let extension_version = byte(&mut input).ok_or(ExtrinsicError::NotEnoughData(
+				ExtrinsicPart::ExtensionVersion,
+			))?;
+			let extensions = extensions(metadata, extension_version, &mut input)?;
+			(ExtrinsicKind::General { extension_version }, extensions)
+		}
+		_ => return Err(unsupported),
+	};
+
+	let call_data = input;
+	let pallet_index =
+		byte(&mut input).ok_or(ExtrinsicError::NotEnoughData(ExtrinsicPart::Call))?;
+	let pallet = metadata
+		.pallet_by_index(pallet_index)
+		.ok_or(ExtrinsicError::UnknownPallet(pallet_index))?;
+	let call_index = *input
+		.first()
+		.ok_or(ExtrinsicError::NotEnoughData(ExtrinsicPart::Call))?;
+	let call = pallet
+		.call_by_index(call_index)
+		.ok_or_else(|| ExtrinsicError::UnknownCall {
+			pallet: pallet.name().into(),
+			index: call_index,
+		})?;
+	let call_ty = pallet
+		.call_ty()
+		.ok_or(ExtrinsicError::MissingType(ExtrinsicPart::Call))?;
+	let call_fields = match decode_part(types, call_ty, &mut input, ExtrinsicPart::Call)? {
+		Value::Variant { fields, .. } => fields,
+		_ => return Err(ExtrinsicError::MissingType(ExtrinsicPart::Call)),
+	};
+	if !input.is_empty() {
+		return Err(ExtrinsicError::TrailingBytes(input.len()));
+	}
+
+	Ok(DecodedExtrinsic {
+		version,
+		kind,
+		extensions,
+		pallet,
+		call,
+		call_data,
+		call_fields,
+	})
+}
+
+/// The transaction extensions of the extension version `extension_version`.
+fn extension_set(
+	metadata: &Metadata,
+	extension_version: u8,
+) -> Result<Vec<TransactionExtension<'_>>, ExtrinsicError> {
+	metadata
+		.extrinsic()
+		.transaction_extensions_by_version(extension_version)
+		.ok_or(ExtrinsicError::UnsupportedExtensionVersion(
+			extension_version,
+		))
+}
+
+/// Split off the data of the transaction extensions of the extension version
+/// `extension_version`.
+fn extensions<'a, 'b>(
+	metadata: &'a Metadata,
+	extension_version: u8,
+	input: &mut &'b [u8],
+) -> Result<Vec<DecodedExtension<'a, 'b>>, ExtrinsicError> {
+	extension_set(metadata, extension_version)?
+		.into_iter()
+		.map(|extension| {
+			let part = ExtrinsicPart::Extension(extension.identifier.into());
+			let data = skip_part(metadata.types(), Some(extension.ty), input, part)?;
+			Ok(DecodedExtension { extension, data })
+		})
+		.collect()
+}
+
+/// Decode a value of the type `ty` from `input`, attributing errors to `part`.
+fn decode_part(
+	types: &PortableRegistry,
+	ty: u32,
+	input: &mut &[u8],
+	part: ExtrinsicPart,
+) -> Result<Value, ExtrinsicError> {
+	decode_value_from(types, ty, input).map_err(|error| ExtrinsicError::InvalidPart(part, error))
+}
+
+/// Split off a value of the type `ty` from `input`.
+fn skip_part<'b>(
+	types: &PortableRegistry,
+	ty: Option<u32>,
+	input: &mut &'b [u8],
+	part: ExtrinsicPart,
+) -> Result<&'b [u8], ExtrinsicError> {
+	let ty = ty.ok_or_else(|| ExtrinsicError::MissingType(part.clone()))?;
+	let start = *input;
+	decode_part(types, ty, input, part)?;
+	Ok(&start[..start.len() - input.len()])
+}
+
+/// Take the first byte of `input`.
+fn byte(input: &mut &[u8]) -> Option<u8> {
+	let (&byte, rest) = input.split_first()?;
+	*input = rest;
+	Some(byte)
+}
+
+/// The values of a transaction extension to encode.
+///
+/// A missing value is encoded as an empty composite, which is all that is needed for
+/// extensions whose type is `()`.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct ExtensionValues {
+	/// The value of the extension's `ty`, included in the extrinsic.
+	pub value: Option<Value>,
+	/// The value of the extension's `implicit` type, only included in the signer payload.
+	pub implicit: Option<Value>,
+}
+
+/// The encoded transaction extensions of an extrinsic.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct EncodedExtensions {
+	/// The data of every extension, in the order they are included in the extrinsic.
+	pub data: Vec<u8>,
+	/// The implicit data of every extension, in the same order.
+	pub implicit: Vec<u8>,
+}
+
+/// Encode the call `call` of the pallet `pallet` with the given arguments, starting with the
+/// pallet index and the call index.
+pub fn encode_call(
+	metadata: &Metadata,
+	pallet: &str,
+	call: &str,
+	args: &Composite,
+) -> Result<Vec<u8>, ExtrinsicError> {
+	let pallet = metadata
+		.pallet_by_name(pallet)
+		.ok_or_else(|| ExtrinsicError::PalletNotFound(pallet.into()))?;
+	let variant = pallet
+		.call_by_name(call)
+		.ok_or_else(|| ExtrinsicError::CallNotFound {
+			pallet: pallet.name().into(),
+			call: call.into(),
+		})?;
+	let call_ty = pallet
+		.call_ty()
+		.ok_or(ExtrinsicError::MissingType(ExtrinsicPart::Call))?;
+
+	let mut out = vec![pallet.index()];
+	let value = Value::Variant {
+		name: call.into(),
+		index: variant.index,
+		fields: args.clone(),
+	};
+	encode_value_to(metadata.types(), call_ty, &value, &mut out)
+		.map_err(|error| ExtrinsicError::InvalidValue(ExtrinsicPart::Call, error))?;
+	Ok(out)
+}
+
+/// Encode the transaction extensions of the extension version `extension_version`, which is
+/// [`V4_EXTENSION_VERSION`] for signed extrinsics, taking their values by identifier from
+/// `values`.
+///
+/// The extensions are the same as those [`decode_extrinsic`] expects, so extensions unknown to
+/// this crate need nothing but values.
+pub fn encode_extensions(
+	metadata: &Metadata,
+	extension_version: u8,
+	values: &BTreeMap<String, ExtensionValues>,
+) -> Result<EncodedExtensions, ExtrinsicError> {
+	let types = metadata.types();
+	let empty = Value::Composite(Composite::Unnamed(Vec::new()));
+	let mut encoded = EncodedExtensions::default();
+	for extension in extension_set(metadata, extension_version)? {
+		let identifier = extension.identifier;
+		let values = values.get(identifier);
+		let value = values.and_then(|values| values.value.as_ref());
+		encode_value_to(
+			types,
+			extension.ty,
+			value.unwrap_or(&empty),
+			&mut encoded.data,
+		)
+		.map_err(|error| {
+			ExtrinsicError::InvalidValue(ExtrinsicPart::Extension(identifier.into()), error)
+		})?;
+		let implicit = values.and_then(|values| values.implicit.as_ref());
+		encode_value_to(
+			types,
+			extension.implicit,
+			implicit.unwrap_or(&empty),
+			&mut encoded.implicit,
+		)
+		.map_err(|error| {
+			ExtrinsicError::InvalidValue(ExtrinsicPart::ExtensionImplicit(identifier.into()), error)
+		})?;
+	}
+	Ok(encoded)
+}
+
+/// The payload signed by the signer of an extrinsic: the call, followed by the data and the
+/// implicit data of the transaction extensions, which is hashed with blake2-256 if it is
+/// longer than 256 bytes.
+#[cfg(feature = "signer-payload")]
+pub fn signer_payload(call: &[u8], extensions: &EncodedExtensions) -> Vec<u8> {
+	let payload = [call, &extensions.data, &extensions.implicit].concat();
+	if payload.len() <= 256 {
+		return payload;
+	}
+	blake2b_simd::Params::new()
+		.hash_length(32)
+		.hash(&payload)
+		.as_bytes()
+		.to_vec()
+}
+
+/// Encode an extrinsic of the format version `version`, prefixed by its compact encoded
+/// length as in a block body.
+///
+/// The encoded `call` and the data of the transaction `extensions` are included as they are,
+/// as are the address and signature of a signed extrinsic. The extensions are left out of
+/// bare extrinsics.
+pub fn encode_extrinsic(
+	metadata: &Metadata,
+	version: u8,
+	kind: ExtrinsicKind<'_>,
+	extensions: &[u8],
+	call: &[u8],
+) -> Result<Vec<u8>, ExtrinsicError> {
+	let mut body = Vec::new();
+	match kind {
+		ExtrinsicKind::Bare => body.push(version | BARE),
+		ExtrinsicKind::Signed { address, signature } => {
+			body.push(version | SIGNED);
+			if version != 4 {
+				return Err(ExtrinsicError::UnsupportedVersion(body[0]));
+			}
+			body.extend_from_slice(address);
+			body.extend_from_slice(signature);
+			body.extend_from_slice(extensions);
+		}
+		ExtrinsicKind::General { extension_version } => {
+			body.push(version | GENERAL);
+			if version < 5 {
+				return Err(ExtrinsicError::UnsupportedVersion(body[0]));
+			}
+			body.push(extension_version);
+			body.extend_from_slice(extensions);
+		}
+	}
+	if version & TYPE_MASK != 0 || !metadata.extrinsic().versions().contains(&version) {
+		return Err(ExtrinsicError::UnsupportedVersion(body[0]));
+	}
+	body.extend_from_slice(call);
+
+	let len = u32::try_from(body.len()).map_err(|_| ExtrinsicError::InvalidLength)?;
+	let mut out = Compact(len).encode();
+	out.extend_from_slice(&body);
+	Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		test::{kusama_v14, kusama_v16},
+		value::Primitive,
+	};
+
+	const ALICE: [u8; 32] = [1; 32];
+	const BOB: [u8; 32] = [2; 32];
+
+	fn with_len(body: &[u8]) -> Vec<u8> {
+		[&Compact(body.len() as u32).encode()[..], body].concat()
+	}
+
+	/// `Balances::transfer_keep_alive` of 1000 to Bob, with his `MultiAddress::Id`.
+	fn transfer() -> Vec<u8> {
+		[&[4, 3, 0][..], &BOB, &Compact(1000u128).encode()].concat()
+	}
+
+	/// An immortal era, nonce 5 and no tip.
+	fn extensions() -> Vec<u8> {
+		[&[0][..], &Compact(5u32).encode(), &Compact(0u128).encode()].concat()
+	}
+
+	#[test]
+	fn should_decode_bare_and_signed_v4_extrinsics() {
+		let metadata = Metadata::from(kusama_v14());
+
+		let set_timestamp = [&[0x04, 2, 0][..], &Compact(1_000_000u64).encode()].concat();
+		let bytes = with_len(&set_timestamp);
+		let decoded = decode_extrinsic(&metadata, &bytes).unwrap();
+		assert_eq!(decoded.version, 4);
+		assert_eq!(decoded.kind, ExtrinsicKind::Bare);
+		assert!(decoded.extensions.is_empty());
+		assert_eq!(decoded.pallet.name(), "Timestamp");
+		assert_eq!(decoded.call.name, "set");
+		assert_eq!(decoded.call_data, &set_timestamp[1..]);
+
+		// `MultiAddress::Id` of Alice and a `MultiSignature::Sr25519`.
+		let address = [&[0][..], &ALICE].concat();
+		let signature = [&[1][..], &[7; 64]].concat();
+		let body = [
+			&[0x84][..],
+			&address,
+			&signature,
+			&extensions(),
+			&transfer(),
+		]
+		.concat();
+		let bytes = with_len(&body);
+		let decoded = decode_extrinsic(&metadata, &bytes).unwrap();
+		assert_eq!(
+			decoded.kind,
+			ExtrinsicKind::Signed {
+				address: &address,
+				signature: &signature
+			}
+		);
+		let extensions: Vec<_> = decoded
+			.extensions
+			.iter()
+			.map(|ext| (ext.extension.identifier, ext.data))
+			.collect();
+		assert_eq!(
+			extensions,
+			[
+				("CheckSpecVersion", &[][..]),
+				("CheckTxVersion", &[]),
+				("CheckGenesis", &[]),
+				("CheckMortality", &[0]),
+				("CheckNonce", &[20]),
+				("CheckWeight", &[]),
+				("ChargeTransactionPayment", &[0]),
+			]
+		);
+		assert_eq!(decoded.pallet.name(), "Balances");
+		assert_eq!(decoded.call.name, "transfer_keep_alive");
+		assert_eq!(decoded.call_data, transfer());
+		let Composite::Named(fields) = &decoded.call_fields else {
+			panic!("the fields of a call should be named")
+		};
+		let names: Vec<_> = fields.iter().map(|(name, _)| name.as_str()).collect();
+		assert_eq!(names, ["dest", "value"]);
+		assert_eq!(fields[1].1.as_u128(), Some(1000));
+	}
+
+	/// Kusama metadata supporting extrinsic versions 4 and 5, which keys its extension sets by
+	/// extension version as polkadot-sdk does. Extension version 1 only has `CheckMortality`,
+	/// `CheckNonce` and `ChargeTransactionPayment`.
+	fn v5_metadata() -> Metadata {
+		let mut v16 = kusama_v16();
+		v16.extrinsic.versions = vec![4, 5];
+		let all = (0..7).map(Compact).collect();
+		let some = [3, 4, 6].map(Compact).to_vec();
+		v16.extrinsic.transaction_extensions_by_version = BTreeMap::from([(0, all), (1, some)]);
+		Metadata::from(v16)
+	}
+
+	fn identifiers<'a>(decoded: &DecodedExtrinsic<'a, '_>) -> Vec<&'a str> {
+		decoded
+			.extensions
+			.iter()
+			.map(|ext| ext.extension.identifier)
+			.collect()
+	}
+
+	#[test]
+	fn should_decode_signed_v4_extrinsics_with_extension_version_0() {
+		let metadata = v5_metadata();
+
+		let address = [&[0][..], &ALICE].concat();
+		let signature = [&[1][..], &[7; 64]].concat();
+		let body = [
+			&[0x84][..],
+			&address,
+			&signature,
+			&extensions(),
+			&transfer(),
+		]
+		.concat();
+		let bytes = with_len(&body);
+		let decoded = decode_extrinsic(&metadata, &bytes).unwrap();
+		assert_eq!(decoded.version, 4);
+		assert_eq!(identifiers(&decoded).len(), 7);
+		assert_eq!(decoded.extensions[4].extension.identifier, "CheckNonce");
+		assert_eq!(decoded.extensions[4].data, &[20]);
+		assert_eq!(decoded.call_data, transfer());
+	}
+
+	#[test]
+	fn should_decode_v5_extrinsics() {
+		let metadata = v5_metadata();
+
+		let body = [&[0x45, 1][..], &extensions(), &transfer()].concat();
+		let bytes = with_len(&body);
+		let decoded = decode_extrinsic(&metadata, &bytes).unwrap();
+		assert_eq!(decoded.version, 5);
+		assert_eq!(
+			decoded.kind,
+			ExtrinsicKind::General {
+				extension_version: 1
+			}
+		);
+		assert_eq!(
+			identifiers(&decoded),
+			["CheckMortality", "CheckNonce", "ChargeTransactionPayment"]
+		);
+		assert_eq!(decoded.call.name, "transfer_keep_alive");
+
+		// The extension version selects the extensions.
+		let body = [&[0x45, 0][..], &extensions(), &transfer()].concat();
+		let bytes = with_len(&body);
+		let decoded = decode_extrinsic(&metadata, &bytes).unwrap();
+		assert_eq!(identifiers(&decoded).len(), 7);
+		let body = [&[0x45, 2][..], &extensions(), &transfer()].concat();
+		assert_eq!(
+			decode_extrinsic(&metadata, &with_len(&body)),
+			Err(ExtrinsicError::UnsupportedExtensionVersion(2))
+		);
+
+		let bare = with_len(&[&[0x05][..], &transfer()].concat());
+		let decoded = decode_extrinsic(&metadata, &bare).unwrap();
+		assert_eq!((decoded.version, decoded.kind), (5, ExtrinsicKind::Bare));
+
+		// Version 5 extrinsics cannot be signed.
+		let signed = with_len(&[&[0x85][..], &transfer()].concat());
+		assert_eq!(
+			decode_extrinsic(&metadata, &signed),
+			Err(ExtrinsicError::UnsupportedVersion(0x85))
+		);
+	}
+
+	#[test]
+	fn should_reject_invalid_extrinsics() {
+		let metadata = Metadata::from(kusama_v14());
+		let decode = |body: &[u8]| decode_extrinsic(&metadata, &with_len(body)).map(|_| ());
+
+		assert_eq!(
+			decode_extrinsic(&metadata, &[8, 4]),
+			Err(ExtrinsicError::InvalidLength)
+		);
+		assert_eq!(
+			decode(&[0x05, 4, 3]),
+			Err(ExtrinsicError::UnsupportedVersion(0x05))
+		);
+		assert_eq!(
+			decode(&[0x04, 255]),
+			Err(ExtrinsicError::UnknownPallet(255))
+		);
+		assert_eq!(
+			decode(&[0x04, 4, 255]),
+			Err(ExtrinsicError::UnknownCall {
+				pallet: "Balances".into(),
+				index: 255
+			})
+		);
+		assert_eq!(
+			decode(&[&[0x04][..], &transfer(), &[0]].concat()),
+			Err(ExtrinsicError::TrailingBytes(1))
+		);
+
+		let truncated = [&[0x84, 0][..], &ALICE, &[1], &[7; 64], &[0]].concat();
+		let Err(ExtrinsicError::InvalidPart(part, error)) = decode(&truncated) else {
+			panic!("the nonce should be missing")
+		};
+		assert_eq!(part, ExtrinsicPart::Extension("CheckNonce".into()));
+		assert_eq!(error.kind, crate::value::DecodeErrorKind::InvalidCompact);
+	}
+
+	fn bytes(bytes: &[u8]) -> Value {
+		let bytes = bytes
+			.iter()
+			.map(|byte| Value::Primitive(Primitive::U128(*byte as u128)));
+		Value::Sequence(bytes.collect())
+	}
+
+	fn unnamed(values: Vec<Value>) -> Value {
+		Value::Composite(Composite::Unnamed(values))
+	}
+
+	#[test]
+	fn should_encode_extrinsics_that_decode_again() {
+		let metadata = Metadata::from(kusama_v14());
+
+		let dest = Value::Variant {
+			name: "Id".into(),
+			index: 0,
+			fields: Composite::Unnamed(vec![unnamed(vec![bytes(&BOB)])]),
+		};
+		let args = Composite::Named(vec![
+			("dest".into(), dest),
+			("value".into(), Value::Primitive(Primitive::U128(1000))),
+		]);
+		let call = encode_call(&metadata, "Balances", "transfer_keep_alive", &args).unwrap();
+		assert_eq!(call, transfer());
+
+		let genesis = unnamed(vec![bytes(&[3; 32])]);
+		let immortal = Value::Variant {
+			name: "Immortal".into(),
+			index: 0,
+			fields: Composite::Unnamed(Vec::new()),
+		};
+		let int = |value| Value::Primitive(Primitive::U128(value));
+		let values = BTreeMap::from(
+			[
+				("CheckSpecVersion", None, Some(int(9430))),
+				("CheckTxVersion", None, Some(int(26))),
+				("CheckGenesis", None, Some(genesis.clone())),
+				(
+					"CheckMortality",
+					Some(unnamed(vec![immortal])),
+					Some(genesis),
+				),
+				("CheckNonce", Some(unnamed(vec![int(5)])), None),
+				(
+					"ChargeTransactionPayment",
+					Some(unnamed(vec![int(0)])),
+					None,
+				),
+			]
+			.map(|(identifier, value, implicit)| {
+				(identifier.into(), ExtensionValues { value, implicit })
+			}),
+		);
+		let encoded = encode_extensions(&metadata, V4_EXTENSION_VERSION, &values).unwrap();
+		assert_eq!(encoded.data, extensions());
+		let implicit = [&9430u32.encode()[..], &26u32.encode(), &[3; 32], &[3; 32]].concat();
+		assert_eq!(encoded.implicit, implicit);
+
+		#[cfg(feature = "signer-payload")]
+		{
+			let payload = signer_payload(&call, &encoded);
+			assert_eq!(payload, [&call[..], &encoded.data, &implicit].concat());
+			let remark = Composite::Named(vec![("remark".into(), bytes(&[0; 300]))]);
+			let long_call = encode_call(&metadata, "System", "remark", &remark).unwrap();
+			assert_eq!(signer_payload(&long_call, &encoded).len(), 32);
+		}
+
+		let address = [&[0][..], &ALICE].concat();
+		let signature = [&[1][..], &[7; 64]].concat();
+		let kind = ExtrinsicKind::Signed {
+			address: &address,
+			signature: &signature,
+		};
+		let extrinsic = encode_extrinsic(&metadata, 4, kind, &encoded.data, &call).unwrap();
+		let decoded = decode_extrinsic(&metadata, &extrinsic).unwrap();
+		assert_eq!(decoded.kind, kind);
+		assert_eq!(decoded.call_data, call);
+		assert_eq!(decoded.extensions[4].data, &[20]);
+
+		let bare = encode_extrinsic(&metadata, 4, ExtrinsicKind::Bare, &encoded.data, &call);
+		assert_eq!(bare, Ok(with_len(&[&[0x04][..], &call].concat())));
+	}
+
+	#[test]
+	fn should_encode_the_extensions_of_the_extension_version() {
+		let metadata = v5_metadata();
+		let immortal = Value::Variant {
+			name: "Immortal".into(),
+			index: 0,
+			fields: Composite::Unnamed(Vec::new()),
+		};
+		let int = |value| Value::Primitive(Primitive::U128(value));
+		let values = BTreeMap::from(
+			[
+				(
+					"CheckMortality",
+					Some(unnamed(vec![immortal])),
+					Some(unnamed(vec![bytes(&[3; 32])])),
+				),
+				("CheckNonce", Some(unnamed(vec![int(5)])), None),
+				(
+					"ChargeTransactionPayment",
+					Some(unnamed(vec![int(0)])),
+					None,
+				),
+			]
+			.map(|(identifier, value, implicit)| {
+				(identifier.into(), ExtensionValues { value, implicit })
+			}),
+		);
+		let encoded = encode_extensions(&metadata, 1, &values).unwrap();
+		assert_eq!(encoded.data, extensions());
+		assert_eq!(encoded.implicit, [3; 32]);
+
+		let general = ExtrinsicKind::General {
+			extension_version: 1,
+		};
+		let extrinsic =
+			encode_extrinsic(&metadata, 5, general, &encoded.data, &transfer()).unwrap();
+		let decoded = decode_extrinsic(&metadata, &extrinsic).unwrap();
+		assert_eq!(decoded.kind, general);
+		assert_eq!(identifiers(&decoded).len(), 3);
+
+		// The extensions of extension version 0 need the implicit data of `CheckSpecVersion`.
+		assert!(encode_extensions(&metadata, 0, &values).is_err());
+	}
+
+	#[test]
+	fn should_reject_invalid_values_to_encode() {
+		let metadata = Metadata::from(kusama_v14());
+		let args = Composite::Named(Vec::new());
+
+		assert_eq!(
+			encode_call(&metadata, "Nope", "transfer", &args),
+			Err(ExtrinsicError::PalletNotFound("Nope".into()))
+		);
+		assert_eq!(
+			encode_call(&metadata, "Balances", "nope", &args),
+			Err(ExtrinsicError::CallNotFound {
+				pallet: "Balances".into(),
+				call: "nope".into()
+			})
+		);
+		let remark = Composite::Named(vec![(
+			"remark".into(),
+			Value::Primitive(Primitive::Bool(true)),
+		)]);
+		let Err(ExtrinsicError::InvalidValue(ExtrinsicPart::Call, error)) =
+			encode_call(&metadata, "System", "remark", &remark)
+		else {
+			panic!("a boolean is not a sequence of bytes")
+		};
+		assert_eq!(
+			error.to_string(),
+			"at `::remark.remark`: value does not have the shape of the type with ID 10"
+		);
+
+		// Only extensions without data can be left out.
+		let Err(ExtrinsicError::InvalidValue(part, _)) =
+			encode_extensions(&metadata, V4_EXTENSION_VERSION, &BTreeMap::new())
+		else {
+			panic!("the spec version should be missing")
+		};
+		assert_eq!(
+			part,
+			ExtrinsicPart::ExtensionImplicit("CheckSpecVersion".into())
+		);
+
+		// V14 metadata only describes the extensions of signed version 4 extrinsics.
+		assert_eq!(
+			encode_extensions(&metadata, 1, &BTreeMap::new()),
+			Err(ExtrinsicError::UnsupportedExtensionVersion(1))
+		);
+
+		let general = ExtrinsicKind::General {
+			extension_version: 0,
+		};
+		assert_eq!(
+			encode_extrinsic(&metadata, 4, general, &[], &transfer()),
+			Err(ExtrinsicError::UnsupportedVersion(0x44))
+		);
+		assert_eq!(
+			encode_extrinsic(&metadata, 5, ExtrinsicKind::Bare, &[], &transfer()),
+			Err(ExtrinsicError::UnsupportedVersion(0x05))
+		);
+	}
+}
@@ -79,6 +79,76 @@ pub mod v15;
 #[cfg(feature = "current")]
 pub mod v16;
 
+/// Conversions between metadata versions.
+#[cfg(feature = "current")]
+pub mod convert;
+
+/// A version agnostic view of the metadata.
+#[cfg(feature = "current")]
+pub mod metadata;
+
+/// Structural differences between two versions of the metadata.
+#[cfg(feature = "current")]
+pub mod diff;
+
+/// Whether the changes between two versions of the metadata break existing clients.
+#[cfg(feature = "current")]
+pub mod compatibility;
+
+/// The RFC-78 metadata hash, as checked by the `CheckMetadataHash` signed extension.
+#[cfg(feature = "metadata-hash")]
+pub mod metadata_hash;
+
+/// Structural hashes of individual metadata items.
+#[cfg(feature = "shape-hash")]
+pub mod shape_hash;
+
+/// Storage keys of the storage entries described by the metadata.
+#[cfg(feature = "storage-key")]
+pub mod storage_key;
+
+/// Decoding and encoding SCALE values of the types described by the metadata.
+#[cfg(feature = "current")]
+pub mod value;
+
+/// Decoding and encoding extrinsics with the types described by the metadata.
+#[cfg(feature = "current")]
+pub mod extrinsic;
+
+/// Decoding the events of a block with the types described by the metadata.
+#[cfg(feature = "current")]
+pub mod events;
+
+/// Decoding the errors of pallets with the types described by the metadata.
+#[cfg(feature = "current")]
+pub mod dispatch_error;
+
+/// Calling runtime APIs and pallet view functions with the types described by the metadata.
+#[cfg(feature = "current")]
+pub mod runtime_api;
+
+/// Trimming the metadata down to selected items.
+#[cfg(feature = "current")]
+pub mod retain;
+
+/// Removing the documentation from the metadata, and attaching it again.
+#[cfg(feature = "current")]
+pub mod docs;
+
+/// Well-formedness checks of the metadata.
+#[cfg(feature = "current")]
+pub mod validate;
+
+/// Generating typed Rust client code from the metadata.
+#[cfg(feature = "codegen")]
+pub mod codegen;
+
+#[cfg(feature = "current")]
+mod named;
+
+#[cfg(feature = "current")]
+mod scale;
+
 /// Metadata prefix.
 pub const META_RESERVED: u32 = 0x6174656d; // 'meta' warning for endianness.
 
@@ -86,6 +156,7 @@ pub const META_RESERVED: u32 = 0x6174656d; // 'meta' warning for endianness.
 #[derive(Eq, Encode, PartialEq, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 pub struct RuntimeMetadataPrefixed(pub u32, pub RuntimeMetadata);
 
 impl From<RuntimeMetadataPrefixed> for Vec<u8> {
@@ -100,6 +171,7 @@ impl From<RuntimeMetadataPrefixed> for Vec<u8> {
 #[derive(Eq, Encode, PartialEq, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 pub enum RuntimeMetadata {
 	/// Unused; enum filler.
 	V0(RuntimeMetadataDeprecated),
@@ -227,10 +299,91 @@ mod test {
 	use super::*;
 	use std::fs;
 
-	fn load_metadata(version: u32) -> Vec<u8> {
+	pub(crate) fn load_metadata(version: u32) -> Vec<u8> {
 		fs::read(format!("./test_data/ksm_metadata_v{}.bin", version)).unwrap()
 	}
 
+	#[cfg(feature = "current")]
+	pub(crate) fn kusama_v14() -> v14::RuntimeMetadataV14 {
+		let meta: RuntimeMetadataPrefixed =
+			Decode::decode(&mut load_metadata(14).as_slice()).unwrap();
+		match meta.1 {
+			RuntimeMetadata::V14(meta) => meta,
+			_ => panic!("expected V14 metadata"),
+		}
+	}
+
+	#[cfg(feature = "current")]
+	pub(crate) fn kusama_v15() -> v15::RuntimeMetadataV15 {
+		v15::RuntimeMetadataV15::try_from(kusama_v14()).unwrap()
+	}
+
+	#[cfg(feature = "current")]
+	pub(crate) fn kusama_v16() -> v16::RuntimeMetadataV16 {
+		kusama_v15().into()
+	}
+
+	/// Kusama with an `AccountNonceApi`, whose `account_nonce` method takes the given
+	/// parameters, by name and type ID, and returns a `u32`.
+	#[cfg(feature = "current")]
+	pub(crate) fn kusama_with_account_nonce_api(inputs: &[(&str, u32)]) -> metadata::Metadata {
+		let mut v15 = kusama_v15();
+		let inputs = inputs
+			.iter()
+			.map(|&(name, ty)| v15::RuntimeApiMethodParamMetadata {
+				name: name.into(),
+				ty: ty.into(),
+			})
+			.collect();
+		v15.apis.push(v15::RuntimeApiMetadata {
+			name: "AccountNonceApi".into(),
+			methods: vec![v15::RuntimeApiMethodMetadata {
+				name: "account_nonce".into(),
+				inputs,
+				output: 4.into(),
+				docs: Vec::new(),
+			}],
+			docs: Vec::new(),
+		});
+		v15.into()
+	}
+
+	/// A recursive type, for calls whose types refer to themselves.
+	#[cfg(feature = "current")]
+	#[allow(dead_code)]
+	#[derive(scale_info::TypeInfo)]
+	pub(crate) enum Tree {
+		Leaf(u64),
+		Node(Vec<Tree>),
+	}
+
+	/// Metadata with only the given pallets, by name, index and call type.
+	#[cfg(feature = "current")]
+	pub(crate) fn metadata_with_calls(
+		pallets: &[(&'static str, u8, scale_info::MetaType)],
+	) -> metadata::Metadata {
+		use scale_info::meta_type;
+
+		let pallets = pallets
+			.iter()
+			.map(|&(name, index, calls)| v14::PalletMetadata {
+				name,
+				storage: None,
+				calls: Some(v14::PalletCallMetadata { ty: calls }),
+				event: None,
+				constants: Vec::new(),
+				error: None,
+				index,
+			})
+			.collect();
+		let extrinsic = v14::ExtrinsicMetadata {
+			ty: meta_type::<()>(),
+			version: 4,
+			signed_extensions: Vec::new(),
+		};
+		v14::RuntimeMetadataV14::new(pallets, extrinsic, meta_type::<()>()).into()
+	}
+
 	#[test]
 	fn should_decode_metadatav9() {
 		let meta: RuntimeMetadataPrefixed =
@@ -272,4 +425,28 @@ mod test {
 			Decode::decode(&mut load_metadata(14).as_slice()).unwrap();
 		assert!(matches!(meta.1, RuntimeMetadata::V14(_)));
 	}
+
+	fn assert_json_round_trip(encoded: &[u8]) {
+		let meta: RuntimeMetadataPrefixed = Decode::decode(&mut &encoded[..]).unwrap();
+		let json = serde_json::to_string(&meta).unwrap();
+		let deserialized: RuntimeMetadataPrefixed = serde_json::from_str(&json).unwrap();
+		assert_eq!(deserialized, meta);
+		assert_eq!(deserialized.encode(), encoded);
+	}
+
+	#[cfg(feature = "legacy")]
+	#[test]
+	fn should_round_trip_legacy_metadata_through_json() {
+		for version in 9..=13 {
+			assert_json_round_trip(&load_metadata(version));
+		}
+	}
+
+	#[cfg(feature = "current")]
+	#[test]
+	fn should_round_trip_metadata_through_json() {
+		assert_json_round_trip(&load_metadata(14));
+		assert_json_round_trip(&RuntimeMetadataPrefixed::from(kusama_v15()).encode());
+		assert_json_round_trip(&RuntimeMetadataPrefixed::from(kusama_v16()).encode());
+	}
 }
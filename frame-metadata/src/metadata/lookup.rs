@@ -0,0 +1,330 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Lookup tables of the [`Metadata`], which are built on first use.
+
+use super::{Metadata, Pallet};
+use core::fmt;
+use scale_info::{
+	form::PortableForm,
+	prelude::{string::String, vec::Vec},
+	Variant,
+};
+
+cfg_if::cfg_if! {
+	if #[cfg(feature = "std")] {
+		type Map<K, V> = std::collections::HashMap<K, V>;
+		type Once<T> = std::sync::OnceLock<T>;
+	} else {
+		type Map<K, V> = scale_info::prelude::collections::BTreeMap<K, V>;
+		type Once<T> = core::cell::OnceCell<T>;
+	}
+}
+
+/// A value that is built on first use.
+///
+/// It only depends on the metadata it belongs to, so it is ignored when comparing or printing
+/// the metadata or its items.
+#[derive(Clone)]
+pub(super) struct Lazy<T>(Once<T>);
+
+impl<T> Default for Lazy<T> {
+	fn default() -> Self {
+		Lazy(Once::new())
+	}
+}
+
+impl<T> Lazy<T> {
+	fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
+		self.0.get_or_init(init)
+	}
+}
+
+impl<T> PartialEq for Lazy<T> {
+	fn eq(&self, _: &Self) -> bool {
+		true
+	}
+}
+
+impl<T> Eq for Lazy<T> {}
+
+impl<T> fmt::Debug for Lazy<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("Lazy")
+	}
+}
+
+/// The positions of named, and optionally indexed, items in a list.
+///
+/// If several items have the same name or index, the first one is found.
+#[derive(Clone, Default)]
+pub(super) struct Positions {
+	by_name: Map<String, usize>,
+	by_index: Map<u8, usize>,
+}
+
+impl Positions {
+	fn new<'b>(items: impl Iterator<Item = (&'b str, Option<u8>)>) -> Self {
+		let mut positions = Positions::default();
+		for (position, (name, index)) in items.enumerate() {
+			positions.by_name.entry(name.into()).or_insert(position);
+			if let Some(index) = index {
+				positions.by_index.entry(index).or_insert(position);
+			}
+		}
+		positions
+	}
+
+	fn variants(variants: &[Variant<PortableForm>]) -> Self {
+		Positions::new(
+			variants
+				.iter()
+				.map(|variant| (AsRef::<str>::as_ref(&variant.name), Some(variant.index))),
+		)
+	}
+
+	pub(super) fn by_name(&self, name: &str) -> Option<usize> {
+		self.by_name.get(name).copied()
+	}
+
+	pub(super) fn by_index(&self, index: u8) -> Option<usize> {
+		self.by_index.get(&index).copied()
+	}
+}
+
+/// The lookup tables of the items of a pallet.
+#[derive(Clone)]
+pub(super) struct PalletLookup {
+	pub(super) calls: Positions,
+	pub(super) events: Positions,
+	pub(super) errors: Positions,
+	pub(super) storage: Positions,
+	pub(super) constants: Positions,
+}
+
+impl Lazy<PalletLookup> {
+	pub(super) fn get(&self, pallet: &Pallet) -> &PalletLookup {
+		self.get_or_init(|| PalletLookup {
+			calls: Positions::variants(pallet.calls()),
+			events: Positions::variants(pallet.events()),
+			errors: Positions::variants(pallet.errors()),
+			storage: Positions::new(pallet.storage_entries().map(|entry| (entry.name, None))),
+			constants: Positions::new(pallet.constants().map(|constant| (constant.name, None))),
+		})
+	}
+}
+
+/// The lookup tables of a [`Metadata`].
+#[derive(Clone, Default, PartialEq, Eq)]
+pub(super) struct Lookup {
+	pallets: Lazy<Positions>,
+	/// The lookup tables of the items of each pallet, in the order of the pallets.
+	pallet_items: Vec<Lazy<PalletLookup>>,
+	runtime_apis: Lazy<Positions>,
+	/// The API and method positions of the runtime API methods by their `Trait_method` name.
+	runtime_api_methods: Lazy<Map<String, (usize, usize)>>,
+	/// The pallet and view function positions of the view functions by their ID.
+	view_functions: Lazy<Map<[u8; 32], (usize, usize)>>,
+}
+
+impl Lookup {
+	/// Create the lookup tables of a metadata with the given number of pallets.
+	pub(super) fn new(pallets: usize) -> Self {
+		Lookup {
+			pallet_items: (0..pallets).map(|_| Lazy::default()).collect(),
+			..Lookup::default()
+		}
+	}
+
+	pub(super) fn pallets(&self, metadata: &Metadata) -> &Positions {
+		self.pallets.get_or_init(|| {
+			Positions::new(
+				metadata
+					.pallets()
+					.map(|pallet| (pallet.name(), Some(pallet.index()))),
+			)
+		})
+	}
+
+	/// The lookup tables of the pallet at the given position.
+	pub(super) fn pallet_items(&self, position: usize) -> Option<&Lazy<PalletLookup>> {
+		self.pallet_items.get(position)
+	}
+
+	pub(super) fn runtime_apis(&self, metadata: &Metadata) -> &Positions {
+		self.runtime_apis
+			.get_or_init(|| Positions::new(metadata.runtime_apis().map(|api| (api.name(), None))))
+	}
+
+	pub(super) fn runtime_api_method(
+		&self,
+		metadata: &Metadata,
+		name: &str,
+	) -> Option<(usize, usize)> {
+		let methods = self.runtime_api_methods.get_or_init(|| {
+			let mut methods = Map::default();
+			for (api_position, api) in metadata.runtime_apis().enumerate() {
+				for (position, method) in api.methods().enumerate() {
+					let name = [api.name(), method.name].join("_");
+					methods.entry(name).or_insert((api_position, position));
+				}
+			}
+			methods
+		});
+		methods.get(name).copied()
+	}
+
+	pub(super) fn view_function(
+		&self,
+		metadata: &Metadata,
+		id: &[u8; 32],
+	) -> Option<(usize, usize)> {
+		let view_functions = self.view_functions.get_or_init(|| {
+			let mut view_functions = Map::default();
+			for (pallet_position, pallet) in metadata.pallets().enumerate() {
+				for (position, function) in pallet.view_functions().iter().enumerate() {
+					view_functions
+						.entry(function.id)
+						.or_insert((pallet_position, position));
+				}
+			}
+			view_functions
+		});
+		view_functions.get(id).copied()
+	}
+}
+
+impl fmt::Debug for Lookup {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("Lookup")
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{
+		metadata::Metadata,
+		test::{kusama_v14, kusama_v15, kusama_v16},
+		v16,
+	};
+	use codec::Compact;
+	use scale_info::prelude::vec;
+
+	#[test]
+	fn should_find_pallet_items_by_name_and_index() {
+		let v14 = kusama_v14();
+		let v15 = kusama_v15();
+		let v16 = kusama_v16();
+
+		for metadata in [Metadata::from(v14), v15.into(), v16.into()] {
+			for pallet in metadata.pallets() {
+				assert_eq!(metadata.pallet_by_name(pallet.name()), Some(pallet));
+				assert_eq!(metadata.pallet_by_index(pallet.index()), Some(pallet));
+
+				for call in pallet.calls() {
+					assert_eq!(pallet.call_by_name(&call.name), Some(call));
+					assert_eq!(pallet.call_by_index(call.index), Some(call));
+				}
+				for event in pallet.events() {
+					assert_eq!(pallet.event_by_name(&event.name), Some(event));
+					assert_eq!(pallet.event_by_index(event.index), Some(event));
+				}
+				for error in pallet.errors() {
+					assert_eq!(pallet.error_by_name(&error.name), Some(error));
+					assert_eq!(pallet.error_by_index(error.index), Some(error));
+				}
+				for entry in pallet.storage_entries() {
+					assert_eq!(pallet.storage_entry(entry.name), Some(entry));
+				}
+				for constant in pallet.constants() {
+					assert_eq!(pallet.constant(constant.name), Some(constant));
+				}
+			}
+			assert_eq!(metadata.pallet_by_name("NotAPallet"), None);
+			assert_eq!(metadata.pallet_by_index(255), None);
+
+			let balances = metadata.pallet_by_name("Balances").unwrap();
+			assert_eq!(balances.call_by_name("not_a_call"), None);
+			assert_eq!(balances.call_by_index(255), None);
+			assert_eq!(balances.storage_entry("NotAnEntry"), None);
+		}
+	}
+
+	#[test]
+	fn should_find_runtime_api_methods_and_view_functions() {
+		let mut v16 = kusama_v16();
+		let method = |name: &str| v16::RuntimeApiMethodMetadata {
+			name: name.into(),
+			inputs: vec![],
+			output: 0.into(),
+			docs: vec![],
+			deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+		};
+		let api = |name: &str, methods| v16::RuntimeApiMetadata {
+			name: name.into(),
+			methods,
+			docs: vec![],
+			version: Compact(1),
+			deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+		};
+		v16.apis = vec![
+			api("Core", vec![method("version"), method("execute_block")]),
+			api("Metadata", vec![method("metadata_at_version")]),
+		];
+		v16.pallets[1]
+			.view_functions
+			.push(v16::PalletViewFunctionMetadata {
+				id: [1; 32],
+				name: "query".into(),
+				inputs: vec![],
+				output: 0.into(),
+				docs: vec![],
+				deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+			});
+		let metadata = Metadata::from(v16);
+
+		let (api, method) = metadata
+			.runtime_api_method("Metadata_metadata_at_version")
+			.unwrap();
+		assert_eq!(
+			(api.name(), method.name),
+			("Metadata", "metadata_at_version")
+		);
+		let (api, method) = metadata.runtime_api_method("Core_execute_block").unwrap();
+		assert_eq!((api.name(), method.name), ("Core", "execute_block"));
+		assert!(metadata
+			.runtime_api_method("Core_metadata_at_version")
+			.is_none());
+		assert_eq!(
+			metadata
+				.runtime_api_by_name("Metadata")
+				.map(|api| api.name()),
+			Some("Metadata")
+		);
+
+		let (pallet, function) = metadata.view_function_by_id(&[1; 32]).unwrap();
+		assert_eq!(pallet, metadata.pallets().nth(1).unwrap());
+		assert_eq!(function.name, "query");
+		assert!(metadata.view_function_by_id(&[2; 32]).is_none());
+	}
+
+	#[test]
+	fn should_ignore_lookup_tables_when_comparing() {
+		let metadata = Metadata::from(kusama_v14());
+		let copy = metadata.clone();
+		assert!(metadata.pallet_by_name("System").is_some());
+		assert_eq!(metadata, copy);
+		assert_eq!(metadata.pallets().next(), copy.pallets().next());
+	}
+}
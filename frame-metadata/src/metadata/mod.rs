@@ -0,0 +1,1013 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A read-only view of the metadata that is the same for every supported version.
+//!
+//! [`Metadata`](crate::metadata::Metadata) wraps decoded V14, V15 or V16 metadata and exposes
+//! its pallets, runtime APIs and extrinsic information through a single API. Information that
+//! an older version does not describe is reported as empty, or as `None` where emptiness would
+//! be ambiguous.
+//!
+//! Lookups of items by name or index use lookup tables that are built on first use, so only the
+//! first lookup of a kind scans the metadata.
+
+mod lookup;
+
+use crate::{v14, v15, v16, RuntimeMetadata, RuntimeMetadataPrefixed, META_RESERVED};
+use core::convert::Infallible;
+use lookup::{Lazy, Lookup, PalletLookup};
+use scale_info::{
+	form::{Form, PortableForm},
+	prelude::vec::Vec,
+	PortableRegistry, TypeDef, Variant,
+};
+
+/// The string type of the metadata, which depends on the enabled features of `scale-info`.
+type String = <PortableForm as Form>::String;
+
+/// The transaction extension version of signed version 4 extrinsics, which is the only one
+/// that V14 and V15 metadata describe.
+pub const V4_EXTENSION_VERSION: u8 = 0;
+
+/// Decoded metadata of one of the versions supported by [`Metadata`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Versioned<A, B, C> {
+	V14(A),
+	V15(B),
+	V16(C),
+}
+
+impl<T, A, B, C> Iterator for Versioned<A, B, C>
+where
+	A: Iterator<Item = T>,
+	B: Iterator<Item = T>,
+	C: Iterator<Item = T>,
+{
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		match self {
+			Versioned::V14(iter) => iter.next(),
+			Versioned::V15(iter) => iter.next(),
+			Versioned::V16(iter) => iter.next(),
+		}
+	}
+}
+
+/// An error that can occur when constructing [`Metadata`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MetadataError {
+	/// The metadata prefix is not [`META_RESERVED`].
+	InvalidPrefix(u32),
+	/// The metadata version is not supported, only V14 and later are.
+	UnsupportedVersion(u32),
+}
+
+impl core::fmt::Display for MetadataError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			MetadataError::InvalidPrefix(prefix) => {
+				write!(f, "invalid metadata prefix {prefix:#010x}")
+			}
+			MetadataError::UnsupportedVersion(version) => {
+				write!(f, "metadata version {version} is not supported")
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MetadataError {}
+
+/// Runtime metadata of any version from V14 onwards.
+///
+/// Without the `std` feature, the lookup tables built on first use are not thread safe, and
+/// `Metadata` is not `Sync`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Metadata {
+	inner: Versioned<v14::RuntimeMetadataV14, v15::RuntimeMetadataV15, v16::RuntimeMetadataV16>,
+	lookup: Lookup,
+}
+
+impl From<v14::RuntimeMetadataV14> for Metadata {
+	fn from(metadata: v14::RuntimeMetadataV14) -> Self {
+		Metadata {
+			lookup: Lookup::new(metadata.pallets.len()),
+			inner: Versioned::V14(metadata),
+		}
+	}
+}
+
+impl From<v15::RuntimeMetadataV15> for Metadata {
+	fn from(metadata: v15::RuntimeMetadataV15) -> Self {
+		Metadata {
+			lookup: Lookup::new(metadata.pallets.len()),
+			inner: Versioned::V15(metadata),
+		}
+	}
+}
+
+impl From<v16::RuntimeMetadataV16> for Metadata {
+	fn from(metadata: v16::RuntimeMetadataV16) -> Self {
+		Metadata {
+			lookup: Lookup::new(metadata.pallets.len()),
+			inner: Versioned::V16(metadata),
+		}
+	}
+}
+
+impl TryFrom<RuntimeMetadata> for Metadata {
+	type Error = MetadataError;
+
+	fn try_from(metadata: RuntimeMetadata) -> Result<Self, Self::Error> {
+		match metadata {
+			RuntimeMetadata::V14(metadata) => Ok(metadata.into()),
+			RuntimeMetadata::V15(metadata) => Ok(metadata.into()),
+			RuntimeMetadata::V16(metadata) => Ok(metadata.into()),
+			other => Err(MetadataError::UnsupportedVersion(other.version())),
+		}
+	}
+}
+
+impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
+	type Error = MetadataError;
+
+	fn try_from(metadata: RuntimeMetadataPrefixed) -> Result<Self, Self::Error> {
+		if metadata.0 != META_RESERVED {
+			return Err(MetadataError::InvalidPrefix(metadata.0));
+		}
+		metadata.1.try_into()
+	}
+}
+
+impl From<Metadata> for RuntimeMetadata {
+	fn from(metadata: Metadata) -> Self {
+		match metadata.inner {
+			Versioned::V14(metadata) => RuntimeMetadata::V14(metadata),
+			Versioned::V15(metadata) => RuntimeMetadata::V15(metadata),
+			Versioned::V16(metadata) => RuntimeMetadata::V16(metadata),
+		}
+	}
+}
+
+impl Metadata {
+	/// The version of the underlying metadata.
+	pub fn version(&self) -> u32 {
+		match self.inner {
+			Versioned::V14(_) => 14,
+			Versioned::V15(_) => 15,
+			Versioned::V16(_) => 16,
+		}
+	}
+
+	/// The underlying V14 metadata, if this is V14 metadata.
+	pub fn as_v14(&self) -> Option<&v14::RuntimeMetadataV14> {
+		match &self.inner {
+			Versioned::V14(metadata) => Some(metadata),
+			_ => None,
+		}
+	}
+
+	/// The underlying V15 metadata, if this is V15 metadata.
+	pub fn as_v15(&self) -> Option<&v15::RuntimeMetadataV15> {
+		match &self.inner {
+			Versioned::V15(metadata) => Some(metadata),
+			_ => None,
+		}
+	}
+
+	/// The underlying V16 metadata, if this is V16 metadata.
+	pub fn as_v16(&self) -> Option<&v16::RuntimeMetadataV16> {
+		match &self.inner {
+			Versioned::V16(metadata) => Some(metadata),
+			_ => None,
+		}
+	}
+
+	/// The type registry that all type IDs of the metadata refer to.
+	pub fn types(&self) -> &PortableRegistry {
+		match &self.inner {
+			Versioned::V14(metadata) => &metadata.types,
+			Versioned::V15(metadata) => &metadata.types,
+			Versioned::V16(metadata) => &metadata.types,
+		}
+	}
+
+	/// The pallets, in the order they are declared in the runtime.
+	pub fn pallets(&self) -> impl Iterator<Item = Pallet<'_>> + '_ {
+		let count = match &self.inner {
+			Versioned::V14(metadata) => metadata.pallets.len(),
+			Versioned::V15(metadata) => metadata.pallets.len(),
+			Versioned::V16(metadata) => metadata.pallets.len(),
+		};
+		(0..count).filter_map(|position| self.pallet_at(position))
+	}
+
+	/// The pallet with the given name.
+	pub fn pallet_by_name(&self, name: &str) -> Option<Pallet<'_>> {
+		self.pallet_at(self.lookup.pallets(self).by_name(name)?)
+	}
+
+	/// The pallet with the given index.
+	pub fn pallet_by_index(&self, index: u8) -> Option<Pallet<'_>> {
+		self.pallet_at(self.lookup.pallets(self).by_index(index)?)
+	}
+
+	fn pallet_at(&self, position: usize) -> Option<Pallet<'_>> {
+		let inner = match &self.inner {
+			Versioned::V14(metadata) => Versioned::V14(metadata.pallets.get(position)?),
+			Versioned::V15(metadata) => Versioned::V15(metadata.pallets.get(position)?),
+			Versioned::V16(metadata) => Versioned::V16(metadata.pallets.get(position)?),
+		};
+		Some(Pallet {
+			types: self.types(),
+			inner,
+			lookup: self.lookup.pallet_items(position)?,
+		})
+	}
+
+	/// The view function with the given ID and the pallet it belongs to. View functions are
+	/// only part of V16 metadata.
+	pub fn view_function_by_id(
+		&self,
+		id: &[u8; 32],
+	) -> Option<(Pallet<'_>, &v16::PalletViewFunctionMetadata<PortableForm>)> {
+		let (pallet, position) = self.lookup.view_function(self, id)?;
+		let pallet = self.pallet_at(pallet)?;
+		Some((pallet, pallet.view_functions().get(position)?))
+	}
+
+	/// The runtime APIs, which are not part of V14 metadata.
+	pub fn runtime_apis(&self) -> impl Iterator<Item = RuntimeApi<'_>> + '_ {
+		match &self.inner {
+			Versioned::V14(_) => Versioned::V14(core::iter::empty()),
+			Versioned::V15(metadata) => {
+				Versioned::V15(metadata.apis.iter().map(|api| RuntimeApi {
+					inner: Versioned::V15(api),
+				}))
+			}
+			Versioned::V16(metadata) => {
+				Versioned::V16(metadata.apis.iter().map(|api| RuntimeApi {
+					inner: Versioned::V16(api),
+				}))
+			}
+		}
+	}
+
+	/// The runtime API with the given trait name.
+	pub fn runtime_api_by_name(&self, name: &str) -> Option<RuntimeApi<'_>> {
+		self.runtime_api_at(self.lookup.runtime_apis(self).by_name(name)?)
+	}
+
+	/// The runtime API method with the given `Trait_method` name, which is the name it is
+	/// called by, together with its runtime API.
+	pub fn runtime_api_method(&self, name: &str) -> Option<(RuntimeApi<'_>, RuntimeApiMethod<'_>)> {
+		let (api, method) = self.lookup.runtime_api_method(self, name)?;
+		let api = self.runtime_api_at(api)?;
+		Some((api, api.method_at(method)?))
+	}
+
+	fn runtime_api_at(&self, position: usize) -> Option<RuntimeApi<'_>> {
+		let inner = match &self.inner {
+			Versioned::V14(_) => return None,
+			Versioned::V15(metadata) => Versioned::V15(metadata.apis.get(position)?),
+			Versioned::V16(metadata) => Versioned::V16(metadata.apis.get(position)?),
+		};
+		Some(RuntimeApi { inner })
+	}
+
+	/// Information about the extrinsic format of the runtime.
+	pub fn extrinsic(&self) -> Extrinsic<'_> {
+		let inner = match &self.inner {
+			Versioned::V14(metadata) => Versioned::V14(&metadata.extrinsic),
+			Versioned::V15(metadata) => Versioned::V15(&metadata.extrinsic),
+			Versioned::V16(metadata) => Versioned::V16(&metadata.extrinsic),
+		};
+		Extrinsic {
+			types: self.types(),
+			inner,
+		}
+	}
+
+	/// The outer enums of the runtime, which are not part of V14 metadata.
+	pub fn outer_enums(&self) -> Option<&v15::OuterEnums<PortableForm>> {
+		match &self.inner {
+			Versioned::V14(_) => None,
+			Versioned::V15(metadata) => Some(&metadata.outer_enums),
+			Versioned::V16(metadata) => Some(&metadata.outer_enums),
+		}
+	}
+
+	/// The custom values, which are not part of V14 metadata.
+	pub fn custom(&self) -> Option<&v15::CustomMetadata<PortableForm>> {
+		match &self.inner {
+			Versioned::V14(_) => None,
+			Versioned::V15(metadata) => Some(&metadata.custom),
+			Versioned::V16(metadata) => Some(&metadata.custom),
+		}
+	}
+}
+
+/// The variants of the variant type `ty`, or none if it is not a variant type.
+fn variants(types: &PortableRegistry, ty: Option<u32>) -> &[Variant<PortableForm>] {
+	let ty = ty.and_then(|ty| types.resolve(ty));
+	match ty.map(|ty| &ty.type_def) {
+		Some(TypeDef::Variant(def)) => &def.variants,
+		_ => &[],
+	}
+}
+
+/// The metadata of a pallet.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Pallet<'a> {
+	types: &'a PortableRegistry,
+	inner: Versioned<
+		&'a v14::PalletMetadata<PortableForm>,
+		&'a v15::PalletMetadata<PortableForm>,
+		&'a v16::PalletMetadata<PortableForm>,
+	>,
+	lookup: &'a Lazy<PalletLookup>,
+}
+
+impl<'a> Pallet<'a> {
+	/// The pallet name.
+	pub fn name(&self) -> &'a str {
+		match self.inner {
+			Versioned::V14(pallet) => AsRef::<str>::as_ref(&pallet.name),
+			Versioned::V15(pallet) => AsRef::<str>::as_ref(&pallet.name),
+			Versioned::V16(pallet) => AsRef::<str>::as_ref(&pallet.name),
+		}
+	}
+
+	/// The pallet index, used to encode its calls, events and errors.
+	pub fn index(&self) -> u8 {
+		match self.inner {
+			Versioned::V14(pallet) => pallet.index,
+			Versioned::V15(pallet) => pallet.index,
+			Versioned::V16(pallet) => pallet.index,
+		}
+	}
+
+	/// The pallet documentation, which is not part of V14 metadata.
+	pub fn docs(&self) -> &'a [String] {
+		match self.inner {
+			Versioned::V14(_) => &[],
+			Versioned::V15(pallet) => &pallet.docs,
+			Versioned::V16(pallet) => &pallet.docs,
+		}
+	}
+
+	/// The deprecation information of the pallet, which is only part of V16 metadata.
+	pub fn deprecation_info(&self) -> Option<&'a v16::ItemDeprecationInfo<PortableForm>> {
+		match self.inner {
+			Versioned::V16(pallet) => Some(&pallet.deprecation_info),
+			_ => None,
+		}
+	}
+
+	/// The type ID of the call enum of the pallet.
+	pub fn call_ty(&self) -> Option<u32> {
+		match self.inner {
+			Versioned::V14(pallet) => pallet.calls.as_ref().map(|calls| calls.ty.id),
+			Versioned::V15(pallet) => pallet.calls.as_ref().map(|calls| calls.ty.id),
+			Versioned::V16(pallet) => pallet.calls.as_ref().map(|calls| calls.ty.id),
+		}
+	}
+
+	/// The type ID of the event enum of the pallet.
+	pub fn event_ty(&self) -> Option<u32> {
+		match self.inner {
+			Versioned::V14(pallet) => pallet.event.as_ref().map(|event| event.ty.id),
+			Versioned::V15(pallet) => pallet.event.as_ref().map(|event| event.ty.id),
+			Versioned::V16(pallet) => pallet.event.as_ref().map(|event| event.ty.id),
+		}
+	}
+
+	/// The type ID of the error enum of the pallet.
+	pub fn error_ty(&self) -> Option<u32> {
+		match self.inner {
+			Versioned::V14(pallet) => pallet.error.as_ref().map(|error| error.ty.id),
+			Versioned::V15(pallet) => pallet.error.as_ref().map(|error| error.ty.id),
+			Versioned::V16(pallet) => pallet.error.as_ref().map(|error| error.ty.id),
+		}
+	}
+
+	/// The calls of the pallet, as the variants of its call enum.
+	pub fn calls(&self) -> &'a [Variant<PortableForm>] {
+		variants(self.types, self.call_ty())
+	}
+
+	/// The call with the given name.
+	pub fn call_by_name(&self, name: &str) -> Option<&'a Variant<PortableForm>> {
+		self.calls().get(self.lookup().calls.by_name(name)?)
+	}
+
+	/// The call with the given variant index.
+	pub fn call_by_index(&self, index: u8) -> Option<&'a Variant<PortableForm>> {
+		self.calls().get(self.lookup().calls.by_index(index)?)
+	}
+
+	/// The events of the pallet, as the variants of its event enum.
+	pub fn events(&self) -> &'a [Variant<PortableForm>] {
+		variants(self.types, self.event_ty())
+	}
+
+	/// The event with the given name.
+	pub fn event_by_name(&self, name: &str) -> Option<&'a Variant<PortableForm>> {
+		self.events().get(self.lookup().events.by_name(name)?)
+	}
+
+	/// The event with the given variant index.
+	pub fn event_by_index(&self, index: u8) -> Option<&'a Variant<PortableForm>> {
+		self.events().get(self.lookup().events.by_index(index)?)
+	}
+
+	/// The errors of the pallet, as the variants of its error enum.
+	pub fn errors(&self) -> &'a [Variant<PortableForm>] {
+		variants(self.types, self.error_ty())
+	}
+
+	/// The error with the given name.
+	pub fn error_by_name(&self, name: &str) -> Option<&'a Variant<PortableForm>> {
+		self.errors().get(self.lookup().errors.by_name(name)?)
+	}
+
+	/// The error with the given variant index.
+	pub fn error_by_index(&self, index: u8) -> Option<&'a Variant<PortableForm>> {
+		self.errors().get(self.lookup().errors.by_index(index)?)
+	}
+
+	/// The prefix of the storage entries of the pallet, if it has storage.
+	pub fn storage_prefix(&self) -> Option<&'a str> {
+		match self.inner {
+			Versioned::V14(pallet) => pallet
+				.storage
+				.as_ref()
+				.map(|s| AsRef::<str>::as_ref(&s.prefix)),
+			Versioned::V15(pallet) => pallet
+				.storage
+				.as_ref()
+				.map(|s| AsRef::<str>::as_ref(&s.prefix)),
+			Versioned::V16(pallet) => pallet
+				.storage
+				.as_ref()
+				.map(|s| AsRef::<str>::as_ref(&s.prefix)),
+		}
+	}
+
+	/// The storage entries of the pallet.
+	pub fn storage_entries(&self) -> impl Iterator<Item = StorageEntry<'a>> {
+		match self.inner {
+			Versioned::V14(pallet) => Versioned::V14(
+				pallet
+					.storage
+					.iter()
+					.flat_map(|s| &s.entries)
+					.map(StorageEntry::from_v14),
+			),
+			Versioned::V15(pallet) => Versioned::V15(
+				pallet
+					.storage
+					.iter()
+					.flat_map(|s| &s.entries)
+					.map(StorageEntry::from_v14),
+			),
+			Versioned::V16(pallet) => Versioned::V16(
+				pallet
+					.storage
+					.iter()
+					.flat_map(|s| &s.entries)
+					.map(StorageEntry::from_v16),
+			),
+		}
+	}
+
+	/// The storage entry with the given name.
+	pub fn storage_entry(&self, name: &str) -> Option<StorageEntry<'a>> {
+		let position = self.lookup().storage.by_name(name)?;
+		match self.inner {
+			Versioned::V14(pallet) => pallet
+				.storage
+				.as_ref()?
+				.entries
+				.get(position)
+				.map(StorageEntry::from_v14),
+			Versioned::V15(pallet) => pallet
+				.storage
+				.as_ref()?
+				.entries
+				.get(position)
+				.map(StorageEntry::from_v14),
+			Versioned::V16(pallet) => pallet
+				.storage
+				.as_ref()?
+				.entries
+				.get(position)
+				.map(StorageEntry::from_v16),
+		}
+	}
+
+	/// The constants of the pallet.
+	pub fn constants(&self) -> impl Iterator<Item = Constant<'a>> {
+		match self.inner {
+			Versioned::V14(pallet) => {
+				Versioned::V14(pallet.constants.iter().map(Constant::from_v14))
+			}
+			Versioned::V15(pallet) => {
+				Versioned::V15(pallet.constants.iter().map(Constant::from_v14))
+			}
+			Versioned::V16(pallet) => {
+				Versioned::V16(pallet.constants.iter().map(Constant::from_v16))
+			}
+		}
+	}
+
+	/// The constant with the given name.
+	pub fn constant(&self, name: &str) -> Option<Constant<'a>> {
+		let position = self.lookup().constants.by_name(name)?;
+		match self.inner {
+			Versioned::V14(pallet) => pallet.constants.get(position).map(Constant::from_v14),
+			Versioned::V15(pallet) => pallet.constants.get(position).map(Constant::from_v14),
+			Versioned::V16(pallet) => pallet.constants.get(position).map(Constant::from_v16),
+		}
+	}
+
+	/// The associated types of the pallet's `Config` trait, which are only part of V16
+	/// metadata.
+	pub fn associated_types(&self) -> &'a [v16::PalletAssociatedTypeMetadata<PortableForm>] {
+		match self.inner {
+			Versioned::V16(pallet) => &pallet.associated_types,
+			_ => &[],
+		}
+	}
+
+	/// The view functions of the pallet, which are only part of V16 metadata.
+	pub fn view_functions(&self) -> &'a [v16::PalletViewFunctionMetadata<PortableForm>] {
+		match self.inner {
+			Versioned::V16(pallet) => &pallet.view_functions,
+			_ => &[],
+		}
+	}
+
+	fn lookup(&self) -> &'a PalletLookup {
+		self.lookup.get(self)
+	}
+}
+
+/// The metadata of a storage entry.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StorageEntry<'a> {
+	/// The name of the storage entry.
+	pub name: &'a str,
+	/// Whether the entry has a default value or is optional.
+	pub modifier: &'a v14::StorageEntryModifier,
+	/// The key hashers, key type and value type of the entry.
+	pub ty: &'a v14::StorageEntryType<PortableForm>,
+	/// The SCALE encoded default value.
+	pub default: &'a [u8],
+	/// The documentation of the entry.
+	pub docs: &'a [String],
+	/// The deprecation information of the entry, which is only part of V16 metadata.
+	pub deprecation_info: Option<&'a v16::ItemDeprecationInfo<PortableForm>>,
+}
+
+impl<'a> StorageEntry<'a> {
+	fn from_v14(entry: &'a v14::StorageEntryMetadata<PortableForm>) -> Self {
+		StorageEntry {
+			name: AsRef::<str>::as_ref(&entry.name),
+			modifier: &entry.modifier,
+			ty: &entry.ty,
+			default: &entry.default,
+			docs: &entry.docs,
+			deprecation_info: None,
+		}
+	}
+
+	fn from_v16(entry: &'a v16::StorageEntryMetadata<PortableForm>) -> Self {
+		StorageEntry {
+			name: AsRef::<str>::as_ref(&entry.name),
+			modifier: &entry.modifier,
+			ty: &entry.ty,
+			default: &entry.default,
+			docs: &entry.docs,
+			deprecation_info: Some(&entry.deprecation_info),
+		}
+	}
+}
+
+/// The metadata of a pallet constant.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Constant<'a> {
+	/// The name of the constant.
+	pub name: &'a str,
+	/// The type ID of the constant.
+	pub ty: u32,
+	/// The SCALE encoded value of the constant.
+	pub value: &'a [u8],
+	/// The documentation of the constant.
+	pub docs: &'a [String],
+	/// The deprecation information of the constant, which is only part of V16 metadata.
+	pub deprecation_info: Option<&'a v16::ItemDeprecationInfo<PortableForm>>,
+}
+
+impl<'a> Constant<'a> {
+	fn from_v14(constant: &'a v14::PalletConstantMetadata<PortableForm>) -> Self {
+		Constant {
+			name: AsRef::<str>::as_ref(&constant.name),
+			ty: constant.ty.id,
+			value: &constant.value,
+			docs: &constant.docs,
+			deprecation_info: None,
+		}
+	}
+
+	fn from_v16(constant: &'a v16::PalletConstantMetadata<PortableForm>) -> Self {
+		Constant {
+			name: AsRef::<str>::as_ref(&constant.name),
+			ty: constant.ty.id,
+			value: &constant.value,
+			docs: &constant.docs,
+			deprecation_info: Some(&constant.deprecation_info),
+		}
+	}
+}
+
+/// The metadata of a runtime API.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RuntimeApi<'a> {
+	inner: Versioned<
+		Infallible,
+		&'a v15::RuntimeApiMetadata<PortableForm>,
+		&'a v16::RuntimeApiMetadata<PortableForm>,
+	>,
+}
+
+impl<'a> RuntimeApi<'a> {
+	/// The name of the runtime API trait.
+	pub fn name(&self) -> &'a str {
+		match self.inner {
+			Versioned::V14(never) => match never {},
+			Versioned::V15(api) => AsRef::<str>::as_ref(&api.name),
+			Versioned::V16(api) => AsRef::<str>::as_ref(&api.name),
+		}
+	}
+
+	/// The documentation of the runtime API.
+	pub fn docs(&self) -> &'a [String] {
+		match self.inner {
+			Versioned::V14(never) => match never {},
+			Versioned::V15(api) => &api.docs,
+			Versioned::V16(api) => &api.docs,
+		}
+	}
+
+	/// The version of the runtime API, which is only part of V16 metadata.
+	pub fn version(&self) -> Option<u32> {
+		match self.inner {
+			Versioned::V16(api) => Some(api.version.0),
+			_ => None,
+		}
+	}
+
+	/// The deprecation information of the runtime API, which is only part of V16 metadata.
+	pub fn deprecation_info(&self) -> Option<&'a v16::ItemDeprecationInfo<PortableForm>> {
+		match self.inner {
+			Versioned::V16(api) => Some(&api.deprecation_info),
+			_ => None,
+		}
+	}
+
+	/// The methods of the runtime API.
+	pub fn methods(&self) -> impl Iterator<Item = RuntimeApiMethod<'a>> {
+		let api = *self;
+		let count = match self.inner {
+			Versioned::V14(never) => match never {},
+			Versioned::V15(api) => api.methods.len(),
+			Versioned::V16(api) => api.methods.len(),
+		};
+		(0..count).filter_map(move |position| api.method_at(position))
+	}
+
+	fn method_at(&self, position: usize) -> Option<RuntimeApiMethod<'a>> {
+		let method = match self.inner {
+			Versioned::V14(never) => match never {},
+			Versioned::V15(api) => {
+				let method = api.methods.get(position)?;
+				RuntimeApiMethod {
+					name: AsRef::<str>::as_ref(&method.name),
+					inputs: method
+						.inputs
+						.iter()
+						.map(|input| (AsRef::<str>::as_ref(&input.name), input.ty.id))
+						.collect(),
+					output: method.output.id,
+					docs: &method.docs,
+					deprecation_info: None,
+				}
+			}
+			Versioned::V16(api) => {
+				let method = api.methods.get(position)?;
+				RuntimeApiMethod {
+					name: AsRef::<str>::as_ref(&method.name),
+					inputs: method
+						.inputs
+						.iter()
+						.map(|input| (AsRef::<str>::as_ref(&input.name), input.ty.id))
+						.collect(),
+					output: method.output.id,
+					docs: &method.docs,
+					deprecation_info: Some(&method.deprecation_info),
+				}
+			}
+		};
+		Some(method)
+	}
+
+	/// The method with the given name.
+	pub fn method_by_name(&self, name: &str) -> Option<RuntimeApiMethod<'a>> {
+		self.methods().find(|method| method.name == name)
+	}
+}
+
+/// The metadata of a runtime API method.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RuntimeApiMethod<'a> {
+	/// The name of the method.
+	pub name: &'a str,
+	/// The names and type IDs of the method parameters.
+	pub inputs: Vec<(&'a str, u32)>,
+	/// The type ID of the method output.
+	pub output: u32,
+	/// The documentation of the method.
+	pub docs: &'a [String],
+	/// The deprecation information of the method, which is only part of V16 metadata.
+	pub deprecation_info: Option<&'a v16::ItemDeprecationInfo<PortableForm>>,
+}
+
+/// Information about the extrinsic format of the runtime.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Extrinsic<'a> {
+	types: &'a PortableRegistry,
+	inner: Versioned<
+		&'a v14::ExtrinsicMetadata<PortableForm>,
+		&'a v15::ExtrinsicMetadata<PortableForm>,
+		&'a v16::ExtrinsicMetadata<PortableForm>,
+	>,
+}
+
+impl<'a> Extrinsic<'a> {
+	/// The extrinsic versions supported by the runtime.
+	pub fn versions(&self) -> &'a [u8] {
+		match self.inner {
+			Versioned::V14(extrinsic) => core::slice::from_ref(&extrinsic.version),
+			Versioned::V15(extrinsic) => core::slice::from_ref(&extrinsic.version),
+			Versioned::V16(extrinsic) => &extrinsic.versions,
+		}
+	}
+
+	/// The type ID of the address of a signed extrinsic.
+	///
+	/// V14 metadata only describes this as a type parameter of the extrinsic type, and `None`
+	/// is returned if there is no such parameter.
+	pub fn address_ty(&self) -> Option<u32> {
+		self.extrinsic_ty("Address", |e| e.address_ty.id, |e| e.address_ty.id)
+	}
+
+	/// The type ID of the runtime call enum.
+	///
+	/// V14 metadata only describes this as a type parameter of the extrinsic type, and `None`
+	/// is returned if there is no such parameter.
+	pub fn call_ty(&self) -> Option<u32> {
+		self.extrinsic_ty("Call", |e| e.call_ty.id, |e| e.call_ty.id)
+	}
+
+	/// The type ID of the signature of a signed extrinsic.
+	///
+	/// V14 metadata only describes this as a type parameter of the extrinsic type, and `None`
+	/// is returned if there is no such parameter.
+	pub fn signature_ty(&self) -> Option<u32> {
+		self.extrinsic_ty("Signature", |e| e.signature_ty.id, |e| e.signature_ty.id)
+	}
+
+	fn extrinsic_ty(
+		&self,
+		name: &'static str,
+		v15: impl FnOnce(&v15::ExtrinsicMetadata<PortableForm>) -> u32,
+		v16: impl FnOnce(&v16::ExtrinsicMetadata<PortableForm>) -> u32,
+	) -> Option<u32> {
+		match self.inner {
+			Versioned::V14(extrinsic) => {
+				crate::convert::extrinsic_type_param(self.types, extrinsic.ty.id, name).ok()
+			}
+			Versioned::V15(extrinsic) => Some(v15(extrinsic)),
+			Versioned::V16(extrinsic) => Some(v16(extrinsic)),
+		}
+	}
+
+	/// All transaction extensions, also known as signed extensions, declared by the runtime.
+	pub fn transaction_extensions(&self) -> impl Iterator<Item = TransactionExtension<'a>> {
+		match self.inner {
+			Versioned::V14(extrinsic) => Versioned::V14(extrinsic.signed_extensions.iter().map(
+				|ext| TransactionExtension {
+					identifier: AsRef::<str>::as_ref(&ext.identifier),
+					ty: ext.ty.id,
+					implicit: ext.additional_signed.id,
+				},
+			)),
+			Versioned::V15(extrinsic) => Versioned::V15(extrinsic.signed_extensions.iter().map(
+				|ext| TransactionExtension {
+					identifier: AsRef::<str>::as_ref(&ext.identifier),
+					ty: ext.ty.id,
+					implicit: ext.additional_signed.id,
+				},
+			)),
+			Versioned::V16(extrinsic) => {
+				Versioned::V16(extrinsic.transaction_extensions.iter().map(|ext| {
+					TransactionExtension {
+						identifier: AsRef::<str>::as_ref(&ext.identifier),
+						ty: ext.ty.id,
+						implicit: ext.implicit.id,
+					}
+				}))
+			}
+		}
+	}
+
+	/// The transaction extension versions described by the metadata, which is only
+	/// [`V4_EXTENSION_VERSION`] before V16.
+	pub fn transaction_extension_versions(&self) -> impl Iterator<Item = u8> + 'a {
+		match self.inner {
+			Versioned::V14(_) => Versioned::V14(core::iter::once(V4_EXTENSION_VERSION)),
+			Versioned::V15(_) => Versioned::V15(core::iter::once(V4_EXTENSION_VERSION)),
+			Versioned::V16(extrinsic) => {
+				Versioned::V16(extrinsic.transaction_extensions_by_version.keys().copied())
+			}
+		}
+	}
+
+	/// The transaction extensions of the given transaction extension version, in the order they
+	/// are encoded, or `None` if the extension version is not described.
+	///
+	/// General extrinsics select their extension version, while signed version 4 extrinsics
+	/// always use [`V4_EXTENSION_VERSION`]. V14 and V15 metadata only describe that version.
+	/// Extension indexes that are out of range in V16 metadata are skipped.
+	pub fn transaction_extensions_by_version(
+		&self,
+		extension_version: u8,
+	) -> Option<Vec<TransactionExtension<'a>>> {
+		let Versioned::V16(extrinsic) = self.inner else {
+			return (extension_version == V4_EXTENSION_VERSION)
+				.then(|| self.transaction_extensions().collect());
+		};
+		let indexes = extrinsic
+			.transaction_extensions_by_version
+			.get(&extension_version)?;
+		let all: Vec<_> = self.transaction_extensions().collect();
+		let extensions = indexes
+			.iter()
+			.filter_map(|index| all.get(index.0 as usize).copied())
+			.collect();
+		Some(extensions)
+	}
+}
+
+/// The metadata of a transaction extension.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TransactionExtension<'a> {
+	/// The unique identifier of the extension.
+	pub identifier: &'a str,
+	/// The type ID of the data included in the extrinsic.
+	pub ty: u32,
+	/// The type ID of the implicit data, known as `additional_signed` before V16, which is
+	/// only included in the signed payload.
+	pub implicit: u32,
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test::{kusama_v14, kusama_v15, kusama_v16, load_metadata};
+	use codec::Decode;
+
+	fn kusama() -> [Metadata; 3] {
+		let v14 = kusama_v14();
+		let v15 = kusama_v15();
+		let v16 = kusama_v16();
+		[v14.into(), v15.into(), v16.into()]
+	}
+
+	#[test]
+	fn should_expose_pallets_of_every_version() {
+		let v14 = kusama_v14();
+		for metadata in kusama() {
+			assert_eq!(metadata.pallets().count(), v14.pallets.len());
+
+			let balances = metadata.pallet_by_name("Balances").unwrap();
+			assert_eq!(metadata.pallet_by_index(balances.index()), Some(balances));
+			assert!(balances.call_by_name("transfer_keep_alive").is_some());
+			assert!(balances.event_by_name("Transfer").is_some());
+			assert!(balances.error_by_name("InsufficientBalance").is_some());
+			assert_eq!(balances.storage_prefix(), Some("Balances"));
+
+			let total_issuance = balances.storage_entry("TotalIssuance").unwrap();
+			assert!(matches!(total_issuance.ty, v14::StorageEntryType::Plain(_)));
+			assert_eq!(
+				total_issuance.deprecation_info.is_some(),
+				metadata.version() == 16
+			);
+
+			let existential_deposit = balances.constant("ExistentialDeposit").unwrap();
+			assert_eq!(existential_deposit.value.len(), 16);
+
+			assert!(metadata.pallet_by_name("NotAPallet").is_none());
+		}
+	}
+
+	#[test]
+	fn should_expose_extrinsic_of_every_version() {
+		let v14 = kusama_v14();
+		for metadata in kusama() {
+			let extrinsic = metadata.extrinsic();
+			assert_eq!(extrinsic.versions(), [4]);
+			assert!(extrinsic.address_ty().is_some());
+			assert!(extrinsic.signature_ty().is_some());
+
+			let call_ty = extrinsic.call_ty().unwrap();
+			let call = metadata.types().resolve(call_ty).unwrap();
+			assert_eq!(call.path.ident().as_deref(), Some("Call"));
+
+			let extensions = extrinsic
+				.transaction_extensions_by_version(V4_EXTENSION_VERSION)
+				.unwrap();
+			let identifiers: Vec<_> = extensions.iter().map(|ext| ext.identifier).collect();
+			let expected: Vec<_> = v14
+				.extrinsic
+				.signed_extensions
+				.iter()
+				.map(|ext| ext.identifier.as_str())
+				.collect();
+			assert_eq!(identifiers, expected);
+			assert_eq!(extrinsic.transaction_extensions_by_version(1), None);
+		}
+	}
+
+	#[test]
+	fn should_expose_runtime_apis() {
+		let [v14, _, v16] = kusama();
+		assert_eq!(v14.runtime_apis().count(), 0);
+		assert!(v14.outer_enums().is_none());
+
+		let mut v16 = v16.as_v16().unwrap().clone();
+		v16.apis.push(v16::RuntimeApiMetadata {
+			name: "Core".into(),
+			methods: vec![v16::RuntimeApiMethodMetadata {
+				name: "version".into(),
+				inputs: Vec::new(),
+				output: 0.into(),
+				docs: Vec::new(),
+				deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+			}],
+			docs: Vec::new(),
+			version: codec::Compact(5),
+			deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+		});
+		let metadata = Metadata::from(v16);
+
+		let core = metadata.runtime_api_by_name("Core").unwrap();
+		assert_eq!(core.version(), Some(5));
+		let version = core.method_by_name("version").unwrap();
+		assert!(version.inputs.is_empty());
+		assert_eq!(version.output, 0);
+		assert!(metadata.outer_enums().is_some());
+	}
+
+	#[test]
+	fn should_reject_unsupported_metadata() {
+		let prefixed = RuntimeMetadataPrefixed::from(kusama_v14());
+		assert!(Metadata::try_from(prefixed).is_ok());
+		let mut prefixed = RuntimeMetadataPrefixed::from(kusama_v14());
+		prefixed.0 = 0;
+		assert_eq!(
+			Metadata::try_from(prefixed),
+			Err(MetadataError::InvalidPrefix(0))
+		);
+
+		let legacy = RuntimeMetadataPrefixed::decode(&mut load_metadata(13).as_slice()).unwrap();
+		assert_eq!(
+			Metadata::try_from(legacy),
+			Err(MetadataError::UnsupportedVersion(13))
+		);
+	}
+}
@@ -0,0 +1,459 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Proofs of the types needed to decode an extrinsic.
+
+use codec::{Compact, Decode};
+use core::ops::Range;
+use scale_info::prelude::collections::{BTreeMap, BTreeSet};
+
+use crate::scale::MAX_DEPTH;
+
+use super::{
+	merkle::MerkleTree, MetadataHashError, Proof, Type, TypeDef, TypeInformation, TypeRef,
+	EXTRINSIC_VERSION,
+};
+
+/// The signed extension data of a signed extrinsic.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SignedExtrinsicData<'a> {
+	/// The encoded data of all signed extensions that is included in the extrinsic.
+	pub included_in_extrinsic: &'a [u8],
+	/// The encoded data of all signed extensions that is only included in the signed payload.
+	pub included_in_signed_data: &'a [u8],
+}
+
+impl TypeInformation {
+	/// Build a proof of the types needed to decode the given extrinsic, and optionally the
+	/// `additional_signed` data of its signed extensions.
+	///
+	/// The extrinsic is encoded with its length prefix, and must be a version 4 extrinsic.
+	pub fn proof_for_extrinsic(
+		&self,
+		mut extrinsic: &[u8],
+		additional_signed: Option<&[u8]>,
+	) -> Result<Proof, MetadataHashError> {
+		let mut decoder = Decoder::new(self);
+		let input = &mut extrinsic;
+		let metadata = &self.extrinsic_metadata;
+
+		let length = Compact::<u32>::decode(input)
+			.map_err(|_| MetadataHashError::InvalidExtrinsicData("length"))?;
+		if length.0 as usize != input.len() {
+			return Err(MetadataHashError::InvalidExtrinsicData("length"));
+		}
+		let version =
+			u8::decode(input).map_err(|_| MetadataHashError::InvalidExtrinsicData("version"))?;
+		if version & 0b0111_1111 != EXTRINSIC_VERSION {
+			return Err(MetadataHashError::UnsupportedExtrinsicVersion(
+				version & 0b0111_1111,
+			));
+		}
+
+		if version & 0b1000_0000 != 0 {
+			decoder.decode_part("address", metadata.address_ty, input)?;
+			decoder.decode_part("signature", metadata.signature_ty, input)?;
+			for ext in &metadata.signed_extensions {
+				decoder.decode_part("signed extensions", ext.included_in_extrinsic, input)?;
+			}
+		}
+		decoder.decode_part("call", metadata.call_ty, input)?;
+		if !input.is_empty() {
+			return Err(MetadataHashError::TrailingBytes("call"));
+		}
+
+		if let Some(mut additional_signed) = additional_signed {
+			let input = &mut additional_signed;
+			for ext in &metadata.signed_extensions {
+				decoder.decode_part(
+					"additional signed data",
+					ext.included_in_signed_data,
+					input,
+				)?;
+			}
+			if !input.is_empty() {
+				return Err(MetadataHashError::TrailingBytes("additional signed data"));
+			}
+		}
+
+		Ok(self.proof(&decoder.accessed))
+	}
+
+	/// Build a proof of the types needed to decode an extrinsic with the given call data.
+	///
+	/// If the extrinsic is signed, `signed_data` is the data of its signed extensions, and all
+	/// types needed to decode any address and signature are part of the proof as well. This
+	/// makes the proof larger than one built with [`TypeInformation::proof_for_extrinsic`].
+	pub fn proof_for_extrinsic_parts(
+		&self,
+		mut call: &[u8],
+		signed_data: Option<SignedExtrinsicData<'_>>,
+	) -> Result<Proof, MetadataHashError> {
+		let mut decoder = Decoder::new(self);
+		let metadata = &self.extrinsic_metadata;
+
+		decoder.decode_part("call", metadata.call_ty, &mut call)?;
+		if !call.is_empty() {
+			return Err(MetadataHashError::TrailingBytes("call"));
+		}
+
+		if let Some(SignedExtrinsicData {
+			mut included_in_extrinsic,
+			mut included_in_signed_data,
+		}) = signed_data
+		{
+			decoder.collect_all(metadata.address_ty);
+			decoder.collect_all(metadata.signature_ty);
+			for ext in &metadata.signed_extensions {
+				decoder.decode_part(
+					"signed extensions",
+					ext.included_in_extrinsic,
+					&mut included_in_extrinsic,
+				)?;
+				decoder.decode_part(
+					"additional signed data",
+					ext.included_in_signed_data,
+					&mut included_in_signed_data,
+				)?;
+			}
+			if !included_in_extrinsic.is_empty() {
+				return Err(MetadataHashError::TrailingBytes("signed extensions"));
+			}
+			if !included_in_signed_data.is_empty() {
+				return Err(MetadataHashError::TrailingBytes("additional signed data"));
+			}
+		}
+
+		Ok(self.proof(&decoder.accessed))
+	}
+
+	/// Build a proof for the types at the given positions.
+	fn proof(&self, leaves: &BTreeSet<usize>) -> Proof {
+		MerkleTree::new(self.types.iter().map(Type::hash)).proof(leaves, &self.types)
+	}
+}
+
+/// Decodes data with the type information, recording the types it needs.
+struct Decoder<'a> {
+	info: &'a TypeInformation,
+	/// The positions of the types of every type ID, which are consecutive.
+	ranges: BTreeMap<u32, Range<usize>>,
+	/// The positions of the types needed so far.
+	accessed: BTreeSet<usize>,
+	depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+	fn new(info: &'a TypeInformation) -> Self {
+		let mut ranges = BTreeMap::<u32, Range<usize>>::new();
+		for (position, ty) in info.types.iter().enumerate() {
+			ranges
+				.entry(ty.type_id.0)
+				.and_modify(|range| range.end = position + 1)
+				.or_insert(position..position + 1);
+		}
+		Decoder {
+			info,
+			ranges,
+			accessed: BTreeSet::new(),
+			depth: 0,
+		}
+	}
+
+	fn decode_part(
+		&mut self,
+		part: &'static str,
+		ty: TypeRef,
+		input: &mut &[u8],
+	) -> Result<(), MetadataHashError> {
+		self.decode(ty, input)
+			.ok_or(MetadataHashError::InvalidExtrinsicData(part))
+	}
+
+	/// Decode a value of the given type, returning `None` if the data is invalid.
+	fn decode(&mut self, ty: TypeRef, input: &mut &[u8]) -> Option<()> {
+		let id = match ty {
+			TypeRef::Bool | TypeRef::U8 | TypeRef::I8 => return skip(input, 1),
+			TypeRef::U16 | TypeRef::I16 => return skip(input, 2),
+			TypeRef::Char | TypeRef::U32 | TypeRef::I32 => return skip(input, 4),
+			TypeRef::U64 | TypeRef::I64 => return skip(input, 8),
+			TypeRef::U128 | TypeRef::I128 => return skip(input, 16),
+			TypeRef::U256 | TypeRef::I256 => return skip(input, 32),
+			TypeRef::Str => {
+				let len = Compact::<u32>::decode(input).ok()?.0;
+				return skip(input, len as usize);
+			}
+			TypeRef::CompactU8
+			| TypeRef::CompactU16
+			| TypeRef::CompactU32
+			| TypeRef::CompactU64
+			| TypeRef::CompactU128
+			| TypeRef::CompactU256 => return skip_compact(input),
+			TypeRef::Void => return Some(()),
+			TypeRef::ById(id) => id.0,
+		};
+
+		if self.depth == MAX_DEPTH {
+			return None;
+		}
+		self.depth += 1;
+		let result = self.decode_by_id(id, input);
+		self.depth -= 1;
+		result
+	}
+
+	/// Decode a value of the type with the given ID, recording the types needed.
+	fn decode_by_id(&mut self, id: u32, input: &mut &[u8]) -> Option<()> {
+		let types = &self.info.types[..];
+		let range = self.ranges.get(&id)?.clone();
+		match &types[range.start].type_def {
+			TypeDef::Enumeration(_) => {
+				let index = u8::decode(input).ok()?;
+				let (position, variant) =
+					range
+						.clone()
+						.find_map(|position| match &types[position].type_def {
+							TypeDef::Enumeration(variant)
+								if variant.index.0 == u32::from(index) =>
+							{
+								Some((position, variant))
+							}
+							_ => None,
+						})?;
+				self.accessed.insert(position);
+				variant
+					.fields
+					.iter()
+					.try_for_each(|field| self.decode(field.ty, input))
+			}
+			TypeDef::Composite(fields) => {
+				self.accessed.insert(range.start);
+				fields
+					.iter()
+					.try_for_each(|field| self.decode(field.ty, input))
+			}
+			TypeDef::Tuple(fields) => {
+				self.accessed.insert(range.start);
+				fields.iter().try_for_each(|ty| self.decode(*ty, input))
+			}
+			TypeDef::Sequence(ty) => {
+				self.accessed.insert(range.start);
+				let len = Compact::<u32>::decode(input).ok()?.0;
+				self.decode_items(*ty, len, input)
+			}
+			TypeDef::Array(array) => {
+				self.accessed.insert(range.start);
+				self.decode_items(array.type_param, array.len, input)
+			}
+			TypeDef::BitSequence(bits) => {
+				self.accessed.insert(range.start);
+				let len = Compact::<u32>::decode(input).ok()?.0 as usize;
+				let store_bits = usize::from(bits.num_bytes) * 8;
+				if store_bits == 0 {
+					return None;
+				}
+				skip(
+					input,
+					len.div_ceil(store_bits) * usize::from(bits.num_bytes),
+				)
+			}
+		}
+	}
+
+	/// Decode `len` values of the given type.
+	fn decode_items(&mut self, ty: TypeRef, len: u32, input: &mut &[u8]) -> Option<()> {
+		for _ in 0..len {
+			let remaining = input.len();
+			self.decode(ty, input)?;
+			// Values that take no bytes are all the same, so there is no need to decode the rest.
+			if input.len() == remaining {
+				break;
+			}
+		}
+		Some(())
+	}
+
+	/// Record all types reachable from the given type.
+	fn collect_all(&mut self, ty: TypeRef) {
+		let TypeRef::ById(id) = ty else {
+			return;
+		};
+		let Some(range) = self.ranges.get(&id.0).cloned() else {
+			return;
+		};
+		for position in range {
+			if !self.accessed.insert(position) {
+				continue;
+			}
+			match &self.info.types[position].type_def {
+				TypeDef::Composite(fields) => fields.iter().for_each(|f| self.collect_all(f.ty)),
+				TypeDef::Enumeration(variant) => {
+					variant.fields.iter().for_each(|f| self.collect_all(f.ty))
+				}
+				TypeDef::Sequence(ty) => self.collect_all(*ty),
+				TypeDef::Array(array) => self.collect_all(array.type_param),
+				TypeDef::Tuple(fields) => fields.iter().for_each(|ty| self.collect_all(*ty)),
+				TypeDef::BitSequence(_) => {}
+			}
+		}
+	}
+}
+
+/// Skip `len` bytes of the input.
+fn skip(input: &mut &[u8], len: usize) -> Option<()> {
+	*input = input.get(len..)?;
+	Some(())
+}
+
+/// Skip a compact encoded integer.
+fn skip_compact(input: &mut &[u8]) -> Option<()> {
+	let first = *input.first()?;
+	let len = match first & 0b11 {
+		0b00 => 1,
+		0b01 => 2,
+		0b10 => 4,
+		_ => usize::from(first >> 2) + 5,
+	};
+	skip(input, len)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{metadata::Metadata, test::kusama_v14};
+	use codec::Encode;
+	use scale_info::prelude::vec::Vec;
+
+	/// `System::remark(b"abc")`.
+	const CALL: &[u8] = &[0, 1, 3 << 2, b'a', b'b', b'c'];
+
+	fn kusama() -> TypeInformation {
+		TypeInformation::new(&Metadata::from(kusama_v14())).unwrap()
+	}
+
+	/// The data of the signed extensions of Kusama: an immortal era, a nonce and a tip.
+	fn included_in_extrinsic() -> Vec<u8> {
+		vec![0, 0, 0]
+	}
+
+	/// The spec and transaction versions, the genesis hash and the block hash.
+	fn additional_signed() -> Vec<u8> {
+		(1u32, 2u32, [0u8; 32], [0u8; 32]).encode()
+	}
+
+	fn signed_extrinsic(call: &[u8]) -> Vec<u8> {
+		let mut body = vec![0b1000_0100];
+		// `MultiAddress::Id` and an sr25519 `MultiSignature`.
+		body.extend((0u8, [1u8; 32], 1u8, [2u8; 64]).encode());
+		body.extend(included_in_extrinsic());
+		body.extend(call);
+		with_length(body)
+	}
+
+	fn with_length(body: Vec<u8>) -> Vec<u8> {
+		let mut extrinsic = Compact(body.len() as u32).encode();
+		extrinsic.extend(body);
+		extrinsic
+	}
+
+	fn leaf_names(proof: &Proof, path: &str) -> Vec<String> {
+		proof
+			.leaves
+			.iter()
+			.filter(|ty| ty.path.last().is_some_and(|name| name == path))
+			.filter_map(|ty| match &ty.type_def {
+				TypeDef::Enumeration(variant) => Some(variant.name.clone()),
+				_ => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn should_prove_the_types_of_a_signed_extrinsic() {
+		let info = kusama();
+		let extrinsic = signed_extrinsic(CALL);
+		let proof = info
+			.proof_for_extrinsic(&extrinsic, Some(&additional_signed()))
+			.unwrap();
+
+		assert!(proof.verify(&info.root()));
+		assert!(proof.leaves.len() < 20);
+		assert_eq!(leaf_names(&proof, "MultiAddress"), ["Id"]);
+		assert_eq!(leaf_names(&proof, "MultiSignature"), ["Sr25519"]);
+		assert!(leaf_names(&proof, "Call").contains(&"remark".into()));
+
+		// The types of the proof are enough to decode the extrinsic again.
+		let proven = TypeInformation {
+			extrinsic_metadata: info.extrinsic_metadata.clone(),
+			types: proof.leaves.clone(),
+		};
+		assert!(proven
+			.proof_for_extrinsic(&extrinsic, Some(&additional_signed()))
+			.is_ok());
+	}
+
+	#[test]
+	fn should_prove_all_address_and_signature_types_for_extrinsic_parts() {
+		let info = kusama();
+		let signed_data = SignedExtrinsicData {
+			included_in_extrinsic: &included_in_extrinsic(),
+			included_in_signed_data: &additional_signed(),
+		};
+		let proof = info
+			.proof_for_extrinsic_parts(CALL, Some(signed_data))
+			.unwrap();
+
+		assert!(proof.verify(&info.root()));
+		assert_eq!(
+			leaf_names(&proof, "MultiAddress"),
+			["Id", "Index", "Raw", "Address32", "Address20"]
+		);
+
+		let unsigned = info.proof_for_extrinsic_parts(CALL, None).unwrap();
+		assert!(unsigned.verify(&info.root()));
+		assert!(leaf_names(&unsigned, "MultiAddress").is_empty());
+	}
+
+	#[test]
+	fn should_reject_invalid_extrinsics() {
+		let info = kusama();
+
+		let mut call = CALL.to_vec();
+		call.push(0);
+		assert_eq!(
+			info.proof_for_extrinsic(&signed_extrinsic(&call), None),
+			Err(MetadataHashError::TrailingBytes("call"))
+		);
+
+		// `System` has no call with index 255.
+		assert_eq!(
+			info.proof_for_extrinsic(&signed_extrinsic(&[0, 255]), None),
+			Err(MetadataHashError::InvalidExtrinsicData("call"))
+		);
+
+		let unsigned_v5 = with_length([&[5], CALL].concat());
+		assert_eq!(
+			info.proof_for_extrinsic(&unsigned_v5, None),
+			Err(MetadataHashError::UnsupportedExtrinsicVersion(5))
+		);
+
+		assert_eq!(
+			info.proof_for_extrinsic(&signed_extrinsic(CALL), Some(&[0; 4])),
+			Err(MetadataHashError::InvalidExtrinsicData(
+				"additional signed data"
+			))
+		);
+	}
+}
@@ -0,0 +1,265 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The merkle tree built over the type information.
+
+#[cfg(feature = "decode")]
+use codec::Decode;
+use codec::Encode;
+use scale_info::prelude::{collections::BTreeSet, vec, vec::Vec};
+
+use super::{blake3, Hash, MetadataHashError, Type};
+
+/// A proof that some types are part of the type information with a given merkle root.
+///
+/// The nodes of the merkle tree are numbered level by level, starting with `0` for the root,
+/// so the children of node `i` are the nodes `2 * i + 1` and `2 * i + 2`.
+#[derive(Clone, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub struct Proof {
+	/// The proven types, from the leftmost leaf of the tree to the rightmost.
+	pub leaves: Vec<Type>,
+	/// The node indexes of the `leaves`, in the same order.
+	pub leaf_indices: Vec<u32>,
+	/// The hashes of the nodes that are needed to compute the root but cannot be computed from
+	/// the `leaves`, from left to right.
+	pub nodes: Vec<Hash>,
+}
+
+impl Proof {
+	/// Compute the merkle root from the proof.
+	///
+	/// Fails if the proof does not contain exactly the nodes needed to compute the root, or if
+	/// a leaf is below another leaf.
+	pub fn root(&self) -> Result<Hash, MetadataHashError> {
+		if self.leaves.len() != self.leaf_indices.len() {
+			return Err(MetadataHashError::InvalidProof);
+		}
+
+		let mut nodes = self.nodes.iter();
+		let mut leaves_used = 0;
+		let root = self
+			.node(0, &mut nodes, &mut leaves_used)
+			.ok_or(MetadataHashError::InvalidProof)?;
+
+		if nodes.next().is_some() || leaves_used != self.leaves.len() {
+			return Err(MetadataHashError::InvalidProof);
+		}
+		Ok(root)
+	}
+
+	/// Whether the proof is valid for the given merkle root of the type information.
+	pub fn verify(&self, types_tree_root: &Hash) -> bool {
+		self.root().is_ok_and(|root| root == *types_tree_root)
+	}
+
+	/// Compute the hash of the node with the given index, taking the hashes of the nodes above
+	/// no leaf from `nodes`.
+	fn node<'a>(
+		&self,
+		index: u64,
+		nodes: &mut impl Iterator<Item = &'a Hash>,
+		leaves_used: &mut usize,
+	) -> Option<Hash> {
+		if let Some(position) = self
+			.leaf_indices
+			.iter()
+			.position(|leaf| u64::from(*leaf) == index)
+		{
+			*leaves_used += 1;
+			return Some(self.leaves[position].hash());
+		}
+
+		if self
+			.leaf_indices
+			.iter()
+			.any(|leaf| is_ancestor(index, u64::from(*leaf)))
+		{
+			let left = self.node(2 * index + 1, nodes, leaves_used)?;
+			let right = self.node(2 * index + 2, nodes, leaves_used)?;
+			return Some(node_hash(&left, &right));
+		}
+
+		nodes.next().copied()
+	}
+}
+
+/// A complete binary tree with the hashes of the types as its leaves.
+///
+/// The nodes are stored level by level, so the children of node `i` are the nodes `2 * i + 1`
+/// and `2 * i + 2`, and the leaves are the last nodes, in order.
+pub(super) struct MerkleTree {
+	nodes: Vec<Hash>,
+}
+
+impl MerkleTree {
+	/// Build the tree over the given leaves.
+	pub(super) fn new(leaves: impl IntoIterator<Item = Hash>) -> Self {
+		let leaves: Vec<Hash> = leaves.into_iter().collect();
+		let inner_nodes = leaves.len().saturating_sub(1);
+
+		let mut nodes = vec![Hash::default(); inner_nodes];
+		nodes.extend(leaves);
+		for index in (0..inner_nodes).rev() {
+			nodes[index] = node_hash(&nodes[2 * index + 1], &nodes[2 * index + 2]);
+		}
+
+		MerkleTree { nodes }
+	}
+
+	/// The root of the tree, which is all zeros for a tree without leaves.
+	pub(super) fn root(&self) -> Hash {
+		self.nodes.first().copied().unwrap_or_default()
+	}
+
+	/// Build a proof for the given leaves, given by their position among `types`, the types
+	/// the tree was built from.
+	pub(super) fn proof(&self, leaves: &BTreeSet<usize>, types: &[Type]) -> Proof {
+		let first_leaf = self.nodes.len() - types.len();
+		let mut leaf_indices: Vec<usize> = leaves.iter().map(|leaf| first_leaf + leaf).collect();
+		// The leaves on the lowest level are the leftmost ones.
+		leaf_indices.sort_by_key(|index| (core::cmp::Reverse(level(*index as u64)), *index));
+
+		let mut nodes = Vec::new();
+		if !self.nodes.is_empty() {
+			self.collect_nodes(0, &leaf_indices, &mut nodes);
+		}
+
+		Proof {
+			leaves: leaf_indices
+				.iter()
+				.map(|index| types[index - first_leaf].clone())
+				.collect(),
+			leaf_indices: leaf_indices.iter().map(|index| *index as u32).collect(),
+			nodes,
+		}
+	}
+
+	/// Collect the hashes of the nodes below `index` that are needed to compute its hash from
+	/// the given leaves, from left to right.
+	fn collect_nodes(&self, index: usize, leaves: &[usize], nodes: &mut Vec<Hash>) {
+		if leaves.contains(&index) {
+			return;
+		}
+		if leaves
+			.iter()
+			.any(|leaf| is_ancestor(index as u64, *leaf as u64))
+		{
+			self.collect_nodes(2 * index + 1, leaves, nodes);
+			self.collect_nodes(2 * index + 2, leaves, nodes);
+		} else {
+			nodes.push(self.nodes[index]);
+		}
+	}
+}
+
+/// The level of the node with the given index, which is `0` for the root.
+fn level(index: u64) -> u32 {
+	(index + 1).ilog2()
+}
+
+/// Whether the node `ancestor` is a strict ancestor of the node `index`.
+fn is_ancestor(ancestor: u64, mut index: u64) -> bool {
+	while index > ancestor {
+		index = (index - 1) / 2;
+		if index == ancestor {
+			return true;
+		}
+	}
+	false
+}
+
+/// The hash of an inner node with the given children.
+pub(super) fn node_hash(left: &Hash, right: &Hash) -> Hash {
+	blake3(&(left, right).encode())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::metadata_hash::{TypeDef, TypeRef};
+
+	fn types(count: u32) -> Vec<Type> {
+		(0..count)
+			.map(|id| Type {
+				path: Vec::new(),
+				type_def: TypeDef::Sequence(TypeRef::U8),
+				type_id: id.into(),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn should_hash_leaves_into_a_complete_binary_tree() {
+		let leaves = [[0; 32], [1; 32], [2; 32]];
+
+		assert_eq!(MerkleTree::new([]).root(), [0; 32]);
+		assert_eq!(MerkleTree::new([leaves[0]]).root(), leaves[0]);
+
+		// The last two leaves share a parent at the lowest level.
+		let parent = node_hash(&leaves[1], &leaves[2]);
+		assert_eq!(
+			MerkleTree::new(leaves).root(),
+			node_hash(&parent, &leaves[0])
+		);
+	}
+
+	#[test]
+	fn should_verify_proofs_of_any_leaves() {
+		for count in [1, 2, 5, 8, 13] {
+			let types = types(count);
+			let tree = MerkleTree::new(types.iter().map(Type::hash));
+
+			// Every subset of up to three leaves.
+			for a in 0..types.len() {
+				for b in a..types.len() {
+					for c in b..types.len() {
+						let leaves = BTreeSet::from([a, b, c]);
+						let proof = tree.proof(&leaves, &types);
+						assert_eq!(proof.leaves.len(), leaves.len());
+						assert!(
+							proof.verify(&tree.root()),
+							"{count} leaves, proof of {leaves:?}"
+						);
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn should_reject_invalid_proofs() {
+		let types = types(5);
+		let tree = MerkleTree::new(types.iter().map(Type::hash));
+		let proof = tree.proof(&BTreeSet::from([1, 3]), &types);
+		assert!(proof.verify(&tree.root()));
+
+		let mut missing_node = proof.clone();
+		missing_node.nodes.pop();
+		assert_eq!(missing_node.root(), Err(MetadataHashError::InvalidProof));
+
+		let mut extra_node = proof.clone();
+		extra_node.nodes.push([0; 32]);
+		assert_eq!(extra_node.root(), Err(MetadataHashError::InvalidProof));
+
+		let mut wrong_type = proof.clone();
+		wrong_type.leaves[0].type_id = 4.into();
+		assert!(!wrong_type.verify(&tree.root()));
+
+		let mut swapped = proof;
+		swapped.leaf_indices.reverse();
+		assert!(!swapped.verify(&tree.root()));
+	}
+}
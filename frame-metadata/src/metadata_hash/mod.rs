@@ -0,0 +1,596 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The metadata hash of [RFC-78](https://polkadot-fellows.github.io/RFCs/approved/0078-merkleized-metadata.html),
+//! which the `CheckMetadataHash` signed extension includes in the signed payload.
+//!
+//! The types needed to decode extrinsics are turned into the leaves of a merkle tree, see
+//! [`TypeInformation`](crate::metadata_hash::TypeInformation). The root of that tree, the
+//! hash of the extrinsic format and an [`ExtraInfo`](crate::metadata_hash::ExtraInfo) about the
+//! chain make up the [`MetadataDigest`](crate::metadata_hash::MetadataDigest), whose hash is
+//! the metadata hash.
+//!
+//! Signers that cannot hold the whole metadata can instead be given a
+//! [`Proof`](crate::metadata_hash::Proof) of just the types needed to decode one extrinsic, see
+//! [`TypeInformation::proof_for_extrinsic`](crate::metadata_hash::TypeInformation::proof_for_extrinsic).
+//! Together with the extrinsic metadata and the extra information, the proof is enough to
+//! recompute the metadata hash.
+
+mod extrinsic;
+mod merkle;
+mod types;
+
+pub use extrinsic::SignedExtrinsicData;
+pub use merkle::Proof;
+
+pub use types::{
+	EnumerationVariant, ExtrinsicMetadata, Field, MetadataDigest, SignedExtensionMetadata, Type,
+	TypeDef, TypeDefArray, TypeDefBitSequence, TypeRef,
+};
+
+use crate::{
+	convert::to_string,
+	metadata::{Metadata, V4_EXTENSION_VERSION},
+};
+use merkle::MerkleTree;
+use scale_info::{
+	form::PortableForm,
+	prelude::{
+		collections::{BTreeMap, BTreeSet},
+		string::String,
+		vec,
+		vec::Vec,
+	},
+	PortableRegistry, TypeDefPrimitive,
+};
+
+/// A 32 bytes hash.
+pub type Hash = [u8; 32];
+
+/// The extrinsic version described by the metadata hash.
+///
+/// RFC-78 is defined over V15 metadata, which only describes version 4 extrinsics.
+const EXTRINSIC_VERSION: u8 = 4;
+
+/// An error that can occur when computing the metadata hash.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MetadataHashError {
+	/// A type ID referenced by the metadata was not found in the type registry.
+	TypeNotFound(u32),
+	/// The extrinsic type of V14 metadata does not have the expected generic type parameter.
+	MissingExtrinsicTypeParam(&'static str),
+	/// The metadata does not support version 4 extrinsics.
+	UnsupportedExtrinsicVersion(u8),
+	/// The metadata does not describe the transaction extensions of the given extension
+	/// version, which signed version 4 extrinsics use.
+	UnsupportedExtensionVersion(u8),
+	/// The compact type with the given ID does not wrap a single unsigned integer.
+	UnsupportedCompactType(u32),
+	/// The bit sequence type with the given ID has an unsupported store or order type.
+	UnsupportedBitSequenceType(u32),
+	/// The given part of an extrinsic could not be decoded with the type information.
+	InvalidExtrinsicData(&'static str),
+	/// The given part of an extrinsic has bytes left after decoding it.
+	TrailingBytes(&'static str),
+	/// A merkle proof does not contain exactly the nodes needed to compute the root.
+	InvalidProof,
+}
+
+impl core::fmt::Display for MetadataHashError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			MetadataHashError::TypeNotFound(id) => {
+				write!(f, "type with ID {id} not found in the type registry")
+			}
+			MetadataHashError::MissingExtrinsicTypeParam(name) => {
+				write!(f, "extrinsic type has no `{name}` type parameter")
+			}
+			MetadataHashError::UnsupportedExtrinsicVersion(version) => {
+				write!(
+					f,
+					"extrinsic version {version} is not supported by the metadata"
+				)
+			}
+			MetadataHashError::UnsupportedExtensionVersion(version) => {
+				write!(
+					f,
+					"transaction extension version {version} is not supported by the metadata"
+				)
+			}
+			MetadataHashError::UnsupportedCompactType(id) => {
+				write!(f, "compact type with ID {id} is not supported")
+			}
+			MetadataHashError::UnsupportedBitSequenceType(id) => {
+				write!(f, "bit sequence type with ID {id} is not supported")
+			}
+			MetadataHashError::InvalidExtrinsicData(part) => {
+				write!(f, "could not decode the {part} of the extrinsic")
+			}
+			MetadataHashError::TrailingBytes(part) => {
+				write!(f, "bytes left after decoding the {part} of the extrinsic")
+			}
+			MetadataHashError::InvalidProof => {
+				write!(f, "the proof does not match the merkle tree")
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MetadataHashError {}
+
+/// Information about the chain that is part of the [`MetadataDigest`] but not of the metadata.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExtraInfo {
+	/// The spec version of the runtime.
+	pub spec_version: u32,
+	/// The spec name of the runtime.
+	pub spec_name: String,
+	/// The SS58 prefix of addresses.
+	pub base58_prefix: u16,
+	/// The number of decimals of the native token.
+	pub decimals: u8,
+	/// The symbol of the native token.
+	pub token_symbol: String,
+}
+
+/// Compute the [`MetadataDigest`] of the metadata. Its [`hash`](MetadataDigest::hash) is the
+/// metadata hash.
+pub fn metadata_digest(
+	metadata: &Metadata,
+	extra_info: ExtraInfo,
+) -> Result<MetadataDigest, MetadataHashError> {
+	TypeInformation::new(metadata).map(|info| info.digest(extra_info))
+}
+
+/// The type information of the metadata, from which the metadata hash is computed.
+///
+/// Only the types reachable from the extrinsic format are included. They are given new IDs in
+/// the order of their IDs in the metadata, skipping primitives, compacts and types without
+/// data, which are referenced inline. Each enumeration variant is a separate leaf.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TypeInformation {
+	/// The extrinsic format.
+	pub extrinsic_metadata: ExtrinsicMetadata,
+	/// The types, in the order of the leaves of the merkle tree.
+	pub types: Vec<Type>,
+}
+
+impl TypeInformation {
+	/// Extract the type information from the metadata.
+	///
+	/// The signed extensions are the ones used by signed version 4 extrinsics, which is what
+	/// V15 metadata describes.
+	pub fn new(metadata: &Metadata) -> Result<Self, MetadataHashError> {
+		let types = metadata.types();
+		let extrinsic = metadata.extrinsic();
+		if !extrinsic.versions().contains(&EXTRINSIC_VERSION) {
+			return Err(MetadataHashError::UnsupportedExtrinsicVersion(
+				EXTRINSIC_VERSION,
+			));
+		}
+		let extensions = extrinsic
+			.transaction_extensions_by_version(V4_EXTENSION_VERSION)
+			.ok_or(MetadataHashError::UnsupportedExtensionVersion(
+				V4_EXTENSION_VERSION,
+			))?;
+		let extrinsic_ty =
+			|name, ty: Option<u32>| ty.ok_or(MetadataHashError::MissingExtrinsicTypeParam(name));
+		let address_ty = extrinsic_ty("Address", extrinsic.address_ty())?;
+		let call_ty = extrinsic_ty("Call", extrinsic.call_ty())?;
+		let signature_ty = extrinsic_ty("Signature", extrinsic.signature_ty())?;
+
+		let roots = [call_ty, address_ty, signature_ty]
+			.into_iter()
+			.chain(extensions.iter().flat_map(|ext| [ext.ty, ext.implicit]));
+		let ids = accessible_types(types, roots)?
+			.into_iter()
+			.filter(|id| {
+				types
+					.resolve(*id)
+					.is_some_and(|ty| is_leaf_type(&ty.type_def))
+			})
+			.enumerate()
+			.map(|(new_id, id)| (id, new_id as u32))
+			.collect();
+		let context = Context { types, ids };
+
+		let extrinsic_metadata = ExtrinsicMetadata {
+			version: EXTRINSIC_VERSION,
+			address_ty: context.type_ref(address_ty)?,
+			call_ty: context.type_ref(call_ty)?,
+			signature_ty: context.type_ref(signature_ty)?,
+			signed_extensions: extensions
+				.iter()
+				.map(|ext| {
+					Ok(SignedExtensionMetadata {
+						identifier: ext.identifier.into(),
+						included_in_extrinsic: context.type_ref(ext.ty)?,
+						included_in_signed_data: context.type_ref(ext.implicit)?,
+					})
+				})
+				.collect::<Result<_, _>>()?,
+		};
+
+		let mut leaves = Vec::new();
+		for (id, new_id) in &context.ids {
+			leaves.extend(context.types(*id, *new_id)?);
+		}
+
+		Ok(TypeInformation {
+			extrinsic_metadata,
+			types: leaves,
+		})
+	}
+
+	/// The root of the merkle tree whose leaves are the hashes of the types.
+	pub fn root(&self) -> Hash {
+		MerkleTree::new(self.types.iter().map(Type::hash)).root()
+	}
+
+	/// The [`MetadataDigest`] of this type information.
+	pub fn digest(&self, extra_info: ExtraInfo) -> MetadataDigest {
+		MetadataDigest::V1 {
+			types_tree_root: self.root(),
+			extrinsic_metadata_hash: self.extrinsic_metadata.hash(),
+			spec_version: extra_info.spec_version,
+			spec_name: extra_info.spec_name,
+			base58_prefix: extra_info.base58_prefix,
+			decimals: extra_info.decimals,
+			token_symbol: extra_info.token_symbol,
+		}
+	}
+}
+
+/// The blake3 hash of `data`, which is the hash used throughout RFC-78.
+pub(crate) fn blake3(data: &[u8]) -> Hash {
+	blake3::hash(data).into()
+}
+
+/// Collect the IDs of all types reachable from `roots`.
+///
+/// The inner types of compacts and bit sequences are not needed to describe them, and are not
+/// followed.
+fn accessible_types(
+	types: &PortableRegistry,
+	roots: impl IntoIterator<Item = u32>,
+) -> Result<BTreeSet<u32>, MetadataHashError> {
+	let mut found = BTreeSet::new();
+	let mut pending: Vec<u32> = roots.into_iter().collect();
+
+	while let Some(id) = pending.pop() {
+		if !found.insert(id) {
+			continue;
+		}
+		let ty = types
+			.resolve(id)
+			.ok_or(MetadataHashError::TypeNotFound(id))?;
+		match &ty.type_def {
+			scale_info::TypeDef::Composite(def) => {
+				pending.extend(def.fields.iter().map(|field| field.ty.id))
+			}
+			scale_info::TypeDef::Variant(def) => pending.extend(
+				def.variants
+					.iter()
+					.flat_map(|variant| &variant.fields)
+					.map(|field| field.ty.id),
+			),
+			scale_info::TypeDef::Sequence(def) => pending.push(def.type_param.id),
+			scale_info::TypeDef::Array(def) => pending.push(def.type_param.id),
+			scale_info::TypeDef::Tuple(def) => pending.extend(def.fields.iter().map(|ty| ty.id)),
+			scale_info::TypeDef::Primitive(_)
+			| scale_info::TypeDef::Compact(_)
+			| scale_info::TypeDef::BitSequence(_) => {}
+		}
+	}
+
+	Ok(found)
+}
+
+/// Whether a type is a leaf of the merkle tree rather than referenced inline by [`TypeRef`].
+fn is_leaf_type(type_def: &scale_info::TypeDef<PortableForm>) -> bool {
+	match type_def {
+		scale_info::TypeDef::Primitive(_) | scale_info::TypeDef::Compact(_) => false,
+		scale_info::TypeDef::Composite(def) => !def.fields.is_empty(),
+		scale_info::TypeDef::Variant(def) => !def.variants.is_empty(),
+		scale_info::TypeDef::Tuple(def) => !def.fields.is_empty(),
+		_ => true,
+	}
+}
+
+/// The type registry of the metadata, together with the new IDs of the leaf types.
+struct Context<'a> {
+	types: &'a PortableRegistry,
+	ids: BTreeMap<u32, u32>,
+}
+
+impl Context<'_> {
+	fn resolve(&self, id: u32) -> Result<&scale_info::Type<PortableForm>, MetadataHashError> {
+		self.types
+			.resolve(id)
+			.ok_or(MetadataHashError::TypeNotFound(id))
+	}
+
+	/// The reference to the type with the given ID in the registry.
+	fn type_ref(&self, id: u32) -> Result<TypeRef, MetadataHashError> {
+		let ty = self.resolve(id)?;
+		let type_ref = match &ty.type_def {
+			scale_info::TypeDef::Primitive(primitive) => match primitive {
+				TypeDefPrimitive::Bool => TypeRef::Bool,
+				TypeDefPrimitive::Char => TypeRef::Char,
+				TypeDefPrimitive::Str => TypeRef::Str,
+				TypeDefPrimitive::U8 => TypeRef::U8,
+				TypeDefPrimitive::U16 => TypeRef::U16,
+				TypeDefPrimitive::U32 => TypeRef::U32,
+				TypeDefPrimitive::U64 => TypeRef::U64,
+				TypeDefPrimitive::U128 => TypeRef::U128,
+				TypeDefPrimitive::U256 => TypeRef::U256,
+				TypeDefPrimitive::I8 => TypeRef::I8,
+				TypeDefPrimitive::I16 => TypeRef::I16,
+				TypeDefPrimitive::I32 => TypeRef::I32,
+				TypeDefPrimitive::I64 => TypeRef::I64,
+				TypeDefPrimitive::I128 => TypeRef::I128,
+				TypeDefPrimitive::I256 => TypeRef::I256,
+			},
+			// A compact of a type wrapping an integer, such as `Compact<Perbill>`, is encoded
+			// like a compact of the integer.
+			scale_info::TypeDef::Compact(def) => match self.primitives(def.type_param.id)?[..] {
+				[] => TypeRef::Void,
+				[TypeDefPrimitive::U8] => TypeRef::CompactU8,
+				[TypeDefPrimitive::U16] => TypeRef::CompactU16,
+				[TypeDefPrimitive::U32] => TypeRef::CompactU32,
+				[TypeDefPrimitive::U64] => TypeRef::CompactU64,
+				[TypeDefPrimitive::U128] => TypeRef::CompactU128,
+				[TypeDefPrimitive::U256] => TypeRef::CompactU256,
+				_ => return Err(MetadataHashError::UnsupportedCompactType(id)),
+			},
+			type_def if !is_leaf_type(type_def) => TypeRef::Void,
+			_ => {
+				let new_id = self
+					.ids
+					.get(&id)
+					.ok_or(MetadataHashError::TypeNotFound(id))?;
+				TypeRef::ById((*new_id).into())
+			}
+		};
+		Ok(type_ref)
+	}
+
+	/// The primitive types reachable from the type with the given ID.
+	fn primitives(&self, id: u32) -> Result<Vec<TypeDefPrimitive>, MetadataHashError> {
+		let mut visited = BTreeSet::new();
+		let mut found = Vec::new();
+		let mut pending = vec![id];
+
+		while let Some(id) = pending.pop() {
+			if !visited.insert(id) {
+				continue;
+			}
+			match &self.resolve(id)?.type_def {
+				scale_info::TypeDef::Primitive(primitive) => found.push(primitive.clone()),
+				scale_info::TypeDef::Composite(def) => {
+					pending.extend(def.fields.iter().map(|field| field.ty.id))
+				}
+				scale_info::TypeDef::Variant(def) => pending.extend(
+					def.variants
+						.iter()
+						.flat_map(|variant| &variant.fields)
+						.map(|field| field.ty.id),
+				),
+				scale_info::TypeDef::Sequence(def) => pending.push(def.type_param.id),
+				scale_info::TypeDef::Array(def) => pending.push(def.type_param.id),
+				scale_info::TypeDef::Tuple(def) => {
+					pending.extend(def.fields.iter().map(|ty| ty.id))
+				}
+				scale_info::TypeDef::Compact(def) => pending.push(def.type_param.id),
+				scale_info::TypeDef::BitSequence(def) => {
+					pending.extend([def.bit_order_type.id, def.bit_store_type.id])
+				}
+			}
+		}
+
+		Ok(found)
+	}
+
+	/// The leaves of the type with the given ID in the registry, given its new ID.
+	fn types(&self, id: u32, new_id: u32) -> Result<Vec<Type>, MetadataHashError> {
+		let ty = self.resolve(id)?;
+		let path: Vec<String> = ty.path.segments.iter().map(to_string).collect();
+		let leaf = |type_def| Type {
+			path: path.clone(),
+			type_def,
+			type_id: new_id.into(),
+		};
+
+		let type_def = match &ty.type_def {
+			scale_info::TypeDef::Variant(def) => {
+				let mut variants: Vec<_> = def.variants.iter().collect();
+				variants.sort_by_key(|variant| variant.index);
+				return variants
+					.into_iter()
+					.map(|variant| {
+						Ok(leaf(TypeDef::Enumeration(EnumerationVariant {
+							name: to_string(&variant.name),
+							fields: self.fields(&variant.fields)?,
+							index: u32::from(variant.index).into(),
+						})))
+					})
+					.collect();
+			}
+			scale_info::TypeDef::Composite(def) => TypeDef::Composite(self.fields(&def.fields)?),
+			scale_info::TypeDef::Sequence(def) => {
+				TypeDef::Sequence(self.type_ref(def.type_param.id)?)
+			}
+			scale_info::TypeDef::Array(def) => TypeDef::Array(TypeDefArray {
+				len: def.len,
+				type_param: self.type_ref(def.type_param.id)?,
+			}),
+			scale_info::TypeDef::Tuple(def) => TypeDef::Tuple(
+				def.fields
+					.iter()
+					.map(|ty| self.type_ref(ty.id))
+					.collect::<Result<_, _>>()?,
+			),
+			scale_info::TypeDef::BitSequence(def) => {
+				let num_bytes = match self.primitives(def.bit_store_type.id)?[..] {
+					[TypeDefPrimitive::U8] => 1,
+					[TypeDefPrimitive::U16] => 2,
+					[TypeDefPrimitive::U32] => 4,
+					[TypeDefPrimitive::U64] => 8,
+					_ => return Err(MetadataHashError::UnsupportedBitSequenceType(id)),
+				};
+				let order = self.resolve(def.bit_order_type.id)?;
+				let least_significant_bit_first = match order
+					.path
+					.segments
+					.iter()
+					.map(AsRef::<str>::as_ref)
+					.find(|segment| *segment == "Lsb0" || *segment == "Msb0")
+				{
+					Some(segment) => segment == "Lsb0",
+					None => return Err(MetadataHashError::UnsupportedBitSequenceType(id)),
+				};
+				TypeDef::BitSequence(TypeDefBitSequence {
+					num_bytes,
+					least_significant_bit_first,
+				})
+			}
+			scale_info::TypeDef::Primitive(_) | scale_info::TypeDef::Compact(_) => {
+				return Ok(Vec::new())
+			}
+		};
+
+		Ok(vec![leaf(type_def)])
+	}
+
+	fn fields(
+		&self,
+		fields: &[scale_info::Field<PortableForm>],
+	) -> Result<Vec<Field>, MetadataHashError> {
+		fields
+			.iter()
+			.map(|field| {
+				Ok(Field {
+					name: field.name.as_ref().map(to_string),
+					ty: self.type_ref(field.ty.id)?,
+					type_name: field.type_name.as_ref().map(to_string),
+				})
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		test::{kusama_v14, kusama_v15, kusama_v16},
+		v16,
+	};
+
+	fn extra_info() -> ExtraInfo {
+		ExtraInfo {
+			spec_version: 1,
+			spec_name: "x".into(),
+			base58_prefix: 2,
+			decimals: 10,
+			token_symbol: "DOT".into(),
+		}
+	}
+
+	fn kusama() -> [Metadata; 3] {
+		let v14 = kusama_v14();
+		let v15 = kusama_v15();
+		let v16 = kusama_v16();
+		[v14.into(), v15.into(), v16.into()]
+	}
+
+	#[test]
+	fn should_compute_the_same_hash_for_every_version() {
+		// Computed with the `merkleized-metadata` reference implementation from the V15 metadata.
+		let expected = "b867257462bd0d53c05338d68e270778eccbbc0929ec3abe3183978c82cb2bd4";
+
+		for metadata in kusama() {
+			let hash = metadata_digest(&metadata, extra_info()).unwrap().hash();
+			let hex: String = hash.iter().map(|byte| format!("{byte:02x}")).collect();
+			assert_eq!(hex, expected, "hash of V{}", metadata.version());
+		}
+	}
+
+	#[test]
+	fn should_number_leaf_types_consecutively() {
+		let [metadata, ..] = kusama();
+		let info = TypeInformation::new(&metadata).unwrap();
+
+		let key = |ty: &Type| match &ty.type_def {
+			TypeDef::Enumeration(variant) => (ty.type_id.0, Some(variant.index.0)),
+			_ => (ty.type_id.0, None),
+		};
+		for pair in info.types.windows(2) {
+			let (last, next) = (key(&pair[0]), key(&pair[1]));
+			assert!(
+				next.0 == last.0 + 1 || (next.0 == last.0 && next.1 > last.1),
+				"{next:?} follows {last:?}"
+			);
+		}
+		assert_eq!(info.types[0].type_id.0, 0);
+
+		let TypeRef::ById(call_ty) = info.extrinsic_metadata.call_ty else {
+			panic!("call type should be a leaf type")
+		};
+		let call_variants = info.types.iter().filter(|ty| ty.type_id == call_ty);
+		assert_eq!(
+			call_variants.count(),
+			metadata.pallets().filter(|p| p.call_ty().is_some()).count()
+		);
+	}
+
+	#[test]
+	fn should_hash_the_extensions_of_extension_version_0() {
+		let expected = metadata_digest(&kusama_v14().into(), extra_info()).unwrap();
+
+		// As polkadot-sdk describes it, with extensions keyed by extension version.
+		let [_, v15, _] = kusama();
+		let mut v16 = v16::RuntimeMetadataV16::from(v15.as_v15().unwrap().clone());
+		let all = (0..v16.extrinsic.transaction_extensions.len() as u32)
+			.map(codec::Compact)
+			.collect();
+		v16.extrinsic.versions = vec![4, 5];
+		v16.extrinsic.transaction_extensions_by_version = [(0, all)].into();
+		let digest = metadata_digest(&v16.clone().into(), extra_info()).unwrap();
+		assert_eq!(digest.hash(), expected.hash());
+
+		let indexes = v16.extrinsic.transaction_extensions_by_version.remove(&0);
+		v16.extrinsic
+			.transaction_extensions_by_version
+			.insert(1, indexes.unwrap());
+		assert_eq!(
+			metadata_digest(&v16.into(), extra_info()),
+			Err(MetadataHashError::UnsupportedExtensionVersion(0))
+		);
+	}
+
+	#[test]
+	fn should_require_version_4_extrinsics() {
+		let [_, v15, _] = kusama();
+		let mut v16 = v16::RuntimeMetadataV16::from(v15.as_v15().unwrap().clone());
+		v16.extrinsic.versions = vec![5];
+
+		assert_eq!(
+			metadata_digest(&v16.into(), extra_info()),
+			Err(MetadataHashError::UnsupportedExtrinsicVersion(4))
+		);
+	}
+}
@@ -0,0 +1,260 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The type information of RFC-78, which is what the leaves of the merkle tree are made of.
+//!
+//! Compared to the type registry of the metadata, documentation and generic parameters are
+//! removed, primitives and compact encoded integers are referenced inline, and every variant
+//! of an enumeration is a type of its own.
+
+#[cfg(feature = "decode")]
+use codec::Decode;
+use codec::{Compact, Encode};
+use scale_info::prelude::{string::String, vec::Vec};
+
+use super::{blake3, Hash};
+
+/// A reference to a type.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub enum TypeRef {
+	/// A `bool`.
+	#[codec(index = 0)]
+	Bool,
+	/// A `char`.
+	#[codec(index = 1)]
+	Char,
+	/// A `str`.
+	#[codec(index = 2)]
+	Str,
+	/// A `u8`.
+	#[codec(index = 3)]
+	U8,
+	/// A `u16`.
+	#[codec(index = 4)]
+	U16,
+	/// A `u32`.
+	#[codec(index = 5)]
+	U32,
+	/// A `u64`.
+	#[codec(index = 6)]
+	U64,
+	/// A `u128`.
+	#[codec(index = 7)]
+	U128,
+	/// A 256 bits unsigned integer.
+	#[codec(index = 8)]
+	U256,
+	/// An `i8`.
+	#[codec(index = 9)]
+	I8,
+	/// An `i16`.
+	#[codec(index = 10)]
+	I16,
+	/// An `i32`.
+	#[codec(index = 11)]
+	I32,
+	/// An `i64`.
+	#[codec(index = 12)]
+	I64,
+	/// An `i128`.
+	#[codec(index = 13)]
+	I128,
+	/// A 256 bits signed integer.
+	#[codec(index = 14)]
+	I256,
+	/// A compact encoded `u8`.
+	#[codec(index = 15)]
+	CompactU8,
+	/// A compact encoded `u16`.
+	#[codec(index = 16)]
+	CompactU16,
+	/// A compact encoded `u32`.
+	#[codec(index = 17)]
+	CompactU32,
+	/// A compact encoded `u64`.
+	#[codec(index = 18)]
+	CompactU64,
+	/// A compact encoded `u128`.
+	#[codec(index = 19)]
+	CompactU128,
+	/// A compact encoded 256 bits unsigned integer.
+	#[codec(index = 20)]
+	CompactU256,
+	/// A type without any data, such as `()` or an enum without variants.
+	#[codec(index = 21)]
+	Void,
+	/// A type of the type information, by its ID.
+	#[codec(index = 22)]
+	ById(Compact<u32>),
+}
+
+/// A type of the type information.
+///
+/// Enumerations have one `Type` per variant, all with the same `type_id`.
+#[derive(Clone, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub struct Type {
+	/// The path of the type, which is empty for built-in types.
+	pub path: Vec<String>,
+	/// The definition of the type.
+	pub type_def: TypeDef,
+	/// The ID of the type.
+	pub type_id: Compact<u32>,
+}
+
+impl Type {
+	/// The hash of the type, which is the leaf of the merkle tree.
+	pub fn hash(&self) -> Hash {
+		blake3(&self.encode())
+	}
+}
+
+/// The definition of a [`Type`].
+#[derive(Clone, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub enum TypeDef {
+	/// A struct or a tuple struct.
+	#[codec(index = 0)]
+	Composite(Vec<Field>),
+	/// A single variant of an enumeration.
+	#[codec(index = 1)]
+	Enumeration(EnumerationVariant),
+	/// A sequence with a length only known at runtime.
+	#[codec(index = 2)]
+	Sequence(TypeRef),
+	/// An array with a fixed length.
+	#[codec(index = 3)]
+	Array(TypeDefArray),
+	/// A tuple.
+	#[codec(index = 4)]
+	Tuple(Vec<TypeRef>),
+	/// A sequence of bits.
+	#[codec(index = 5)]
+	BitSequence(TypeDefBitSequence),
+}
+
+/// A field of a struct or an enumeration variant.
+#[derive(Clone, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub struct Field {
+	/// The name of the field, which is `None` for unnamed fields.
+	pub name: Option<String>,
+	/// The type of the field.
+	pub ty: TypeRef,
+	/// The name of the type of the field as written in the source.
+	pub type_name: Option<String>,
+}
+
+/// A variant of an enumeration.
+#[derive(Clone, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub struct EnumerationVariant {
+	/// The name of the variant.
+	pub name: String,
+	/// The fields of the variant.
+	pub fields: Vec<Field>,
+	/// The index of the variant, which is how it is encoded.
+	pub index: Compact<u32>,
+}
+
+/// An array with a fixed length.
+#[derive(Clone, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub struct TypeDefArray {
+	/// The length of the array.
+	pub len: u32,
+	/// The type of the elements.
+	pub type_param: TypeRef,
+}
+
+/// The layout of a sequence of bits.
+#[derive(Clone, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub struct TypeDefBitSequence {
+	/// The number of bytes of the integer type storing the bits.
+	pub num_bytes: u8,
+	/// Whether the bits are ordered from the least significant bit first.
+	pub least_significant_bit_first: bool,
+}
+
+/// The information needed to decode the extrinsic format of a runtime.
+#[derive(Clone, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub struct ExtrinsicMetadata {
+	/// The extrinsic version.
+	pub version: u8,
+	/// The type of the address of a signed extrinsic.
+	pub address_ty: TypeRef,
+	/// The type of the runtime call enum.
+	pub call_ty: TypeRef,
+	/// The type of the signature of a signed extrinsic.
+	pub signature_ty: TypeRef,
+	/// The signed extensions, in the order they are encoded.
+	pub signed_extensions: Vec<SignedExtensionMetadata>,
+}
+
+impl ExtrinsicMetadata {
+	/// The hash of the extrinsic metadata, which is part of the [`MetadataDigest`].
+	pub fn hash(&self) -> Hash {
+		blake3(&self.encode())
+	}
+}
+
+/// A signed extension, also known as a transaction extension.
+#[derive(Clone, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub struct SignedExtensionMetadata {
+	/// The unique identifier of the extension.
+	pub identifier: String,
+	/// The type of the data included in the extrinsic.
+	pub included_in_extrinsic: TypeRef,
+	/// The type of the data only included in the signed payload.
+	pub included_in_signed_data: TypeRef,
+}
+
+/// The digest of the metadata, whose hash is the metadata hash.
+#[derive(Clone, PartialEq, Eq, Debug, Encode)]
+#[cfg_attr(feature = "decode", derive(Decode))]
+pub enum MetadataDigest {
+	/// The metadata hash is not checked.
+	#[codec(index = 0)]
+	Disabled,
+	/// The first version of the digest.
+	#[codec(index = 1)]
+	V1 {
+		/// The root of the merkle tree of the type information.
+		types_tree_root: Hash,
+		/// The hash of the [`ExtrinsicMetadata`].
+		extrinsic_metadata_hash: Hash,
+		/// The spec version of the runtime.
+		spec_version: u32,
+		/// The spec name of the runtime.
+		spec_name: String,
+		/// The SS58 prefix of addresses.
+		base58_prefix: u16,
+		/// The number of decimals of the native token.
+		decimals: u8,
+		/// The symbol of the native token.
+		token_symbol: String,
+	},
+}
+
+impl MetadataDigest {
+	/// The metadata hash, as checked by the `CheckMetadataHash` signed extension.
+	pub fn hash(&self) -> Hash {
+		blake3(&self.encode())
+	}
+}
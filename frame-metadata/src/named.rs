@@ -0,0 +1,82 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Walking the named items of pallets and runtime APIs the same way for every metadata version.
+
+use crate::{v14, v15, v16};
+use scale_info::{
+	form::{Form, PortableForm},
+	prelude::vec::Vec,
+};
+
+/// An item of a pallet or runtime API, with a name and docs.
+pub(crate) trait Named {
+	/// The name of the item.
+	fn name(&self) -> &str;
+	/// The name of the item, and its docs.
+	fn name_and_docs(&mut self) -> (&str, &mut Vec<<PortableForm as Form>::String>);
+}
+
+/// A runtime API with its methods.
+pub(crate) trait RuntimeApi: Named {
+	/// The method type of the runtime API.
+	type Method: Named;
+
+	/// The name of the runtime API, and its methods.
+	fn name_and_methods(&mut self) -> (&str, &mut Vec<Self::Method>);
+}
+
+macro_rules! impl_named {
+	($($ty:ty),*) => {
+		$(impl Named for $ty {
+			fn name(&self) -> &str {
+				AsRef::<str>::as_ref(&self.name)
+			}
+
+			fn name_and_docs(&mut self) -> (&str, &mut Vec<<PortableForm as Form>::String>) {
+				(AsRef::<str>::as_ref(&self.name), &mut self.docs)
+			}
+		})*
+	};
+}
+
+impl_named!(
+	v14::StorageEntryMetadata<PortableForm>,
+	v14::PalletConstantMetadata<PortableForm>,
+	v15::RuntimeApiMetadata<PortableForm>,
+	v15::RuntimeApiMethodMetadata<PortableForm>,
+	v16::StorageEntryMetadata<PortableForm>,
+	v16::PalletConstantMetadata<PortableForm>,
+	v16::PalletAssociatedTypeMetadata<PortableForm>,
+	v16::PalletViewFunctionMetadata<PortableForm>,
+	v16::RuntimeApiMetadata<PortableForm>,
+	v16::RuntimeApiMethodMetadata<PortableForm>
+);
+
+impl RuntimeApi for v15::RuntimeApiMetadata<PortableForm> {
+	type Method = v15::RuntimeApiMethodMetadata<PortableForm>;
+
+	fn name_and_methods(&mut self) -> (&str, &mut Vec<Self::Method>) {
+		(AsRef::<str>::as_ref(&self.name), &mut self.methods)
+	}
+}
+
+impl RuntimeApi for v16::RuntimeApiMetadata<PortableForm> {
+	type Method = v16::RuntimeApiMethodMetadata<PortableForm>;
+
+	fn name_and_methods(&mut self) -> (&str, &mut Vec<Self::Method>) {
+		(AsRef::<str>::as_ref(&self.name), &mut self.methods)
+	}
+}
@@ -0,0 +1,619 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Trimming the metadata down to selected items.
+//!
+//! `retain` on [`RuntimeMetadataV14`](crate::v14::RuntimeMetadataV14),
+//! [`RuntimeMetadataV15`](crate::v15::RuntimeMetadataV15) and
+//! [`RuntimeMetadataV16`](crate::v16::RuntimeMetadataV16) removes the pallets, pallet items
+//! and runtime APIs that are not selected by a predicate, and then removes every type of the
+//! registry that is no longer used. The remaining types are renumbered to keep the registry
+//! dense, and all type IDs of the metadata are updated to match.
+
+use crate::{
+	named::{Named, RuntimeApi},
+	v14, v15, v16,
+};
+use scale_info::{
+	form::PortableForm,
+	prelude::{collections::BTreeSet, vec, vec::Vec},
+	PortableRegistry, Type, TypeDef,
+};
+
+/// An item of the metadata that can be removed by `retain`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Item<'a> {
+	/// A pallet, which is removed together with all of its items.
+	Pallet(&'a str),
+	/// A call of a pallet.
+	Call {
+		/// The pallet name.
+		pallet: &'a str,
+		/// The call name.
+		name: &'a str,
+	},
+	/// An event of a pallet.
+	Event {
+		/// The pallet name.
+		pallet: &'a str,
+		/// The event name.
+		name: &'a str,
+	},
+	/// An error of a pallet.
+	Error {
+		/// The pallet name.
+		pallet: &'a str,
+		/// The error name.
+		name: &'a str,
+	},
+	/// A storage entry of a pallet.
+	StorageEntry {
+		/// The pallet name.
+		pallet: &'a str,
+		/// The storage entry name.
+		name: &'a str,
+	},
+	/// A constant of a pallet.
+	Constant {
+		/// The pallet name.
+		pallet: &'a str,
+		/// The constant name.
+		name: &'a str,
+	},
+	/// A view function of a pallet, which is only part of V16 metadata.
+	ViewFunction {
+		/// The pallet name.
+		pallet: &'a str,
+		/// The view function name.
+		name: &'a str,
+	},
+	/// A runtime API, which is removed together with all of its methods. Runtime APIs are not
+	/// part of V14 metadata.
+	RuntimeApi(&'a str),
+	/// A method of a runtime API.
+	RuntimeApiMethod {
+		/// The runtime API trait name.
+		api: &'a str,
+		/// The method name.
+		name: &'a str,
+	},
+}
+
+impl v14::RuntimeMetadataV14 {
+	/// Keep only the items for which `keep` returns `true`, and remove the types that are no
+	/// longer used.
+	///
+	/// The items of a pallet are only passed to `keep` if the pallet itself is kept. Enum
+	/// variants that wrap the call, event or error type of a removed pallet, such as those of
+	/// the outer `RuntimeCall` enum, are removed as well.
+	pub fn retain(&mut self, mut keep: impl FnMut(Item<'_>) -> bool) {
+		let types = &mut self.types;
+		let mut removed = BTreeSet::new();
+		self.pallets.retain_mut(|pallet| {
+			let name = AsRef::<str>::as_ref(&pallet.name);
+			let enums = [
+				pallet.calls.as_ref().map(|calls| calls.ty.id),
+				pallet.event.as_ref().map(|event| event.ty.id),
+				pallet.error.as_ref().map(|error| error.ty.id),
+			];
+			if retain_pallet(types, name, enums, &mut removed, &mut keep).is_none() {
+				return false;
+			}
+			if let Some(storage) = &mut pallet.storage {
+				retain_items(&mut storage.entries, name, storage_entry, &mut keep);
+			}
+			retain_items(&mut pallet.constants, name, constant, &mut keep);
+			true
+		});
+
+		remove_wrapping_variants(types, &removed);
+		self.prune_types();
+	}
+
+	/// Remove the types of the registry that the metadata does not use, and renumber the
+	/// remaining ones.
+	pub fn prune_types(&mut self) {
+		let mut refs = vec![&mut self.ty.id, &mut self.extrinsic.ty.id];
+		for ext in &mut self.extrinsic.signed_extensions {
+			refs.extend([&mut ext.ty.id, &mut ext.additional_signed.id]);
+		}
+		for pallet in &mut self.pallets {
+			refs.extend(pallet.calls.as_mut().map(|calls| &mut calls.ty.id));
+			refs.extend(pallet.event.as_mut().map(|event| &mut event.ty.id));
+			refs.extend(pallet.error.as_mut().map(|error| &mut error.ty.id));
+			for entry in pallet.storage.iter_mut().flat_map(|s| &mut s.entries) {
+				refs.extend(storage_entry_refs(&mut entry.ty));
+			}
+			refs.extend(pallet.constants.iter_mut().map(|c| &mut c.ty.id));
+		}
+		prune(&mut self.types, refs);
+	}
+}
+
+impl v15::RuntimeMetadataV15 {
+	/// Keep only the items for which `keep` returns `true`, and remove the types that are no
+	/// longer used.
+	///
+	/// The items of pallets and runtime APIs are only passed to `keep` if the pallet or API
+	/// itself is kept. Enum variants that wrap the call, event or error type of a removed
+	/// pallet, such as those of the outer enums, are removed as well.
+	pub fn retain(&mut self, mut keep: impl FnMut(Item<'_>) -> bool) {
+		let types = &mut self.types;
+		let mut removed = BTreeSet::new();
+		self.pallets.retain_mut(|pallet| {
+			let name = AsRef::<str>::as_ref(&pallet.name);
+			let enums = [
+				pallet.calls.as_ref().map(|calls| calls.ty.id),
+				pallet.event.as_ref().map(|event| event.ty.id),
+				pallet.error.as_ref().map(|error| error.ty.id),
+			];
+			if retain_pallet(types, name, enums, &mut removed, &mut keep).is_none() {
+				return false;
+			}
+			if let Some(storage) = &mut pallet.storage {
+				retain_items(&mut storage.entries, name, storage_entry, &mut keep);
+			}
+			retain_items(&mut pallet.constants, name, constant, &mut keep);
+			true
+		});
+
+		retain_apis(&mut self.apis, &mut keep);
+
+		remove_wrapping_variants(types, &removed);
+		self.prune_types();
+	}
+
+	/// Remove the types of the registry that the metadata does not use, and renumber the
+	/// remaining ones.
+	pub fn prune_types(&mut self) {
+		let extrinsic = &mut self.extrinsic;
+		let mut refs = vec![
+			&mut self.ty.id,
+			&mut extrinsic.address_ty.id,
+			&mut extrinsic.call_ty.id,
+			&mut extrinsic.signature_ty.id,
+			&mut extrinsic.extra_ty.id,
+		];
+		for ext in &mut extrinsic.signed_extensions {
+			refs.extend([&mut ext.ty.id, &mut ext.additional_signed.id]);
+		}
+		for pallet in &mut self.pallets {
+			refs.extend(pallet.calls.as_mut().map(|calls| &mut calls.ty.id));
+			refs.extend(pallet.event.as_mut().map(|event| &mut event.ty.id));
+			refs.extend(pallet.error.as_mut().map(|error| &mut error.ty.id));
+			for entry in pallet.storage.iter_mut().flat_map(|s| &mut s.entries) {
+				refs.extend(storage_entry_refs(&mut entry.ty));
+			}
+			refs.extend(pallet.constants.iter_mut().map(|c| &mut c.ty.id));
+		}
+		for method in self.apis.iter_mut().flat_map(|api| &mut api.methods) {
+			refs.extend(method.inputs.iter_mut().map(|input| &mut input.ty.id));
+			refs.push(&mut method.output.id);
+		}
+		refs.extend(outer_enum_refs(&mut self.outer_enums));
+		refs.extend(self.custom.map.values_mut().map(|value| &mut value.ty.id));
+		prune(&mut self.types, refs);
+	}
+}
+
+impl v16::RuntimeMetadataV16 {
+	/// Keep only the items for which `keep` returns `true`, and remove the types that are no
+	/// longer used.
+	///
+	/// The items of pallets and runtime APIs are only passed to `keep` if the pallet or API
+	/// itself is kept. Enum variants that wrap the call, event or error type of a removed
+	/// pallet, such as those of the outer enums, are removed as well. The associated types of
+	/// kept pallets are always kept.
+	pub fn retain(&mut self, mut keep: impl FnMut(Item<'_>) -> bool) {
+		let types = &mut self.types;
+		let mut removed = BTreeSet::new();
+		self.pallets.retain_mut(|pallet| {
+			let name = AsRef::<str>::as_ref(&pallet.name);
+			let enums = [
+				pallet.calls.as_ref().map(|calls| calls.ty.id),
+				pallet.event.as_ref().map(|event| event.ty.id),
+				pallet.error.as_ref().map(|error| error.ty.id),
+			];
+			let Some([calls, events, errors]) =
+				retain_pallet(types, name, enums, &mut removed, &mut keep)
+			else {
+				return false;
+			};
+			// Deprecation information is kept by variant index, and must follow the variants.
+			let deprecation_info = [
+				pallet
+					.calls
+					.as_mut()
+					.map(|calls| &mut calls.deprecation_info),
+				pallet
+					.event
+					.as_mut()
+					.map(|event| &mut event.deprecation_info),
+				pallet
+					.error
+					.as_mut()
+					.map(|error| &mut error.deprecation_info),
+			];
+			for (info, removed) in deprecation_info.into_iter().zip([calls, events, errors]) {
+				if let Some(info) = info {
+					info.0.retain(|index, _| !removed.contains(index));
+				}
+			}
+
+			if let Some(storage) = &mut pallet.storage {
+				retain_items(&mut storage.entries, name, storage_entry, &mut keep);
+			}
+			retain_items(&mut pallet.constants, name, constant, &mut keep);
+			retain_items(&mut pallet.view_functions, name, view_function, &mut keep);
+			true
+		});
+
+		retain_apis(&mut self.apis, &mut keep);
+
+		remove_wrapping_variants(types, &removed);
+		self.prune_types();
+	}
+
+	/// Remove the types of the registry that the metadata does not use, and renumber the
+	/// remaining ones.
+	pub fn prune_types(&mut self) {
+		let extrinsic = &mut self.extrinsic;
+		let mut refs = vec![
+			&mut extrinsic.address_ty.id,
+			&mut extrinsic.call_ty.id,
+			&mut extrinsic.signature_ty.id,
+		];
+		for ext in &mut extrinsic.transaction_extensions {
+			refs.extend([&mut ext.ty.id, &mut ext.implicit.id]);
+		}
+		for pallet in &mut self.pallets {
+			refs.extend(pallet.calls.as_mut().map(|calls| &mut calls.ty.id));
+			refs.extend(pallet.event.as_mut().map(|event| &mut event.ty.id));
+			refs.extend(pallet.error.as_mut().map(|error| &mut error.ty.id));
+			for entry in pallet.storage.iter_mut().flat_map(|s| &mut s.entries) {
+				refs.extend(storage_entry_refs(&mut entry.ty));
+			}
+			refs.extend(pallet.constants.iter_mut().map(|c| &mut c.ty.id));
+			refs.extend(pallet.associated_types.iter_mut().map(|t| &mut t.ty.id));
+			for view_function in &mut pallet.view_functions {
+				refs.extend(
+					view_function
+						.inputs
+						.iter_mut()
+						.map(|input| &mut input.ty.id),
+				);
+				refs.push(&mut view_function.output.id);
+			}
+		}
+		for method in self.apis.iter_mut().flat_map(|api| &mut api.methods) {
+			refs.extend(method.inputs.iter_mut().map(|input| &mut input.ty.id));
+			refs.push(&mut method.output.id);
+		}
+		refs.extend(outer_enum_refs(&mut self.outer_enums));
+		refs.extend(self.custom.map.values_mut().map(|value| &mut value.ty.id));
+		prune(&mut self.types, refs);
+	}
+}
+
+/// Whether `keep` selects the pallet `name`, whose `[calls, event, error]` types are `enums`.
+///
+/// The calls, events and errors of a kept pallet that `keep` rejects are removed, and the
+/// indexes of the removed variants are returned. The types of a removed pallet are added to
+/// `removed`.
+fn retain_pallet(
+	types: &mut PortableRegistry,
+	name: &str,
+	enums: [Option<u32>; 3],
+	removed: &mut BTreeSet<u32>,
+	keep: &mut impl FnMut(Item<'_>) -> bool,
+) -> Option<[Vec<u8>; 3]> {
+	if !keep(Item::Pallet(name)) {
+		removed.extend(enums.into_iter().flatten());
+		return None;
+	}
+	Some(retain_pallet_enums(types, name, enums, keep))
+}
+
+/// Remove the `items` of the pallet or runtime API `parent` that `keep` rejects, where `item`
+/// builds the [`Item`] of a child of `parent` from both names.
+fn retain_items(
+	items: &mut Vec<impl Named>,
+	parent: &str,
+	item: for<'b> fn(&'b str, &'b str) -> Item<'b>,
+	keep: &mut impl FnMut(Item<'_>) -> bool,
+) {
+	items.retain(|child| keep(item(parent, child.name())));
+}
+
+/// Remove the runtime APIs and methods that `keep` rejects.
+fn retain_apis(apis: &mut Vec<impl RuntimeApi>, keep: &mut impl FnMut(Item<'_>) -> bool) {
+	apis.retain_mut(|api| {
+		let (name, methods) = api.name_and_methods();
+		if !keep(Item::RuntimeApi(name)) {
+			return false;
+		}
+		retain_items(
+			methods,
+			name,
+			|api, name| Item::RuntimeApiMethod { api, name },
+			keep,
+		);
+		true
+	});
+}
+
+fn storage_entry<'b>(pallet: &'b str, name: &'b str) -> Item<'b> {
+	Item::StorageEntry { pallet, name }
+}
+
+fn constant<'b>(pallet: &'b str, name: &'b str) -> Item<'b> {
+	Item::Constant { pallet, name }
+}
+
+fn view_function<'b>(pallet: &'b str, name: &'b str) -> Item<'b> {
+	Item::ViewFunction { pallet, name }
+}
+
+/// Remove the calls, events and errors of `pallet` that `keep` rejects from the pallet's
+/// `[calls, event, error]` variant types, returning the indexes of the removed variants.
+fn retain_pallet_enums(
+	types: &mut PortableRegistry,
+	pallet: &str,
+	enums: [Option<u32>; 3],
+	keep: &mut impl FnMut(Item<'_>) -> bool,
+) -> [Vec<u8>; 3] {
+	let items: [for<'b> fn(&'b str, &'b str) -> Item<'b>; 3] = [
+		|pallet, name| Item::Call { pallet, name },
+		|pallet, name| Item::Event { pallet, name },
+		|pallet, name| Item::Error { pallet, name },
+	];
+	let mut removed = [Vec::new(), Vec::new(), Vec::new()];
+	for ((ty, item), removed) in enums.into_iter().zip(items).zip(&mut removed) {
+		let ty = ty.and_then(|ty| types.types.get_mut(ty as usize));
+		let Some(TypeDef::Variant(def)) = ty.map(|ty| &mut ty.ty.type_def) else {
+			continue;
+		};
+		def.variants.retain(|variant| {
+			let kept = keep(item(pallet, AsRef::<str>::as_ref(&variant.name)));
+			if !kept {
+				removed.push(variant.index);
+			}
+			kept
+		});
+	}
+	removed
+}
+
+/// Remove the enum variants whose only field is one of the `removed` types.
+fn remove_wrapping_variants(types: &mut PortableRegistry, removed: &BTreeSet<u32>) {
+	if removed.is_empty() {
+		return;
+	}
+	for ty in &mut types.types {
+		if let TypeDef::Variant(def) = &mut ty.ty.type_def {
+			def.variants.retain(
+				|variant| !matches!(&variant.fields[..], [field] if removed.contains(&field.ty.id)),
+			);
+		}
+	}
+}
+
+fn storage_entry_refs(ty: &mut v14::StorageEntryType<PortableForm>) -> Vec<&mut u32> {
+	match ty {
+		v14::StorageEntryType::Plain(value) => vec![&mut value.id],
+		v14::StorageEntryType::Map { key, value, .. } => vec![&mut key.id, &mut value.id],
+	}
+}
+
+fn outer_enum_refs(outer_enums: &mut v15::OuterEnums<PortableForm>) -> [&mut u32; 3] {
+	[
+		&mut outer_enums.call_enum_ty.id,
+		&mut outer_enums.event_enum_ty.id,
+		&mut outer_enums.error_enum_ty.id,
+	]
+}
+
+/// The type IDs that the type `ty` refers to.
+fn type_refs(ty: &mut Type<PortableForm>) -> Vec<&mut u32> {
+	let mut refs: Vec<_> = ty
+		.type_params
+		.iter_mut()
+		.filter_map(|param| param.ty.as_mut())
+		.map(|ty| &mut ty.id)
+		.collect();
+	match &mut ty.type_def {
+		TypeDef::Composite(composite) => {
+			refs.extend(composite.fields.iter_mut().map(|field| &mut field.ty.id))
+		}
+		TypeDef::Variant(variant) => refs.extend(
+			variant
+				.variants
+				.iter_mut()
+				.flat_map(|variant| &mut variant.fields)
+				.map(|field| &mut field.ty.id),
+		),
+		TypeDef::Sequence(sequence) => refs.push(&mut sequence.type_param.id),
+		TypeDef::Array(array) => refs.push(&mut array.type_param.id),
+		TypeDef::Tuple(tuple) => refs.extend(tuple.fields.iter_mut().map(|ty| &mut ty.id)),
+		TypeDef::Primitive(_) => {}
+		TypeDef::Compact(compact) => refs.push(&mut compact.type_param.id),
+		TypeDef::BitSequence(bits) => {
+			refs.extend([&mut bits.bit_store_type.id, &mut bits.bit_order_type.id])
+		}
+	}
+	refs
+}
+
+/// Remove the types that are not reachable from the type IDs `refs` of the metadata, and
+/// renumber the remaining types and all references to them.
+///
+/// Types are identified by their position in the registry, which becomes their ID. References
+/// to types that are not in the registry are left as they are, and stay dangling.
+fn prune(types: &mut PortableRegistry, refs: Vec<&mut u32>) {
+	let mut reachable = vec![false; types.types.len()];
+	let mut pending: Vec<u32> = refs.iter().map(|id| **id).collect();
+	while let Some(id) = pending.pop() {
+		let Some(ty) = types.types.get_mut(id as usize) else {
+			continue;
+		};
+		if core::mem::replace(&mut reachable[id as usize], true) {
+			continue;
+		}
+		pending.extend(type_refs(&mut ty.ty).into_iter().map(|id| *id));
+	}
+
+	let mut new_ids = Vec::with_capacity(reachable.len());
+	let mut next = 0;
+	for &reachable in &reachable {
+		new_ids.push(next);
+		next += reachable as u32;
+	}
+	let renumber = |id: &mut u32| {
+		if let Some(&new_id) = new_ids.get(*id as usize) {
+			*id = new_id;
+		}
+	};
+
+	for id in refs {
+		renumber(id);
+	}
+	let old_types = core::mem::take(&mut types.types);
+	for (mut ty, reachable) in old_types.into_iter().zip(reachable) {
+		if !reachable {
+			continue;
+		}
+		ty.id = types.types.len() as u32;
+		type_refs(&mut ty.ty).into_iter().for_each(renumber);
+		types.types.push(ty);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		convert::extrinsic_type_param,
+		metadata::Metadata,
+		test::{kusama_v14, kusama_v16},
+		validate::validate,
+	};
+	use scale_info::{Path, TypeDefComposite};
+
+	fn variant_names(types: &PortableRegistry, ty: u32) -> Vec<&str> {
+		match &types.resolve(ty).unwrap().type_def {
+			TypeDef::Variant(def) => def.variants.iter().map(|v| v.name.as_str()).collect(),
+			_ => panic!("type {ty} should be a variant type"),
+		}
+	}
+
+	#[test]
+	fn should_keep_selected_pallets_and_their_types() {
+		let original = kusama_v14();
+		let mut v14 = original.clone();
+		v14.retain(|item| match item {
+			Item::Pallet(pallet) => matches!(pallet, "System" | "Balances"),
+			_ => true,
+		});
+
+		let names: Vec<_> = v14.pallets.iter().map(|p| p.name.as_str()).collect();
+		assert_eq!(names, ["System", "Balances"]);
+		assert!(v14.types.types.len() < original.types.types.len() / 2);
+		assert_eq!(validate(&Metadata::from(v14.clone())), Ok(()));
+
+		let call_ty = extrinsic_type_param(&v14.types, v14.extrinsic.ty.id, "Call").unwrap();
+		assert_eq!(variant_names(&v14.types, call_ty), ["System", "Balances"]);
+
+		let account_ty = |metadata: &v14::RuntimeMetadataV14| {
+			let system = &metadata.pallets[0];
+			let entries = &system.storage.as_ref().unwrap().entries;
+			let account = entries.iter().find(|e| e.name == "Account").unwrap();
+			let v14::StorageEntryType::Map { value, .. } = &account.ty else {
+				panic!("System::Account should be a map")
+			};
+			metadata.types.resolve(value.id).unwrap().path.clone()
+		};
+		assert_eq!(account_ty(&v14), account_ty(&original));
+	}
+
+	#[test]
+	fn should_keep_selected_items_of_pallets_and_apis() {
+		let mut v16 = kusama_v16();
+		let api_ty = v16.types.types.len() as u32;
+		v16.types.types.push(scale_info::PortableType::new(
+			api_ty,
+			Type::new(
+				Path::from_segments_unchecked(["ApiOnly".into()]),
+				[],
+				TypeDefComposite::new([]),
+				Vec::new(),
+			),
+		));
+		v16.apis.push(v16::RuntimeApiMetadata {
+			name: "Api".into(),
+			methods: vec![v16::RuntimeApiMethodMetadata {
+				name: "method".into(),
+				inputs: Vec::new(),
+				output: api_ty.into(),
+				docs: Vec::new(),
+				deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+			}],
+			docs: Vec::new(),
+			version: codec::Compact(1),
+			deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+		});
+		let types = v16.types.types.len();
+
+		v16.retain(|item| match item {
+			Item::Pallet(pallet) => pallet == "Balances",
+			Item::Call { name, .. } => name == "transfer_keep_alive",
+			Item::StorageEntry { name, .. } => name == "Account",
+			Item::RuntimeApi(_) => false,
+			_ => true,
+		});
+
+		let [balances] = &v16.pallets[..] else {
+			panic!("only Balances should be kept")
+		};
+		let call_ty = balances.calls.as_ref().unwrap().ty.id;
+		assert_eq!(variant_names(&v16.types, call_ty), ["transfer_keep_alive"]);
+		let entries = &balances.storage.as_ref().unwrap().entries;
+		assert_eq!(entries.len(), 1);
+		assert!(!balances.constants.is_empty());
+		assert!(v16.apis.is_empty());
+		assert!(v16.types.types.len() < types);
+		assert!(v16
+			.types
+			.types
+			.iter()
+			.all(|ty| ty.ty.path.segments != ["ApiOnly"]));
+		assert_eq!(validate(&Metadata::from(v16)), Ok(()));
+	}
+
+	#[test]
+	fn should_prune_unused_types() {
+		let mut pruned = kusama_v14();
+		pruned.prune_types();
+		assert_eq!(validate(&Metadata::from(pruned.clone())), Ok(()));
+
+		let mut unused = pruned.clone();
+		let id = unused.types.types.len() as u32;
+		unused.types.types.push(scale_info::PortableType::new(
+			id,
+			Type::new(Path::default(), [], TypeDefComposite::new([]), Vec::new()),
+		));
+		unused.prune_types();
+		assert_eq!(unused, pruned);
+	}
+}
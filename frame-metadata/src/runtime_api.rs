@@ -0,0 +1,451 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Calling runtime APIs and pallet view functions with the types described by the metadata.
+//!
+//! A runtime API method is called through the `state_call` RPC method with the name
+//! `Trait_method` and the SCALE encoded arguments, and returns its SCALE encoded output. See
+//! [`runtime_api_call`](crate::runtime_api::runtime_api_call).
+//!
+//! The view functions of V16 pallets are all called through a single runtime API method,
+//! [`VIEW_FUNCTION_METHOD`](crate::runtime_api::VIEW_FUNCTION_METHOD), with the ID of the
+//! view function and its encoded inputs. See
+//! [`view_function_query`](crate::runtime_api::view_function_query).
+
+use crate::{
+	metadata::{Metadata, Pallet, RuntimeApiMethod},
+	v16,
+	value::{decode_value, encode_value_to, Composite, DecodeError, EncodeError, Value},
+};
+use codec::{Decode, DecodeAll, Encode};
+use scale_info::{
+	form::PortableForm,
+	prelude::{format, string::String, vec::Vec},
+	PortableRegistry,
+};
+
+/// The `Trait_method` name of the runtime API method that executes view functions.
+pub const VIEW_FUNCTION_METHOD: &str = "RuntimeViewFunction_execute_view_function";
+
+/// An error that can occur when building a runtime API call or decoding its output.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RuntimeApiError {
+	/// There is no runtime API with the given trait name.
+	ApiNotFound(String),
+	/// The runtime API has no method with the given name.
+	MethodNotFound {
+		/// The runtime API trait name.
+		api: String,
+		/// The method name.
+		method: String,
+	},
+	/// There is no pallet with the given name.
+	PalletNotFound(String),
+	/// The pallet has no view function with the given name.
+	ViewFunctionNotFound {
+		/// The pallet name.
+		pallet: String,
+		/// The view function name.
+		function: String,
+	},
+	/// The method or view function takes a different number of arguments.
+	WrongArgumentCount {
+		/// The number of parameters of the method.
+		expected: usize,
+		/// The number of arguments given.
+		found: usize,
+	},
+	/// No argument was given for the parameter with the given name.
+	MissingArgument(String),
+	/// An argument does not encode as the type of its parameter.
+	InvalidArgument {
+		/// The parameter name.
+		name: String,
+		/// The error.
+		error: EncodeError,
+	},
+	/// The output does not decode as the output type of the method or view function.
+	InvalidOutput(DecodeError),
+}
+
+impl core::fmt::Display for RuntimeApiError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			RuntimeApiError::ApiNotFound(name) => write!(f, "no runtime API named `{name}`"),
+			RuntimeApiError::MethodNotFound { api, method } => {
+				write!(f, "runtime API `{api}` has no method named `{method}`")
+			}
+			RuntimeApiError::PalletNotFound(name) => write!(f, "no pallet named `{name}`"),
+			RuntimeApiError::ViewFunctionNotFound { pallet, function } => {
+				write!(
+					f,
+					"pallet `{pallet}` has no view function named `{function}`"
+				)
+			}
+			RuntimeApiError::WrongArgumentCount { expected, found } => {
+				write!(f, "expected {expected} arguments, found {found}")
+			}
+			RuntimeApiError::MissingArgument(name) => write!(f, "missing argument `{name}`"),
+			RuntimeApiError::InvalidArgument { name, error } => {
+				write!(f, "invalid argument `{name}`: {error}")
+			}
+			RuntimeApiError::InvalidOutput(error) => write!(f, "invalid output: {error}"),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RuntimeApiError {}
+
+/// A call of a runtime API method, borrowing from the metadata.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RuntimeApiCall<'a> {
+	/// The `Trait_method` name to call the method by.
+	pub name: String,
+	/// The SCALE encoded arguments, in the order of the method parameters.
+	pub args: Vec<u8>,
+	/// The method being called.
+	pub method: RuntimeApiMethod<'a>,
+	types: &'a PortableRegistry,
+}
+
+impl RuntimeApiCall<'_> {
+	/// Decode the output of the call as `T`.
+	pub fn decode_output_as<T: Decode>(&self, bytes: &[u8]) -> Result<T, codec::Error> {
+		T::decode_all(&mut &*bytes)
+	}
+
+	/// Decode the output of the call as the output type of the method.
+	pub fn decode_output(&self, bytes: &[u8]) -> Result<Value, RuntimeApiError> {
+		decode_value(self.types, self.method.output, bytes).map_err(RuntimeApiError::InvalidOutput)
+	}
+}
+
+/// Build a call of the method `method` of the runtime API `api`.
+///
+/// The arguments are given by position, or by name in any order, and must match the
+/// parameters of the method in number and type.
+pub fn runtime_api_call<'a>(
+	metadata: &'a Metadata,
+	api: &str,
+	method: &str,
+	args: &Composite,
+) -> Result<RuntimeApiCall<'a>, RuntimeApiError> {
+	let runtime_api = metadata
+		.runtime_api_by_name(api)
+		.ok_or_else(|| RuntimeApiError::ApiNotFound(api.into()))?;
+	let method =
+		runtime_api
+			.method_by_name(method)
+			.ok_or_else(|| RuntimeApiError::MethodNotFound {
+				api: api.into(),
+				method: method.into(),
+			})?;
+
+	Ok(RuntimeApiCall {
+		name: format!("{}_{}", runtime_api.name(), method.name),
+		args: encode_args(metadata.types(), &method.inputs, args)?,
+		method,
+		types: metadata.types(),
+	})
+}
+
+/// A query of a pallet view function, borrowing from the metadata.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ViewFunctionQuery<'a> {
+	/// The pallet of the view function.
+	pub pallet: Pallet<'a>,
+	/// The view function being queried.
+	pub function: &'a v16::PalletViewFunctionMetadata<PortableForm>,
+	/// The SCALE encoded inputs, in the order of the view function parameters.
+	pub input: Vec<u8>,
+	types: &'a PortableRegistry,
+}
+
+impl ViewFunctionQuery<'_> {
+	/// The arguments of [`VIEW_FUNCTION_METHOD`]: the ID of the view function, followed by
+	/// the encoded inputs as a byte vector.
+	pub fn encode(&self) -> Vec<u8> {
+		(self.function.id, &self.input).encode()
+	}
+
+	/// Decode the output of the view function as `T`.
+	///
+	/// The runtime API method returns a `Result` whose `Ok` value is the encoded output as a
+	/// byte vector, and `bytes` are the contents of that vector.
+	pub fn decode_output_as<T: Decode>(&self, bytes: &[u8]) -> Result<T, codec::Error> {
+		T::decode_all(&mut &*bytes)
+	}
+
+	/// Decode the output of the view function as its output type, with `bytes` as in
+	/// [`decode_output_as`](Self::decode_output_as).
+	pub fn decode_output(&self, bytes: &[u8]) -> Result<Value, RuntimeApiError> {
+		decode_value(self.types, self.function.output.id, bytes)
+			.map_err(RuntimeApiError::InvalidOutput)
+	}
+}
+
+/// Build a query of the view function `function` of the pallet `pallet`, with arguments as
+/// in [`runtime_api_call`].
+pub fn view_function_query<'a>(
+	metadata: &'a Metadata,
+	pallet: &str,
+	function: &str,
+	args: &Composite,
+) -> Result<ViewFunctionQuery<'a>, RuntimeApiError> {
+	let pallet = metadata
+		.pallet_by_name(pallet)
+		.ok_or_else(|| RuntimeApiError::PalletNotFound(pallet.into()))?;
+	let view_function = pallet
+		.view_functions()
+		.iter()
+		.find(|view_function| view_function.name == function)
+		.ok_or_else(|| RuntimeApiError::ViewFunctionNotFound {
+			pallet: pallet.name().into(),
+			function: function.into(),
+		})?;
+
+	let inputs: Vec<_> = view_function
+		.inputs
+		.iter()
+		.map(|input| (AsRef::<str>::as_ref(&input.name), input.ty.id))
+		.collect();
+	Ok(ViewFunctionQuery {
+		pallet,
+		function: view_function,
+		input: encode_args(metadata.types(), &inputs, args)?,
+		types: metadata.types(),
+	})
+}
+
+/// The ID of the view function with the given signature in the pallet `pallet`.
+///
+/// The ID is the 128-bit XX hash of the pallet name, followed by that of the signature of the
+/// view function. The signature is the function name, the argument types separated by commas
+/// and the return type, with the types as written in the pallet without any whitespace, such as
+/// `get_value() -> Option<u32>` or `get_value_with_arg(u32) -> Option<u32>`. The metadata does
+/// not contain the signature, so only those who know it can check an ID.
+#[cfg(feature = "view-function-id")]
+pub fn view_function_id(pallet: &str, signature: &str) -> [u8; 32] {
+	// Each 128-bit XX hash is two 64-bit XX hashes, with the seeds 0 and 1.
+	let hashes = [(pallet, 0), (pallet, 1), (signature, 0), (signature, 1)];
+	let mut id = [0; 32];
+	for (bytes, (data, seed)) in id.chunks_mut(8).zip(hashes) {
+		bytes.copy_from_slice(&twox_hash::XxHash64::oneshot(seed, data.as_bytes()).to_le_bytes());
+	}
+	id
+}
+
+/// Encode the arguments `args` of a method or view function with the given parameter names
+/// and types.
+fn encode_args(
+	types: &PortableRegistry,
+	inputs: &[(&str, u32)],
+	args: &Composite,
+) -> Result<Vec<u8>, RuntimeApiError> {
+	let found = match args {
+		Composite::Named(args) => args.len(),
+		Composite::Unnamed(args) => args.len(),
+	};
+	if found != inputs.len() {
+		return Err(RuntimeApiError::WrongArgumentCount {
+			expected: inputs.len(),
+			found,
+		});
+	}
+
+	let mut encoded = Vec::new();
+	for (position, &(name, ty)) in inputs.iter().enumerate() {
+		let arg = match args {
+			Composite::Named(args) => args
+				.iter()
+				.find(|(arg, _)| arg == name)
+				.map(|(_, value)| value)
+				.ok_or_else(|| RuntimeApiError::MissingArgument(name.into()))?,
+			Composite::Unnamed(args) => &args[position],
+		};
+		encode_value_to(types, ty, arg, &mut encoded).map_err(|error| {
+			RuntimeApiError::InvalidArgument {
+				name: name.into(),
+				error,
+			}
+		})?;
+	}
+	Ok(encoded)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		test::{kusama_v16, kusama_with_account_nonce_api},
+		v16,
+		value::Primitive,
+	};
+	use codec::Encode;
+	use scale_info::prelude::vec;
+
+	/// Kusama whose `account_nonce` runtime API method takes an `AccountId32`.
+	fn metadata() -> Metadata {
+		kusama_with_account_nonce_api(&[("account", 0)])
+	}
+
+	fn account() -> Value {
+		let bytes = [1; 32].map(|byte| Value::Primitive(Primitive::U128(byte)));
+		Value::Composite(Composite::Unnamed(vec![Value::Sequence(bytes.to_vec())]))
+	}
+
+	#[test]
+	fn should_build_calls_and_decode_their_output() {
+		let metadata = metadata();
+		let positional = Composite::Unnamed(vec![account()]);
+		let call =
+			runtime_api_call(&metadata, "AccountNonceApi", "account_nonce", &positional).unwrap();
+		assert_eq!(call.name, "AccountNonceApi_account_nonce");
+		assert_eq!(call.args, [1; 32]);
+
+		let named = Composite::Named(vec![("account".into(), account())]);
+		let by_name = runtime_api_call(&metadata, "AccountNonceApi", "account_nonce", &named);
+		assert_eq!(by_name, Ok(call.clone()));
+
+		let output = 7u32.encode();
+		assert_eq!(call.decode_output_as::<u32>(&output), Ok(7));
+		assert_eq!(call.decode_output(&output).unwrap().as_u128(), Some(7));
+		assert!(matches!(
+			call.decode_output(&[7]),
+			Err(RuntimeApiError::InvalidOutput(_))
+		));
+	}
+
+	#[test]
+	fn should_reject_invalid_arguments() {
+		let metadata = metadata();
+		let call = |method: &str, args: Composite| {
+			runtime_api_call(&metadata, "AccountNonceApi", method, &args).unwrap_err()
+		};
+
+		assert_eq!(
+			runtime_api_call(
+				&metadata,
+				"Core",
+				"version",
+				&Composite::Unnamed(Vec::new())
+			),
+			Err(RuntimeApiError::ApiNotFound("Core".into()))
+		);
+		assert_eq!(
+			call("nonce", Composite::Unnamed(Vec::new())),
+			RuntimeApiError::MethodNotFound {
+				api: "AccountNonceApi".into(),
+				method: "nonce".into()
+			}
+		);
+		assert_eq!(
+			call("account_nonce", Composite::Unnamed(Vec::new())),
+			RuntimeApiError::WrongArgumentCount {
+				expected: 1,
+				found: 0
+			}
+		);
+		assert_eq!(
+			call(
+				"account_nonce",
+				Composite::Named(vec![("who".into(), account())])
+			),
+			RuntimeApiError::MissingArgument("account".into())
+		);
+		let error = call(
+			"account_nonce",
+			Composite::Unnamed(vec![Value::Primitive(Primitive::U128(1))]),
+		);
+		assert_eq!(
+			error.to_string(),
+			"invalid argument `account`: value does not have the shape of the type with ID 0"
+		);
+	}
+
+	#[test]
+	fn should_build_view_function_queries() {
+		let mut v16 = kusama_v16();
+		let balances = v16
+			.pallets
+			.iter_mut()
+			.find(|pallet| pallet.name == "Balances")
+			.unwrap();
+		balances
+			.view_functions
+			.push(v16::PalletViewFunctionMetadata {
+				id: [1; 32],
+				name: "free_balance".into(),
+				inputs: vec![v16::FunctionParamMetadata {
+					name: "who".into(),
+					ty: 0.into(),
+				}],
+				output: 6.into(),
+				docs: Vec::new(),
+				deprecation_info: v16::ItemDeprecationInfo::NotDeprecated,
+			});
+		let metadata = Metadata::from(v16);
+
+		let args = Composite::Unnamed(vec![account()]);
+		let query = view_function_query(&metadata, "Balances", "free_balance", &args).unwrap();
+		assert_eq!(query.pallet.name(), "Balances");
+		assert_eq!(query.input, [1; 32]);
+		assert_eq!(query.encode(), [&[1; 32][..], &[128], &[1; 32]].concat());
+		let output = 1000u128.encode();
+		assert_eq!(query.decode_output_as::<u128>(&output), Ok(1000));
+		assert_eq!(query.decode_output(&output).unwrap().as_u128(), Some(1000));
+
+		assert_eq!(
+			view_function_query(&metadata, "System", "free_balance", &args),
+			Err(RuntimeApiError::ViewFunctionNotFound {
+				pallet: "System".into(),
+				function: "free_balance".into()
+			})
+		);
+		assert_eq!(
+			view_function_query(
+				&metadata,
+				"Balances",
+				"free_balance",
+				&Composite::Unnamed(Vec::new())
+			),
+			Err(RuntimeApiError::WrongArgumentCount {
+				expected: 1,
+				found: 0
+			})
+		);
+	}
+
+	#[cfg(feature = "view-function-id")]
+	#[test]
+	fn should_compute_view_function_ids() {
+		let hex = |id: [u8; 32]| -> String { id.iter().map(|b| format!("{b:02x}")).collect() };
+
+		// The same hashes make up the storage prefix of `System::Account`.
+		let id = view_function_id("System", "Account");
+		let expected = "26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9";
+		assert_eq!(hex(id), expected);
+
+		// The view functions of the example pallet of polkadot-sdk.
+		let pallet = "ViewFunctionsExample";
+		let get_value = view_function_id(pallet, "get_value() -> Option<u32>");
+		let expected = "5b4197ce8a2dacf5edffc6c53e2577cbc432ccbf0d8dc0566fad93f986116b99";
+		assert_eq!(hex(get_value), expected);
+		let get_value_with_arg = view_function_id(pallet, "get_value_with_arg(u32) -> Option<u32>");
+		let expected = "5b4197ce8a2dacf5edffc6c53e2577cbab2cf42ee38eaccb85373535338148bf";
+		assert_eq!(hex(get_value_with_arg), expected);
+	}
+}
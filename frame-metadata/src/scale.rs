@@ -0,0 +1,125 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Walking SCALE encoded values by their type in the type registry.
+
+use codec::{Compact, Decode};
+use scale_info::{PortableRegistry, TypeDef, TypeDefPrimitive};
+
+/// The maximum nesting depth of a value, to protect against stack overflows.
+pub(crate) const MAX_DEPTH: usize = 256;
+
+/// The length of the SCALE encoded value of the type `ty` at the start of `data`.
+pub(crate) fn encoded_len(types: &PortableRegistry, ty: u32, data: &[u8]) -> Option<usize> {
+	let mut input = data;
+	skip(types, ty, &mut input, 0)?;
+	Some(data.len() - input.len())
+}
+
+/// Skip a SCALE encoded value of the type `ty`.
+fn skip(types: &PortableRegistry, ty: u32, input: &mut &[u8], depth: usize) -> Option<()> {
+	if depth == MAX_DEPTH {
+		return None;
+	}
+	match &types.resolve(ty)?.type_def {
+		TypeDef::Composite(composite) => skip_all(
+			types,
+			input,
+			depth,
+			composite.fields.iter().map(|field| field.ty.id),
+		),
+		TypeDef::Variant(variant) => {
+			let index = *take(input, 1)?.first()?;
+			let variant = variant.variants.iter().find(|v| v.index == index)?;
+			skip_all(
+				types,
+				input,
+				depth,
+				variant.fields.iter().map(|field| field.ty.id),
+			)
+		}
+		TypeDef::Sequence(sequence) => {
+			let len = Compact::<u32>::decode(input).ok()?.0;
+			skip_items(types, input, depth, sequence.type_param.id, len)
+		}
+		TypeDef::Array(array) => skip_items(types, input, depth, array.type_param.id, array.len),
+		TypeDef::Tuple(tuple) => skip_all(types, input, depth, tuple.fields.iter().map(|ty| ty.id)),
+		TypeDef::Primitive(primitive) => {
+			let len = match primitive {
+				TypeDefPrimitive::Bool | TypeDefPrimitive::U8 | TypeDefPrimitive::I8 => 1,
+				TypeDefPrimitive::U16 | TypeDefPrimitive::I16 => 2,
+				TypeDefPrimitive::Char | TypeDefPrimitive::U32 | TypeDefPrimitive::I32 => 4,
+				TypeDefPrimitive::U64 | TypeDefPrimitive::I64 => 8,
+				TypeDefPrimitive::U128 | TypeDefPrimitive::I128 => 16,
+				TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => 32,
+				TypeDefPrimitive::Str => Compact::<u32>::decode(input).ok()?.0 as usize,
+			};
+			take(input, len).map(|_| ())
+		}
+		TypeDef::Compact(_) => Compact::<u128>::skip(input).ok(),
+		TypeDef::BitSequence(bits) => {
+			let store_bytes = match &types.resolve(bits.bit_store_type.id)?.type_def {
+				TypeDef::Primitive(TypeDefPrimitive::U8) => 1,
+				TypeDef::Primitive(TypeDefPrimitive::U16) => 2,
+				TypeDef::Primitive(TypeDefPrimitive::U32) => 4,
+				TypeDef::Primitive(TypeDefPrimitive::U64) => 8,
+				_ => return None,
+			};
+			let bits = Compact::<u32>::decode(input).ok()?.0 as usize;
+			let stores = bits.div_ceil(store_bytes * 8);
+			take(input, stores * store_bytes).map(|_| ())
+		}
+	}
+}
+
+/// Skip SCALE encoded values of the types `tys`, which are nested in a value at `depth`.
+fn skip_all(
+	types: &PortableRegistry,
+	input: &mut &[u8],
+	depth: usize,
+	tys: impl IntoIterator<Item = u32>,
+) -> Option<()> {
+	tys.into_iter()
+		.try_for_each(|ty| skip(types, ty, input, depth + 1))
+}
+
+/// Skip `len` SCALE encoded values of the type `ty`, which are nested in a value at `depth`.
+fn skip_items(
+	types: &PortableRegistry,
+	input: &mut &[u8],
+	depth: usize,
+	ty: u32,
+	len: u32,
+) -> Option<()> {
+	for _ in 0..len {
+		let remaining = input.len();
+		skip(types, ty, input, depth + 1)?;
+		// Values that take no bytes are all the same, so there is no need to skip the rest.
+		if input.len() == remaining {
+			break;
+		}
+	}
+	Some(())
+}
+
+/// Take the first `len` bytes of `input`.
+pub(crate) fn take<'b>(input: &mut &'b [u8], len: usize) -> Option<&'b [u8]> {
+	if input.len() < len {
+		return None;
+	}
+	let (taken, rest) = input.split_at(len);
+	*input = rest;
+	Some(taken)
+}
@@ -0,0 +1,529 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Structural hashes of individual metadata items, such as a single call or storage entry.
+//!
+//! Clients that only use a few items of the metadata can keep the hashes of those items and
+//! compare them with the hashes computed from the metadata of a new runtime, instead of
+//! diffing the whole metadata. See [`ShapeHasher`](crate::shape_hash::ShapeHasher).
+//!
+//! Types are hashed by their structure, including the names and indexes of fields and
+//! variants, but not by their type ID, path, type parameters or documentation. The
+//! documentation and deprecation information of items is ignored as well, so equivalent items
+//! have the same hash in V14, V15 and V16 metadata, and in registries with different type IDs.
+
+use crate::{
+	metadata::{Constant, Metadata, Pallet, RuntimeApi, RuntimeApiMethod, StorageEntry},
+	v14,
+};
+use codec::Encode;
+use scale_info::{
+	form::PortableForm,
+	prelude::{collections::BTreeMap, vec::Vec},
+	Field, TypeDef, TypeDefPrimitive, Variant,
+};
+
+/// A 32 byte blake3 hash.
+pub type Hash = [u8; 32];
+
+/// The hashed structure of a type, with the hashes of the types it refers to.
+#[derive(Encode)]
+enum TypeShape<'a> {
+	Composite(Vec<FieldShape<'a>>),
+	Variant(Vec<VariantShape<'a>>),
+	Sequence(Hash),
+	Array(u32, Hash),
+	Tuple(Vec<Hash>),
+	Primitive(&'a TypeDefPrimitive),
+	Compact(Hash),
+	BitSequence(Hash, Hash),
+	/// A reference to the type that is being hashed the given number of levels further up,
+	/// which ends the hashing of recursive types.
+	Recursive(u32),
+	/// A type ID that is not in the registry.
+	Missing,
+}
+
+#[derive(Encode)]
+struct FieldShape<'a> {
+	name: Option<&'a str>,
+	ty: Hash,
+}
+
+#[derive(Encode)]
+struct VariantShape<'a> {
+	name: &'a str,
+	index: u8,
+	fields: Vec<FieldShape<'a>>,
+}
+
+/// Computes the structural hashes of the items of a metadata.
+///
+/// Type hashes are cached, so one hasher should be reused for all items of the same metadata.
+pub struct ShapeHasher<'a> {
+	metadata: &'a Metadata,
+	/// The hashes of the types that are not part of a cycle.
+	cache: BTreeMap<u32, Hash>,
+	/// The types being hashed, outermost first.
+	stack: Vec<u32>,
+}
+
+impl<'a> ShapeHasher<'a> {
+	/// Create a hasher for the items of `metadata`.
+	pub fn new(metadata: &'a Metadata) -> Self {
+		ShapeHasher {
+			metadata,
+			cache: BTreeMap::new(),
+			stack: Vec::new(),
+		}
+	}
+
+	/// The hash of the type with the given ID.
+	///
+	/// Recursive types are followed until they refer back to a type that is already being
+	/// hashed, which is hashed as a reference to it instead.
+	pub fn ty(&mut self, id: u32) -> Hash {
+		self.hash_type(id).0
+	}
+
+	/// The hash of a call, event or error variant.
+	///
+	/// The index of the pallet is not part of the hash, see [`Self::pallet`].
+	pub fn variant(&mut self, variant: &Variant<PortableForm>) -> Hash {
+		let mut refs = usize::MAX;
+		hash(&self.variant_shape(variant, &mut refs))
+	}
+
+	/// The hash of a storage entry.
+	///
+	/// The storage prefix of the pallet is not part of the hash, see [`Self::pallet`].
+	pub fn storage_entry(&mut self, entry: &StorageEntry) -> Hash {
+		let (hashers, key, value) = match entry.ty {
+			v14::StorageEntryType::Plain(value) => (&[][..], None, value.id),
+			v14::StorageEntryType::Map {
+				hashers,
+				key,
+				value,
+			} => (&hashers[..], Some(self.ty(key.id)), value.id),
+		};
+		hash(&(
+			entry.name,
+			entry.modifier,
+			hashers,
+			key,
+			self.ty(value),
+			entry.default,
+		))
+	}
+
+	/// The hash of a pallet constant, including its value.
+	pub fn constant(&mut self, constant: &Constant) -> Hash {
+		hash(&(constant.name, self.ty(constant.ty), constant.value))
+	}
+
+	/// The hash of a runtime API method.
+	pub fn runtime_api_method(&mut self, method: &RuntimeApiMethod) -> Hash {
+		let inputs: Vec<_> = method
+			.inputs
+			.iter()
+			.map(|(name, ty)| (*name, self.ty(*ty)))
+			.collect();
+		hash(&(method.name, inputs, self.ty(method.output)))
+	}
+
+	/// The hash of a runtime API, with all of its methods.
+	///
+	/// The version of the API is not part of the hash, since only V16 metadata records it.
+	pub fn runtime_api(&mut self, api: &RuntimeApi) -> Hash {
+		let methods = by_name(
+			api.methods()
+				.map(|method| (method.name, self.runtime_api_method(&method))),
+		);
+		hash(&(api.name(), methods))
+	}
+
+	/// The hash of a pallet, with all of its calls, events, errors, storage entries,
+	/// constants, associated types and view functions.
+	pub fn pallet(&mut self, pallet: &Pallet) -> Hash {
+		let mut variants = |variants: &[Variant<PortableForm>]| {
+			by_name(
+				variants
+					.iter()
+					.map(|variant| (AsRef::<str>::as_ref(&variant.name), self.variant(variant))),
+			)
+		};
+		let calls = variants(pallet.calls());
+		let events = variants(pallet.events());
+		let errors = variants(pallet.errors());
+
+		let storage = by_name(
+			pallet
+				.storage_entries()
+				.map(|entry| (entry.name, self.storage_entry(&entry))),
+		);
+		let constants = by_name(
+			pallet
+				.constants()
+				.map(|constant| (constant.name, self.constant(&constant))),
+		);
+		let associated_types = by_name(pallet.associated_types().iter().map(|ty| {
+			let name = AsRef::<str>::as_ref(&ty.name);
+			(name, hash(&(name, self.ty(ty.ty.id))))
+		}));
+		let view_functions = by_name(pallet.view_functions().iter().map(|function| {
+			let name = AsRef::<str>::as_ref(&function.name);
+			let inputs: Vec<_> = function
+				.inputs
+				.iter()
+				.map(|input| (AsRef::<str>::as_ref(&input.name), self.ty(input.ty.id)))
+				.collect();
+			let output = self.ty(function.output.id);
+			(name, hash(&(function.id, name, inputs, output)))
+		}));
+
+		hash(&(
+			pallet.name(),
+			pallet.index(),
+			pallet.storage_prefix(),
+			calls,
+			events,
+			errors,
+			storage,
+			constants,
+			associated_types,
+			view_functions,
+		))
+	}
+
+	/// The hash of the whole metadata: all pallets, runtime APIs and the extrinsic format.
+	///
+	/// The outer enums and custom values are not part of the hash, since V14 metadata does not
+	/// describe them.
+	pub fn metadata(&mut self) -> Hash {
+		let metadata = self.metadata;
+		let pallets = by_name(
+			metadata
+				.pallets()
+				.map(|pallet| (pallet.name(), self.pallet(&pallet))),
+		);
+		let apis = by_name(
+			metadata
+				.runtime_apis()
+				.map(|api| (api.name(), self.runtime_api(&api))),
+		);
+
+		let extrinsic = metadata.extrinsic();
+		let extensions: Vec<_> = extrinsic
+			.transaction_extension_versions()
+			.map(|version| {
+				let extensions: Vec<_> = extrinsic
+					.transaction_extensions_by_version(version)
+					.unwrap_or_default()
+					.into_iter()
+					.map(|ext| (ext.identifier, self.ty(ext.ty), self.ty(ext.implicit)))
+					.collect();
+				(version, extensions)
+			})
+			.collect();
+		let extrinsic = (
+			extrinsic.versions(),
+			extensions,
+			extrinsic.address_ty().map(|ty| self.ty(ty)),
+			extrinsic.call_ty().map(|ty| self.ty(ty)),
+			extrinsic.signature_ty().map(|ty| self.ty(ty)),
+		);
+
+		hash(&(pallets, apis, extrinsic))
+	}
+
+	/// Hash a type, also returning the position in [`Self::stack`] of the outermost type it
+	/// refers to, or `usize::MAX` if it refers to none.
+	fn hash_type(&mut self, id: u32) -> (Hash, usize) {
+		if let Some(hash) = self.cache.get(&id) {
+			return (*hash, usize::MAX);
+		}
+		if let Some(position) = self.stack.iter().position(|&ty| ty == id) {
+			let levels = (self.stack.len() - position) as u32;
+			return (hash(&TypeShape::Recursive(levels)), position);
+		}
+		let Some(ty) = self.metadata.types().resolve(id) else {
+			return (hash(&TypeShape::Missing), usize::MAX);
+		};
+
+		let position = self.stack.len();
+		self.stack.push(id);
+		let mut refs = usize::MAX;
+		let shape = match &ty.type_def {
+			TypeDef::Composite(composite) => {
+				TypeShape::Composite(self.fields(&composite.fields, &mut refs))
+			}
+			TypeDef::Variant(variant) => {
+				let mut variants: Vec<_> = variant
+					.variants
+					.iter()
+					.map(|variant| self.variant_shape(variant, &mut refs))
+					.collect();
+				variants.sort_by_key(|variant| variant.index);
+				TypeShape::Variant(variants)
+			}
+			TypeDef::Sequence(sequence) => {
+				TypeShape::Sequence(self.child(sequence.type_param.id, &mut refs))
+			}
+			TypeDef::Array(array) => {
+				TypeShape::Array(array.len, self.child(array.type_param.id, &mut refs))
+			}
+			TypeDef::Tuple(tuple) => TypeShape::Tuple(
+				tuple
+					.fields
+					.iter()
+					.map(|ty| self.child(ty.id, &mut refs))
+					.collect(),
+			),
+			TypeDef::Primitive(primitive) => TypeShape::Primitive(primitive),
+			TypeDef::Compact(compact) => {
+				TypeShape::Compact(self.child(compact.type_param.id, &mut refs))
+			}
+			TypeDef::BitSequence(bits) => TypeShape::BitSequence(
+				self.child(bits.bit_store_type.id, &mut refs),
+				self.child(bits.bit_order_type.id, &mut refs),
+			),
+		};
+		self.stack.pop();
+
+		let hash = hash(&shape);
+		// The hash of a type that is part of a cycle depends on where the cycle was entered,
+		// so only types that do not refer back to themselves or to a type further up are
+		// cached.
+		if refs > position {
+			self.cache.insert(id, hash);
+		}
+		(hash, refs)
+	}
+
+	fn child(&mut self, id: u32, refs: &mut usize) -> Hash {
+		let (hash, child_refs) = self.hash_type(id);
+		*refs = (*refs).min(child_refs);
+		hash
+	}
+
+	fn fields<'b>(
+		&mut self,
+		fields: &'b [Field<PortableForm>],
+		refs: &mut usize,
+	) -> Vec<FieldShape<'b>> {
+		fields
+			.iter()
+			.map(|field| FieldShape {
+				name: field.name.as_ref().map(AsRef::<str>::as_ref),
+				ty: self.child(field.ty.id, refs),
+			})
+			.collect()
+	}
+
+	fn variant_shape<'b>(
+		&mut self,
+		variant: &'b Variant<PortableForm>,
+		refs: &mut usize,
+	) -> VariantShape<'b> {
+		VariantShape {
+			name: AsRef::<str>::as_ref(&variant.name),
+			index: variant.index,
+			fields: self.fields(&variant.fields, refs),
+		}
+	}
+}
+
+/// The hashes of named items, ordered by name so that reordering the items does not change
+/// the hash.
+fn by_name<'b>(items: impl Iterator<Item = (&'b str, Hash)>) -> Vec<Hash> {
+	items.collect::<BTreeMap<_, _>>().into_values().collect()
+}
+
+fn hash(value: &impl Encode) -> Hash {
+	blake3::hash(&value.encode()).into()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test::{kusama_v14, kusama_v15, kusama_v16, metadata_with_calls, Tree};
+	use scale_info::{meta_type, TypeInfo};
+
+	#[allow(dead_code)]
+	#[derive(TypeInfo)]
+	enum Call {
+		Grow(Tree),
+		Toggle { flag: bool },
+	}
+
+	#[allow(dead_code)]
+	#[derive(TypeInfo)]
+	enum RenamedCall {
+		Grow(Tree),
+		Toggle { enabled: bool },
+	}
+
+	#[allow(dead_code)]
+	#[derive(TypeInfo)]
+	enum OtherTree {
+		Leaf(u32),
+		Node(Vec<OtherTree>),
+	}
+
+	fn type_id(metadata: &Metadata, name: &str) -> u32 {
+		metadata
+			.types()
+			.types
+			.iter()
+			.find(|ty| ty.ty.path.ident().as_deref() == Some(name))
+			.unwrap()
+			.id
+	}
+
+	#[test]
+	fn should_hash_equivalent_metadata_versions_equally() {
+		let v14 = kusama_v14();
+		let v15 = kusama_v15();
+		let v16 = kusama_v16();
+		let v14 = Metadata::from(v14);
+		let mut expected = ShapeHasher::new(&v14);
+
+		for metadata in [v15.into(), v16.into()] {
+			let mut hasher = ShapeHasher::new(&metadata);
+			assert_eq!(hasher.metadata(), expected.metadata());
+			for (pallet, expected_pallet) in metadata.pallets().zip(v14.pallets()) {
+				assert_eq!(hasher.pallet(&pallet), expected.pallet(&expected_pallet));
+			}
+		}
+	}
+
+	#[test]
+	fn should_hash_the_extensions_of_every_extension_version() {
+		let v16 = kusama_v16();
+		let expected = ShapeHasher::new(&v16.clone().into()).metadata();
+
+		let mut changed = v16;
+		changed
+			.extrinsic
+			.transaction_extensions_by_version
+			.get_mut(&0)
+			.unwrap()
+			.pop();
+		assert_ne!(ShapeHasher::new(&changed.into()).metadata(), expected);
+	}
+
+	#[test]
+	fn should_only_change_the_hashes_of_changed_items() {
+		let old = kusama_v14();
+		let mut new = old.clone();
+		let system = new.pallets.iter_mut().find(|p| p.name == "System").unwrap();
+		let account = system
+			.storage
+			.as_mut()
+			.unwrap()
+			.entries
+			.iter_mut()
+			.find(|e| e.name == "Account")
+			.unwrap();
+		let v14::StorageEntryType::Map { hashers, .. } = &mut account.ty else {
+			panic!("System::Account should be a map")
+		};
+		hashers[0] = v14::StorageHasher::Twox64Concat;
+
+		let (old, new) = (Metadata::from(old), Metadata::from(new));
+		let (mut old_hasher, mut new_hasher) = (ShapeHasher::new(&old), ShapeHasher::new(&new));
+		let mut entry_hashes = |pallet: &str, entry: &str| {
+			let old = old.pallet_by_name(pallet).unwrap().storage_entry(entry);
+			let new = new.pallet_by_name(pallet).unwrap().storage_entry(entry);
+			(
+				old_hasher.storage_entry(&old.unwrap()),
+				new_hasher.storage_entry(&new.unwrap()),
+			)
+		};
+		let (old_account, new_account) = entry_hashes("System", "Account");
+		assert_ne!(old_account, new_account);
+		let (old_number, new_number) = entry_hashes("System", "Number");
+		assert_eq!(old_number, new_number);
+
+		let mut hasher = ShapeHasher::new(&old);
+		let mut new_hasher = ShapeHasher::new(&new);
+		let mut pallet_hashes = |name: &str| {
+			(
+				hasher.pallet(&old.pallet_by_name(name).unwrap()),
+				new_hasher.pallet(&new.pallet_by_name(name).unwrap()),
+			)
+		};
+		let (old_system, new_system) = pallet_hashes("System");
+		assert_ne!(old_system, new_system);
+		let (old_balances, new_balances) = pallet_hashes("Balances");
+		assert_eq!(old_balances, new_balances);
+		assert_ne!(
+			ShapeHasher::new(&old).metadata(),
+			ShapeHasher::new(&new).metadata()
+		);
+	}
+
+	#[test]
+	fn should_not_depend_on_type_ids() {
+		let a = ("A", 0, meta_type::<Call>());
+		let b = ("B", 1, meta_type::<OtherTree>());
+		let old = metadata_with_calls(&[a, b]);
+		let new = metadata_with_calls(&[b, a]);
+		assert_ne!(type_id(&old, "Call"), type_id(&new, "Call"));
+
+		let mut old_hasher = ShapeHasher::new(&old);
+		let mut new_hasher = ShapeHasher::new(&new);
+		assert_eq!(
+			old_hasher.ty(type_id(&old, "Call")),
+			new_hasher.ty(type_id(&new, "Call"))
+		);
+		assert_eq!(old_hasher.metadata(), new_hasher.metadata());
+
+		let renamed = metadata_with_calls(&[("A", 0, meta_type::<RenamedCall>()), b]);
+		let mut renamed_hasher = ShapeHasher::new(&renamed);
+		let calls = |metadata: &Metadata| metadata.pallet_by_name("A").unwrap().calls().to_vec();
+		let (old_calls, renamed_calls) = (calls(&old), calls(&renamed));
+		assert_eq!(
+			old_hasher.variant(&old_calls[0]),
+			renamed_hasher.variant(&renamed_calls[0])
+		);
+		assert_ne!(
+			old_hasher.variant(&old_calls[1]),
+			renamed_hasher.variant(&renamed_calls[1])
+		);
+	}
+
+	#[test]
+	fn should_hash_recursive_types() {
+		let metadata = metadata_with_calls(&[
+			("A", 0, meta_type::<Call>()),
+			("B", 1, meta_type::<OtherTree>()),
+		]);
+		let tree = type_id(&metadata, "Tree");
+		let other_tree = type_id(&metadata, "OtherTree");
+		let TypeDef::Variant(variant) = &metadata.types().resolve(tree).unwrap().type_def else {
+			panic!("Tree should be a variant type")
+		};
+		let trees = variant.variants[1].fields[0].ty.id;
+
+		let expected = ShapeHasher::new(&metadata).ty(tree);
+		assert_ne!(expected, ShapeHasher::new(&metadata).ty(other_tree));
+
+		// Hashing `Vec<Tree>` first must not leave a different hash of `Tree` in the cache.
+		let mut hasher = ShapeHasher::new(&metadata);
+		let trees_hash = hasher.ty(trees);
+		assert_eq!(hasher.ty(tree), expected);
+		assert_eq!(ShapeHasher::new(&metadata).ty(trees), trees_hash);
+	}
+}
@@ -0,0 +1,498 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage keys of the storage entries described by the metadata.
+//!
+//! The key of a storage entry is the 128-bit XX hash of the pallet's storage prefix, followed
+//! by the 128-bit XX hash of the entry name and, for maps, by each SCALE encoded key hashed
+//! with its [`StorageHasher`](crate::v14::StorageHasher). Leaving out trailing keys of a map
+//! gives the prefix shared by all of its keys that start with the given ones, which is used to
+//! iterate over them. See [`storage_key`](crate::storage_key::storage_key).
+//!
+//! In the other direction, a [`StorageKeyDecoder`](crate::storage_key::StorageKeyDecoder)
+//! finds the storage entry of a raw storage key and recovers the keys that are not hidden by
+//! their hash.
+
+use crate::{
+	metadata::{Metadata, Pallet, StorageEntry},
+	scale::{encoded_len, take},
+	v14::{StorageEntryType, StorageHasher},
+};
+use scale_info::{
+	form::PortableForm,
+	prelude::{collections::BTreeMap, vec, vec::Vec},
+	PortableRegistry, TypeDef,
+};
+
+/// An error that can occur when computing a storage key.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StorageKeyError {
+	/// More keys were given than the storage entry has.
+	TooManyKeys {
+		/// The number of keys of the storage entry, which is zero for plain entries.
+		expected: usize,
+		/// The number of keys given.
+		given: usize,
+	},
+	/// The key does not start with the prefix of a storage entry of the metadata.
+	UnknownStorageEntry,
+	/// The key of a map with several hashers is not a tuple with a type per hasher.
+	KeyTypeMismatch,
+	/// The key with the given index of a map could not be split off the storage key.
+	InvalidKey {
+		/// The index of the key among the keys of the map.
+		index: usize,
+	},
+	/// The storage key is longer than the key of its storage entry.
+	TrailingBytes,
+}
+
+impl core::fmt::Display for StorageKeyError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			StorageKeyError::TooManyKeys { expected, given } => {
+				write!(
+					f,
+					"{given} keys given for a storage entry with {expected} keys"
+				)
+			}
+			StorageKeyError::UnknownStorageEntry => {
+				write!(f, "storage key does not belong to a known storage entry")
+			}
+			StorageKeyError::KeyTypeMismatch => {
+				write!(f, "storage map key type does not have a type per hasher")
+			}
+			StorageKeyError::InvalidKey { index } => {
+				write!(f, "key {index} of the storage key is invalid")
+			}
+			StorageKeyError::TrailingBytes => {
+				write!(f, "storage key is longer than the key of its storage entry")
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StorageKeyError {}
+
+impl StorageHasher {
+	/// Hash `data`, appending `data` itself for the concatenating hashers.
+	pub fn hash(&self, data: &[u8]) -> Vec<u8> {
+		let mut hashed = Vec::with_capacity(self.hash_len() + data.len());
+		match self {
+			StorageHasher::Blake2_128 | StorageHasher::Blake2_128Concat => {
+				hashed.extend_from_slice(&blake2(16, data))
+			}
+			StorageHasher::Blake2_256 => hashed.extend_from_slice(&blake2(32, data)),
+			StorageHasher::Twox128 => hashed.extend_from_slice(&twox(2, data)),
+			StorageHasher::Twox256 => hashed.extend_from_slice(&twox(4, data)),
+			StorageHasher::Twox64Concat => hashed.extend_from_slice(&twox(1, data)),
+			StorageHasher::Identity => {}
+		}
+		if self.is_concat() {
+			hashed.extend_from_slice(data);
+		}
+		hashed
+	}
+
+	/// The length of the hash, not counting the data appended by the concatenating hashers.
+	pub fn hash_len(&self) -> usize {
+		match self {
+			StorageHasher::Blake2_128 | StorageHasher::Blake2_128Concat => 16,
+			StorageHasher::Twox128 => 16,
+			StorageHasher::Blake2_256 | StorageHasher::Twox256 => 32,
+			StorageHasher::Twox64Concat => 8,
+			StorageHasher::Identity => 0,
+		}
+	}
+
+	/// Whether the hashed data is appended to the hash, which allows recovering it from a key.
+	pub fn is_concat(&self) -> bool {
+		matches!(
+			self,
+			StorageHasher::Blake2_128Concat | StorageHasher::Twox64Concat | StorageHasher::Identity
+		)
+	}
+}
+
+/// The key of a plain storage entry, which is also the prefix of all keys of a map.
+pub fn storage_prefix(pallet_prefix: &str, entry: &str) -> [u8; 32] {
+	let mut prefix = [0; 32];
+	prefix[..16].copy_from_slice(&twox(2, pallet_prefix.as_bytes()));
+	prefix[16..].copy_from_slice(&twox(2, entry.as_bytes()));
+	prefix
+}
+
+/// Compute the key of the storage entry `entry` of the pallet with storage prefix
+/// `pallet_prefix`, given the SCALE encoded keys of a map.
+///
+/// A map has a key per hasher, and if it has several, its key type is a tuple of their types.
+/// Giving fewer keys than the map has returns the prefix of the keys starting with them, and
+/// no keys at all returns the [`storage_prefix`]. The keys are not checked against the key
+/// type.
+pub fn storage_key<K: AsRef<[u8]>>(
+	pallet_prefix: &str,
+	entry: &str,
+	ty: &StorageEntryType<PortableForm>,
+	keys: &[K],
+) -> Result<Vec<u8>, StorageKeyError> {
+	let hashers = match ty {
+		StorageEntryType::Plain(_) => &[][..],
+		StorageEntryType::Map { hashers, .. } => &hashers[..],
+	};
+	if keys.len() > hashers.len() {
+		return Err(StorageKeyError::TooManyKeys {
+			expected: hashers.len(),
+			given: keys.len(),
+		});
+	}
+
+	let mut storage_key = storage_prefix(pallet_prefix, entry).to_vec();
+	for (hasher, key) in hashers.iter().zip(keys) {
+		storage_key.extend(hasher.hash(key.as_ref()));
+	}
+	Ok(storage_key)
+}
+
+/// A storage key split into the storage entry it belongs to and its keys.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DecodedStorageKey<'a> {
+	/// The pallet of the storage entry.
+	pub pallet: Pallet<'a>,
+	/// The storage entry.
+	pub entry: StorageEntry<'a>,
+	/// The keys of a map, one per hasher, which are empty for plain entries.
+	pub keys: Vec<DecodedKey>,
+}
+
+/// A hashed key of a storage map.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DecodedKey {
+	/// The hasher of the key.
+	pub hasher: StorageHasher,
+	/// The type ID of the key.
+	pub ty: u32,
+	/// The hash of the key, without the key appended by the concatenating hashers.
+	pub hash: Vec<u8>,
+	/// The SCALE encoded key, if the hasher appends it to the hash.
+	pub value: Option<Vec<u8>>,
+}
+
+/// Splits raw storage keys into the storage entries and keys they are made of.
+///
+/// The prefixes of all storage entries are hashed once when the decoder is created, so one
+/// decoder should be reused for all keys of the same metadata.
+pub struct StorageKeyDecoder<'a> {
+	types: &'a PortableRegistry,
+	entries: BTreeMap<[u8; 32], (Pallet<'a>, StorageEntry<'a>)>,
+}
+
+impl<'a> StorageKeyDecoder<'a> {
+	/// Create a decoder for the storage keys of `metadata`.
+	pub fn new(metadata: &'a Metadata) -> Self {
+		let mut entries = BTreeMap::new();
+		for pallet in metadata.pallets() {
+			let Some(prefix) = pallet.storage_prefix() else {
+				continue;
+			};
+			for entry in pallet.storage_entries() {
+				entries
+					.entry(storage_prefix(prefix, entry.name))
+					.or_insert((pallet, entry));
+			}
+		}
+		StorageKeyDecoder {
+			types: metadata.types(),
+			entries,
+		}
+	}
+
+	/// Decode the full key of a storage entry.
+	///
+	/// Keys that are hashed by a concatenating hasher are recovered by decoding a value of the
+	/// key type, while the other keys are only known by their hash.
+	pub fn decode(&self, key: &[u8]) -> Result<DecodedStorageKey<'a>, StorageKeyError> {
+		let (pallet, entry) = key
+			.get(..32)
+			.and_then(|prefix| self.entries.get(prefix))
+			.copied()
+			.ok_or(StorageKeyError::UnknownStorageEntry)?;
+		let mut input = &key[32..];
+
+		let mut keys = Vec::new();
+		if let StorageEntryType::Map {
+			hashers,
+			key: key_ty,
+			..
+		} = entry.ty
+		{
+			let key_tys = self.key_types(hashers.len(), key_ty.id)?;
+			for (index, (hasher, ty)) in hashers.iter().zip(key_tys).enumerate() {
+				let invalid = StorageKeyError::InvalidKey { index };
+				let hash = take(&mut input, hasher.hash_len()).ok_or(invalid)?;
+				let value = if hasher.is_concat() {
+					let len = encoded_len(self.types, ty, input).ok_or(invalid)?;
+					Some(take(&mut input, len).ok_or(invalid)?.to_vec())
+				} else {
+					None
+				};
+				keys.push(DecodedKey {
+					hasher: hasher.clone(),
+					ty,
+					hash: hash.to_vec(),
+					value,
+				});
+			}
+		}
+
+		if !input.is_empty() {
+			return Err(StorageKeyError::TrailingBytes);
+		}
+		Ok(DecodedStorageKey {
+			pallet,
+			entry,
+			keys,
+		})
+	}
+
+	/// The type IDs of the keys of a map with `hashers` hashers and the given key type.
+	fn key_types(&self, hashers: usize, key: u32) -> Result<Vec<u32>, StorageKeyError> {
+		if hashers == 1 {
+			return Ok(vec![key]);
+		}
+		match self.types.resolve(key).map(|ty| &ty.type_def) {
+			Some(TypeDef::Tuple(tuple)) if tuple.fields.len() == hashers => {
+				Ok(tuple.fields.iter().map(|ty| ty.id).collect())
+			}
+			_ => Err(StorageKeyError::KeyTypeMismatch),
+		}
+	}
+}
+
+/// The blake2b hash of `data` with the given length in bytes.
+fn blake2(len: usize, data: &[u8]) -> Vec<u8> {
+	blake2b_simd::Params::new()
+		.hash_length(len)
+		.hash(data)
+		.as_bytes()
+		.to_vec()
+}
+
+/// The concatenated little endian 64-bit XX hashes of `data` with the seeds `0..hashes`.
+fn twox(hashes: u64, data: &[u8]) -> Vec<u8> {
+	(0..hashes)
+		.flat_map(|seed| twox_hash::XxHash64::oneshot(seed, data).to_le_bytes())
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{metadata::Metadata, test::kusama_v14};
+	use codec::Encode;
+
+	fn hex(bytes: &[u8]) -> String {
+		bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+	}
+
+	#[test]
+	fn should_hash_with_every_hasher() {
+		let cases = [
+			(
+				StorageHasher::Blake2_128,
+				"cae66941d9efbd404e4d88758ea67670",
+			),
+			(
+				StorageHasher::Blake2_256,
+				"0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8",
+			),
+			(StorageHasher::Twox128, "99e9d85137db46ef4bbea33613baafd5"),
+			(
+				StorageHasher::Twox256,
+				"99e9d85137db46ef4bbea33613baafd56f963c64b1f3685a4eb4abd67ff6203a",
+			),
+			(StorageHasher::Twox64Concat, "99e9d85137db46ef"),
+			(StorageHasher::Identity, ""),
+		];
+		for (hasher, expected) in cases {
+			assert_eq!(hex(&hasher.hash(&[])), expected, "hashing with {hasher:?}");
+			assert_eq!(hasher.hash(&[]).len(), hasher.hash_len());
+		}
+
+		let hashed = StorageHasher::Blake2_128Concat.hash(&[1, 2, 3]);
+		assert_eq!(hashed[..16], StorageHasher::Blake2_128.hash(&[1, 2, 3]));
+		assert_eq!(hashed[16..], [1, 2, 3]);
+		assert_eq!(StorageHasher::Identity.hash(&[1, 2, 3]), [1, 2, 3]);
+	}
+
+	#[test]
+	fn should_compute_storage_keys() {
+		let metadata = Metadata::from(kusama_v14());
+		let system = metadata.pallet_by_name("System").unwrap();
+		let prefix = system.storage_prefix().unwrap();
+
+		let number = system.storage_entry("Number").unwrap();
+		let key = storage_key::<&[u8]>(prefix, number.name, number.ty, &[]).unwrap();
+		assert_eq!(
+			hex(&key),
+			"26aa394eea5630e07c48ae0c9558cef702a5c1b19ab7a04f536c519aca4983ac"
+		);
+		assert_eq!(
+			storage_key(prefix, number.name, number.ty, &[[0u8]]),
+			Err(StorageKeyError::TooManyKeys {
+				expected: 0,
+				given: 1
+			})
+		);
+
+		// The account of Alice in the development chains.
+		let alice = [
+			0xd4, 0x35, 0x93, 0xc7, 0x15, 0xfd, 0xd3, 0x1c, 0x61, 0x14, 0x1a, 0xbd, 0x04, 0xa9,
+			0x9f, 0xd6, 0x82, 0x2c, 0x85, 0x58, 0x85, 0x4c, 0xcd, 0xe3, 0x9a, 0x56, 0x84, 0xe7,
+			0xa5, 0x6d, 0xa2, 0x7d,
+		];
+		let account = system.storage_entry("Account").unwrap();
+		let key = storage_key(prefix, account.name, account.ty, &[alice]).unwrap();
+		assert_eq!(
+			hex(&key),
+			"26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9\
+			 de1e86a9a8c739864cf3cc5ec2bea59fd43593c715fdd31c61141abd04a99fd6\
+			 822c8558854ccde39a5684e7a56da27d"
+		);
+	}
+
+	#[test]
+	fn should_compute_prefixes_of_partial_keys() {
+		let metadata = Metadata::from(kusama_v14());
+		let staking = metadata.pallet_by_name("Staking").unwrap();
+		let prefix = staking.storage_prefix().unwrap();
+		let stakers = staking.storage_entry("ErasStakers").unwrap();
+		let StorageEntryType::Map { hashers, .. } = stakers.ty else {
+			panic!("Staking::ErasStakers should be a map")
+		};
+		assert_eq!(
+			hashers,
+			&[StorageHasher::Twox64Concat, StorageHasher::Twox64Concat]
+		);
+
+		let era = 5u32.encode();
+		let validator = [7u8; 32].encode();
+		let key = |keys: &[&[u8]]| storage_key(prefix, stakers.name, stakers.ty, keys);
+
+		let full = key(&[&era, &validator]).unwrap();
+		let partial = key(&[&era]).unwrap();
+		let none = key(&[]).unwrap();
+		assert_eq!(none, storage_prefix(prefix, stakers.name));
+		assert_eq!(partial.len(), 32 + 8 + 4);
+		assert_eq!(partial[..32], none);
+		assert_eq!(full[..partial.len()], partial);
+		assert_eq!(
+			full[partial.len()..],
+			StorageHasher::Twox64Concat.hash(&validator)
+		);
+		assert_eq!(
+			key(&[&era, &validator, &era]),
+			Err(StorageKeyError::TooManyKeys {
+				expected: 2,
+				given: 3
+			})
+		);
+	}
+
+	#[test]
+	fn should_decode_storage_keys() {
+		let metadata = Metadata::from(kusama_v14());
+		let decoder = StorageKeyDecoder::new(&metadata);
+
+		let alice = [0xd4; 32];
+		let system = metadata.pallet_by_name("System").unwrap();
+		let account = system.storage_entry("Account").unwrap();
+		let key = storage_key("System", "Account", account.ty, &[alice]).unwrap();
+		let decoded = decoder.decode(&key).unwrap();
+		assert_eq!((decoded.pallet, decoded.entry), (system, account));
+		let [key] = &decoded.keys[..] else {
+			panic!("System::Account should have a single key")
+		};
+		assert_eq!(key.hasher, StorageHasher::Blake2_128Concat);
+		assert_eq!(key.hash, StorageHasher::Blake2_128.hash(&alice));
+		assert_eq!(key.value.as_deref(), Some(&alice[..]));
+
+		let number = storage_prefix("System", "Number");
+		let decoded = decoder.decode(&number).unwrap();
+		assert_eq!(decoded.entry.name, "Number");
+		assert!(decoded.keys.is_empty());
+
+		let stakers = metadata
+			.pallet_by_name("Staking")
+			.unwrap()
+			.storage_entry("ErasStakers")
+			.unwrap();
+		let (era, validator) = (5u32.encode(), [7u8; 32].encode());
+		let key = storage_key("Staking", "ErasStakers", stakers.ty, &[&era, &validator]).unwrap();
+		let values: Vec<_> = decoder
+			.decode(&key)
+			.unwrap()
+			.keys
+			.into_iter()
+			.map(|key| key.value.unwrap())
+			.collect();
+		assert_eq!(values, [era, validator]);
+	}
+
+	#[test]
+	fn should_report_opaque_keys_by_their_hash() {
+		let mut v14 = kusama_v14();
+		let system = v14.pallets.iter_mut().find(|p| p.name == "System").unwrap();
+		let entries = &mut system.storage.as_mut().unwrap().entries;
+		let account = entries.iter_mut().find(|e| e.name == "Account").unwrap();
+		let StorageEntryType::Map { hashers, .. } = &mut account.ty else {
+			panic!("System::Account should be a map")
+		};
+		hashers[0] = StorageHasher::Twox256;
+		let ty = account.ty.clone();
+		let metadata = Metadata::from(v14);
+
+		let key = storage_key("System", "Account", &ty, &[[0xd4; 32]]).unwrap();
+		let decoded = StorageKeyDecoder::new(&metadata).decode(&key).unwrap();
+		assert_eq!(decoded.keys[0].hash, key[32..]);
+		assert_eq!(decoded.keys[0].value, None);
+	}
+
+	#[test]
+	fn should_reject_invalid_storage_keys() {
+		let metadata = Metadata::from(kusama_v14());
+		let decoder = StorageKeyDecoder::new(&metadata);
+		let account = storage_prefix("System", "Account");
+
+		assert_eq!(
+			decoder.decode(&account[..16]),
+			Err(StorageKeyError::UnknownStorageEntry)
+		);
+		assert_eq!(
+			decoder.decode(&storage_prefix("System", "NotAnEntry")),
+			Err(StorageKeyError::UnknownStorageEntry)
+		);
+		let truncated = [&account[..], &[0; 16], &[0xd4; 31]].concat();
+		assert_eq!(
+			decoder.decode(&truncated),
+			Err(StorageKeyError::InvalidKey { index: 0 })
+		);
+		let trailing = [&account[..], &[0; 16], &[0xd4; 33]].concat();
+		assert_eq!(
+			decoder.decode(&trailing),
+			Err(StorageKeyError::TrailingBytes)
+		);
+	}
+}
@@ -24,7 +24,7 @@ use codec::{Encode, Output};
 cfg_if::cfg_if! {
 	if #[cfg(feature = "std")] {
 		use codec::Decode;
-		use serde::Serialize;
+		use serde::{Deserialize, Serialize};
 
 		type StringBuf = String;
 	} else {
@@ -42,7 +42,7 @@ pub const META_RESERVED: u32 = 0x6174656d; // 'meta' warn endianness
 
 /// All the metadata about a function.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct FunctionMetadata {
 	pub name: DecodeDifferentStr,
 	pub arguments: DecodeDifferentArray<FunctionArgumentMetadata>,
@@ -51,7 +51,7 @@ pub struct FunctionMetadata {
 
 /// All the metadata about a function argument.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct FunctionArgumentMetadata {
 	pub name: DecodeDifferentStr,
 	pub ty: DecodeDifferentStr,
@@ -59,7 +59,7 @@ pub struct FunctionArgumentMetadata {
 
 /// All the metadata about an outer event.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct OuterEventMetadata {
 	pub name: DecodeDifferentStr,
 	pub events: DecodeDifferentArray<
@@ -70,7 +70,7 @@ pub struct OuterEventMetadata {
 
 /// All the metadata about an event.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct EventMetadata {
 	pub name: DecodeDifferentStr,
 	pub arguments: DecodeDifferentArray<&'static str, StringBuf>,
@@ -79,7 +79,7 @@ pub struct EventMetadata {
 
 /// All the metadata about one storage entry.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct StorageEntryMetadata {
 	pub name: DecodeDifferentStr,
 	pub modifier: StorageEntryModifier,
@@ -90,7 +90,7 @@ pub struct StorageEntryMetadata {
 
 /// All the metadata about one module constant.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct ModuleConstantMetadata {
 	pub name: DecodeDifferentStr,
 	pub ty: DecodeDifferentStr,
@@ -100,7 +100,7 @@ pub struct ModuleConstantMetadata {
 
 /// All the metadata about a module error.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct ErrorMetadata {
 	pub name: DecodeDifferentStr,
 	pub documentation: DecodeDifferentArray<&'static str, StringBuf>,
@@ -165,7 +165,7 @@ impl core::fmt::Debug for DefaultByteGetter {
 
 /// Hasher used by storage maps
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub enum StorageHasher {
 	Blake2_128,
 	Blake2_256,
@@ -177,7 +177,7 @@ pub enum StorageHasher {
 
 /// A storage entry type.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub enum StorageEntryType {
 	Plain(DecodeDifferentStr),
 	Map {
@@ -201,7 +201,7 @@ pub enum StorageEntryType {
 /// `Optional` means you should expect an `Option<T>`, with `None` returned if the key is not present.
 /// `Default` means you should expect a `T` with the default value of default if the key is not present.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub enum StorageEntryModifier {
 	Optional,
 	Default,
@@ -209,7 +209,7 @@ pub enum StorageEntryModifier {
 
 /// All metadata of the storage.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct StorageMetadata {
 	/// The common prefix used by all storage entries.
 	pub prefix: DecodeDifferent<&'static str, StringBuf>,
@@ -218,14 +218,14 @@ pub struct StorageMetadata {
 
 /// The metadata of a runtime.
 #[derive(Eq, Encode, PartialEq, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct RuntimeMetadataV10 {
 	pub modules: DecodeDifferentArray<ModuleMetadata>,
 }
 
 /// All metadata about an runtime module.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct ModuleMetadata {
 	pub name: DecodeDifferentStr,
 	pub storage: Option<DecodeDifferent<FnEncode<StorageMetadata>, StorageMetadata>>,
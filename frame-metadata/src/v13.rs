@@ -24,7 +24,7 @@ use codec::{Encode, Output};
 cfg_if::cfg_if! {
 	if #[cfg(feature = "std")] {
 		use codec::Decode;
-		use serde::Serialize;
+		use serde::{Deserialize, Serialize};
 	} else {
 		extern crate alloc;
 		use alloc::vec::Vec;
@@ -36,7 +36,7 @@ pub const META_RESERVED: u32 = 0x6174656d; // 'meta' warn endianness
 
 /// Metadata about a function.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct FunctionMetadata {
 	/// Function name.
 	pub name: DecodeDifferentStr,
@@ -48,7 +48,7 @@ pub struct FunctionMetadata {
 
 /// Metadata about a function argument.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct FunctionArgumentMetadata {
 	/// Name of the variable for the argument.
 	pub name: DecodeDifferentStr,
@@ -58,7 +58,7 @@ pub struct FunctionArgumentMetadata {
 
 /// Metadata about an outer event.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct OuterEventMetadata {
 	/// Name of the event.
 	pub name: DecodeDifferentStr,
@@ -71,7 +71,7 @@ pub struct OuterEventMetadata {
 
 /// Metadata about an event.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct EventMetadata {
 	/// Name of the event.
 	pub name: DecodeDifferentStr,
@@ -83,7 +83,7 @@ pub struct EventMetadata {
 
 /// Metadata about one storage entry.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct StorageEntryMetadata {
 	/// Variable name of the storage entry.
 	pub name: DecodeDifferentStr,
@@ -99,7 +99,7 @@ pub struct StorageEntryMetadata {
 
 /// Metadata about a module constant.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct ModuleConstantMetadata {
 	/// Name of the module constant.
 	pub name: DecodeDifferentStr,
@@ -113,7 +113,7 @@ pub struct ModuleConstantMetadata {
 
 /// Metadata about a module error.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct ErrorMetadata {
 	/// Name of the error.
 	pub name: DecodeDifferentStr,
@@ -182,7 +182,7 @@ impl core::fmt::Debug for DefaultByteGetter {
 
 /// Hasher used by storage maps
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub enum StorageHasher {
 	/// 128-bit Blake2 hash.
 	Blake2_128,
@@ -202,7 +202,7 @@ pub enum StorageHasher {
 
 /// A storage entry type.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub enum StorageEntryType {
 	/// Plain storage entry (just the value).
 	Plain(DecodeDifferentStr),
@@ -247,7 +247,7 @@ pub enum StorageEntryType {
 /// `Optional` means you should expect an `Option<T>`, with `None` returned if the key is not present.
 /// `Default` means you should expect a `T` with the default value of default if the key is not present.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub enum StorageEntryModifier {
 	/// The storage entry returns an `Option<T>`, with `None` if the key is not present.
 	Optional,
@@ -257,7 +257,7 @@ pub enum StorageEntryModifier {
 
 /// All metadata of the storage.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct StorageMetadata {
 	/// The common prefix used by all storage entries.
 	pub prefix: DecodeDifferent<&'static str, StringBuf>,
@@ -267,7 +267,7 @@ pub struct StorageMetadata {
 
 /// Metadata of the extrinsic used by the runtime.
 #[derive(Eq, Encode, PartialEq, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct ExtrinsicMetadata {
 	/// Extrinsic version.
 	pub version: u8,
@@ -277,7 +277,7 @@ pub struct ExtrinsicMetadata {
 
 /// The metadata of a runtime.
 #[derive(Eq, Encode, PartialEq, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct RuntimeMetadataV13 {
 	/// Metadata of all the modules.
 	pub modules: DecodeDifferentArray<ModuleMetadata>,
@@ -287,7 +287,7 @@ pub struct RuntimeMetadataV13 {
 
 /// All metadata about a runtime module.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct ModuleMetadata {
 	/// Module name.
 	pub name: DecodeDifferentStr,
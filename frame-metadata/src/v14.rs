@@ -17,6 +17,8 @@
 use codec::Decode;
 #[cfg(feature = "serde_full")]
 use serde::Serialize;
+#[cfg(all(feature = "serde_full", feature = "decode"))]
+use serde::{de::DeserializeOwned, Deserialize};
 
 use super::RuntimeMetadataPrefixed;
 use codec::Encode;
@@ -42,6 +44,7 @@ impl From<RuntimeMetadataLastVersion> for super::RuntimeMetadataPrefixed {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 pub struct RuntimeMetadataV14 {
 	/// Type registry containing all types used in the metadata.
 	pub types: PortableRegistry,
@@ -77,9 +80,13 @@ impl RuntimeMetadataV14 {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct ExtrinsicMetadata<T: Form = MetaForm> {
 	/// The type of the extrinsic.
@@ -106,9 +113,13 @@ impl IntoPortable for ExtrinsicMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct SignedExtensionMetadata<T: Form = MetaForm> {
 	/// The unique signed extension identifier, which may be different from the type name.
@@ -135,9 +146,13 @@ impl IntoPortable for SignedExtensionMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletMetadata<T: Form = MetaForm> {
 	/// Pallet name.
@@ -177,9 +192,13 @@ impl IntoPortable for PalletMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletStorageMetadata<T: Form = MetaForm> {
 	/// The common prefix used by all storage entries.
@@ -203,9 +222,13 @@ impl IntoPortable for PalletStorageMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct StorageEntryMetadata<T: Form = MetaForm> {
 	/// Variable name of the storage entry.
@@ -242,6 +265,7 @@ impl IntoPortable for StorageEntryMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 pub enum StorageEntryModifier {
 	/// The storage entry returns an `Option<T>`, with `None` if the key is not present.
 	Optional,
@@ -253,6 +277,7 @@ pub enum StorageEntryModifier {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 pub enum StorageHasher {
 	/// 128-bit Blake2 hash.
 	Blake2_128,
@@ -274,9 +299,13 @@ pub enum StorageHasher {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub enum StorageEntryType<T: Form = MetaForm> {
 	/// Plain storage entry (just the value).
@@ -315,9 +344,13 @@ impl IntoPortable for StorageEntryType {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletCallMetadata<T: Form = MetaForm> {
 	/// The corresponding enum type for the pallet call.
@@ -344,6 +377,7 @@ impl From<MetaType> for PalletCallMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 pub struct PalletEventMetadata<T: Form = MetaForm> {
 	/// The Event type.
 	pub ty: T::Type,
@@ -369,9 +403,13 @@ impl From<MetaType> for PalletEventMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletConstantMetadata<T: Form = MetaForm> {
 	/// Name of the pallet constant.
@@ -401,7 +439,14 @@ impl IntoPortable for PalletConstantMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
-#[cfg_attr(feature = "serde_full", serde(bound(serialize = "T::Type: Serialize")))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
+#[cfg_attr(
+	feature = "serde_full",
+	serde(bound(
+		serialize = "T::Type: Serialize",
+		deserialize = "T::Type: DeserializeOwned"
+	))
+)]
 pub struct PalletErrorMetadata<T: Form = MetaForm> {
 	/// The error type information.
 	pub ty: T::Type,
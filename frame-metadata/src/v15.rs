@@ -17,6 +17,8 @@
 use codec::Decode;
 #[cfg(feature = "serde_full")]
 use serde::Serialize;
+#[cfg(all(feature = "serde_full", feature = "decode"))]
+use serde::{de::DeserializeOwned, Deserialize};
 
 use super::{RuntimeMetadataPrefixed, META_RESERVED};
 use codec::Encode;
@@ -45,6 +47,7 @@ impl From<RuntimeMetadataLastVersion> for super::RuntimeMetadataPrefixed {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 pub struct RuntimeMetadataV15 {
 	/// Type registry containing all types used in the metadata.
 	pub types: PortableRegistry,
@@ -98,9 +101,13 @@ impl RuntimeMetadataV15 {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct RuntimeApiMetadata<T: Form = MetaForm> {
 	/// Trait name.
@@ -127,9 +134,13 @@ impl IntoPortable for RuntimeApiMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct RuntimeApiMethodMetadata<T: Form = MetaForm> {
 	/// Method name.
@@ -159,9 +170,13 @@ impl IntoPortable for RuntimeApiMethodMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct RuntimeApiMethodParamMetadata<T: Form = MetaForm> {
 	/// Parameter name.
@@ -185,9 +200,13 @@ impl IntoPortable for RuntimeApiMethodParamMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct ExtrinsicMetadata<T: Form = MetaForm> {
 	/// Extrinsic version.
@@ -225,9 +244,13 @@ impl IntoPortable for ExtrinsicMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct SignedExtensionMetadata<T: Form = MetaForm> {
 	/// The unique signed extension identifier, which may be different from the type name.
@@ -254,9 +277,13 @@ impl IntoPortable for SignedExtensionMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletMetadata<T: Form = MetaForm> {
 	/// Pallet name.
@@ -301,9 +328,13 @@ impl IntoPortable for PalletMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct CustomMetadata<T: Form = MetaForm> {
 	/// The custom map.
@@ -328,9 +359,13 @@ impl IntoPortable for CustomMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct CustomValueMetadata<T: Form = MetaForm> {
 	/// The custom type.
@@ -354,9 +389,13 @@ impl IntoPortable for CustomValueMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct OuterEnums<T: Form = MetaForm> {
 	/// The type of the outer `RuntimeCall` enum.
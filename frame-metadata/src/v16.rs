@@ -17,6 +17,8 @@
 use codec::Decode;
 #[cfg(feature = "serde_full")]
 use serde::Serialize;
+#[cfg(all(feature = "serde_full", feature = "decode"))]
+use serde::{de::DeserializeOwned, Deserialize};
 
 use super::{RuntimeMetadataPrefixed, META_RESERVED};
 use codec::{Compact, Encode};
@@ -47,6 +49,7 @@ impl From<RuntimeMetadataLastVersion> for super::RuntimeMetadataPrefixed {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 pub struct RuntimeMetadataV16 {
 	/// Type registry containing all types used in the metadata.
 	pub types: PortableRegistry,
@@ -95,9 +98,13 @@ impl RuntimeMetadataV16 {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct RuntimeApiMetadata<T: Form = MetaForm> {
 	/// Trait name.
@@ -130,9 +137,13 @@ impl IntoPortable for RuntimeApiMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct RuntimeApiMethodMetadata<T: Form = MetaForm> {
 	/// Method name.
@@ -165,9 +176,13 @@ impl IntoPortable for RuntimeApiMethodMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct ExtrinsicMetadata<T: Form = MetaForm> {
 	/// Extrinsic versions supported by the runtime.
@@ -210,9 +225,13 @@ impl IntoPortable for ExtrinsicMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct TransactionExtensionMetadata<T: Form = MetaForm> {
 	/// The unique transaction extension identifier, which may be different from the type name.
@@ -239,9 +258,13 @@ impl IntoPortable for TransactionExtensionMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletMetadata<T: Form = MetaForm> {
 	/// Pallet name.
@@ -293,9 +316,13 @@ impl IntoPortable for PalletMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletCallMetadata<T: Form = MetaForm> {
 	/// The corresponding enum type for the pallet call.
@@ -319,9 +346,13 @@ impl IntoPortable for PalletCallMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletStorageMetadata<T: Form = MetaForm> {
 	/// The common prefix used by all storage entries.
@@ -345,9 +376,13 @@ impl IntoPortable for PalletStorageMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct StorageEntryMetadata<T: Form = MetaForm> {
 	/// Variable name of the storage entry.
@@ -383,9 +418,13 @@ impl IntoPortable for StorageEntryMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletEventMetadata<T: Form = MetaForm> {
 	/// The Event type.
@@ -409,9 +448,13 @@ impl IntoPortable for PalletEventMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletConstantMetadata<T: Form = MetaForm> {
 	/// Name of the pallet constant.
@@ -444,9 +487,13 @@ impl IntoPortable for PalletConstantMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletErrorMetadata<T: Form = MetaForm> {
 	/// The error type information.
@@ -470,9 +517,13 @@ impl IntoPortable for PalletErrorMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletAssociatedTypeMetadata<T: Form = MetaForm> {
 	/// The name of the associated type.
@@ -499,9 +550,13 @@ impl IntoPortable for PalletAssociatedTypeMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct PalletViewFunctionMetadata<T: Form = MetaForm> {
 	/// Method id.
@@ -537,9 +592,13 @@ impl IntoPortable for PalletViewFunctionMetadata {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub enum ItemDeprecationInfo<T: Form = MetaForm> {
 	/// Item is not deprecated.
@@ -576,9 +635,13 @@ impl IntoPortable for ItemDeprecationInfo {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub struct EnumDeprecationInfo<T: Form = MetaForm>(pub BTreeMap<u8, VariantDeprecationInfo<T>>);
 
@@ -617,9 +680,13 @@ impl IntoPortable for EnumDeprecationInfo {
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
 #[cfg_attr(feature = "decode", derive(Decode))]
 #[cfg_attr(feature = "serde_full", derive(Serialize))]
+#[cfg_attr(all(feature = "serde_full", feature = "decode"), derive(Deserialize))]
 #[cfg_attr(
 	feature = "serde_full",
-	serde(bound(serialize = "T::Type: Serialize, T::String: Serialize"))
+	serde(bound(
+		serialize = "T::Type: Serialize, T::String: Serialize",
+		deserialize = "T::Type: DeserializeOwned, T::String: DeserializeOwned"
+	))
 )]
 pub enum VariantDeprecationInfo<T: Form = MetaForm> {
 	/// Variant is deprecated without a note.
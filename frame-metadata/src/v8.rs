@@ -23,7 +23,7 @@ use codec::{Encode, Output};
 cfg_if::cfg_if! {
 	if #[cfg(feature = "std")] {
 		use codec::Decode;
-		use serde::Serialize;
+		use serde::{Deserialize, Serialize};
 
 		type StringBuf = String;
 	} else {
@@ -41,7 +41,7 @@ pub const META_RESERVED: u32 = 0x6174656d; // 'meta' warn endianness
 
 /// All the metadata about a function.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct FunctionMetadata {
 	/// Function name.
 	pub name: DecodeDifferentStr,
@@ -53,7 +53,7 @@ pub struct FunctionMetadata {
 
 /// All the metadata about a function argument.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct FunctionArgumentMetadata {
 	/// Name of the variable for the argument.
 	pub name: DecodeDifferentStr,
@@ -63,7 +63,7 @@ pub struct FunctionArgumentMetadata {
 
 /// All the metadata about an outer event.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct OuterEventMetadata {
 	/// Name of the event.
 	pub name: DecodeDifferentStr,
@@ -76,7 +76,7 @@ pub struct OuterEventMetadata {
 
 /// All the metadata about an event.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct EventMetadata {
 	/// Name of the event.
 	pub name: DecodeDifferentStr,
@@ -88,7 +88,7 @@ pub struct EventMetadata {
 
 /// All the metadata about one storage entry.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct StorageEntryMetadata {
 	/// Variable name of the storage entry.
 	pub name: DecodeDifferentStr,
@@ -104,7 +104,7 @@ pub struct StorageEntryMetadata {
 
 /// All the metadata about one module constant.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct ModuleConstantMetadata {
 	/// Name of the module constant.
 	pub name: DecodeDifferentStr,
@@ -118,7 +118,7 @@ pub struct ModuleConstantMetadata {
 
 /// All the metadata about a module error.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct ErrorMetadata {
 	/// Name of the error.
 	pub name: DecodeDifferentStr,
@@ -187,7 +187,7 @@ impl core::fmt::Debug for DefaultByteGetter {
 
 /// Hasher used by storage maps
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub enum StorageHasher {
 	/// 128-bit Blake2 hash.
 	Blake2_128,
@@ -203,7 +203,7 @@ pub enum StorageHasher {
 
 /// A storage entry type.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub enum StorageEntryType {
 	/// Plain storage entry (just the value).
 	Plain(DecodeDifferentStr),
@@ -238,7 +238,7 @@ pub enum StorageEntryType {
 /// `Optional` means you should expect an `Option<T>`, with `None` returned if the key is not present.
 /// `Default` means you should expect a `T` with the default value of default if the key is not present.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub enum StorageEntryModifier {
 	/// The storage entry returns an `Option<T>`, with `None` if the key is not present.
 	Optional,
@@ -248,7 +248,7 @@ pub enum StorageEntryModifier {
 
 /// All metadata of the storage.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct StorageMetadata {
 	/// The common prefix used by all storage entries.
 	pub prefix: DecodeDifferent<&'static str, StringBuf>,
@@ -257,7 +257,7 @@ pub struct StorageMetadata {
 
 /// The metadata of a runtime.
 #[derive(Eq, Encode, PartialEq, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct RuntimeMetadataV8 {
 	pub modules: DecodeDifferentArray<ModuleMetadata>,
 }
@@ -267,7 +267,7 @@ pub type RuntimeMetadataLastVersion = RuntimeMetadataV8;
 
 /// All metadata about a runtime module.
 #[derive(Clone, PartialEq, Eq, Encode, Debug)]
-#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+#[cfg_attr(feature = "std", derive(Decode, Serialize, Deserialize))]
 pub struct ModuleMetadata {
 	/// Module name.
 	pub name: DecodeDifferentStr,
@@ -0,0 +1,580 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks that the metadata is well formed.
+//!
+//! Metadata built by a runtime is consistent, but metadata that is assembled by hand,
+//! rewritten by other tools or received from an untrusted source may refer to types that are
+//! not in the type registry, or contain values that do not decode as their types.
+//! [`validate`](crate::validate::validate) reports all such problems at once, so that code
+//! working with the metadata afterwards can rely on it.
+
+use crate::{
+	metadata::{Metadata, StorageEntry},
+	scale::encoded_len,
+	v14::{StorageEntryModifier, StorageEntryType},
+};
+use scale_info::{
+	prelude::{collections::BTreeSet, format, string::String, vec, vec::Vec},
+	PortableRegistry, TypeDef,
+};
+
+/// A problem found in the metadata.
+///
+/// Items are given by their `::` separated path, such as `System::Account` for a storage
+/// entry, `Balances::Call` for the call type of a pallet, `Core::version` for a runtime API
+/// method or `Extrinsic::CheckNonce` for a transaction extension. Types of the registry are
+/// given by their position, such as `types[5]`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ValidationError {
+	/// An item refers to a type that is not in the type registry.
+	TypeNotFound {
+		/// The path of the item.
+		item: String,
+		/// The missing type ID.
+		id: u32,
+	},
+	/// A type of the registry has an ID other than its position. Types are resolved by their
+	/// position, so every reference to the ID resolves to a different type.
+	TypeIdMismatch {
+		/// The position of the type in the registry.
+		position: usize,
+		/// The ID of the type.
+		id: u32,
+	},
+	/// Several pallets have the given name.
+	DuplicatePalletName(String),
+	/// Several pallets have the given index.
+	DuplicatePalletIndex(u8),
+	/// The call, event or error type of a pallet, or an outer enum, is not a variant type.
+	NotAVariantType {
+		/// The path of the item.
+		item: String,
+		/// The type ID of the item.
+		id: u32,
+	},
+	/// The number of hashers of a storage map differs from the number of its keys, which is
+	/// the arity of the key tuple if there are several.
+	HasherCountMismatch {
+		/// The path of the storage entry.
+		item: String,
+		/// The number of hashers.
+		hashers: usize,
+		/// The number of keys.
+		keys: usize,
+	},
+	/// A transaction extension version refers to a transaction extension index that is out of
+	/// range.
+	TransactionExtensionNotFound {
+		/// The transaction extension version.
+		version: u8,
+		/// The transaction extension index.
+		index: u32,
+	},
+	/// The value of a constant, the default value of a storage entry or a custom value does
+	/// not decode as its type.
+	InvalidValue {
+		/// The path of the item.
+		item: String,
+		/// The type ID of the value.
+		ty: u32,
+	},
+}
+
+impl core::fmt::Display for ValidationError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			ValidationError::TypeNotFound { item, id } => {
+				write!(
+					f,
+					"`{item}` refers to type {id}, which is not in the registry"
+				)
+			}
+			ValidationError::TypeIdMismatch { position, id } => {
+				write!(f, "type at position {position} of the registry has ID {id}")
+			}
+			ValidationError::DuplicatePalletName(name) => {
+				write!(f, "several pallets are named `{name}`")
+			}
+			ValidationError::DuplicatePalletIndex(index) => {
+				write!(f, "several pallets have index {index}")
+			}
+			ValidationError::NotAVariantType { item, id } => {
+				write!(f, "type {id} of `{item}` is not a variant type")
+			}
+			ValidationError::HasherCountMismatch {
+				item,
+				hashers,
+				keys,
+			} => {
+				write!(
+					f,
+					"storage map `{item}` has {hashers} hashers for {keys} keys"
+				)
+			}
+			ValidationError::TransactionExtensionNotFound { version, index } => {
+				write!(
+					f,
+					"transaction extension {index} of extension version {version} not found"
+				)
+			}
+			ValidationError::InvalidValue { item, ty } => {
+				write!(f, "value of `{item}` does not decode as type {ty}")
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
+
+/// Check that the metadata is well formed, returning every problem found.
+///
+/// This checks that:
+/// - every type ID referenced by the type registry, the pallets, the extrinsic, the runtime
+///   APIs, the outer enums and the custom values is in the type registry,
+/// - pallet names and indexes are unique,
+/// - the call, event and error types of pallets and the outer enums are variant types,
+/// - storage maps have a hasher per key,
+/// - the transaction extensions of every extrinsic version exist,
+/// - constants, storage entry defaults and custom values decode as their types.
+///
+/// Raw V14, V15 or V16 metadata can be checked by converting it into [`Metadata`] first.
+pub fn validate(metadata: &Metadata) -> Result<(), Vec<ValidationError>> {
+	let mut validator = Validator {
+		types: metadata.types(),
+		errors: Vec::new(),
+	};
+	validator.registry();
+	validator.pallets(metadata);
+	validator.extrinsic(metadata);
+	validator.runtime_apis(metadata);
+	validator.outer_enums(metadata);
+	validator.custom(metadata);
+
+	if validator.errors.is_empty() {
+		Ok(())
+	} else {
+		Err(validator.errors)
+	}
+}
+
+struct Validator<'a> {
+	types: &'a PortableRegistry,
+	errors: Vec<ValidationError>,
+}
+
+impl Validator<'_> {
+	fn registry(&mut self) {
+		for (position, ty) in self.types.types.iter().enumerate() {
+			if ty.id as usize != position {
+				self.errors.push(ValidationError::TypeIdMismatch {
+					position,
+					id: ty.id,
+				});
+			}
+
+			let mut ids = match &ty.ty.type_def {
+				TypeDef::Composite(composite) => {
+					composite.fields.iter().map(|field| field.ty.id).collect()
+				}
+				TypeDef::Variant(variant) => variant
+					.variants
+					.iter()
+					.flat_map(|variant| &variant.fields)
+					.map(|field| field.ty.id)
+					.collect(),
+				TypeDef::Sequence(sequence) => vec![sequence.type_param.id],
+				TypeDef::Array(array) => vec![array.type_param.id],
+				TypeDef::Tuple(tuple) => tuple.fields.iter().map(|ty| ty.id).collect(),
+				TypeDef::Primitive(_) => Vec::new(),
+				TypeDef::Compact(compact) => vec![compact.type_param.id],
+				TypeDef::BitSequence(bits) => {
+					vec![bits.bit_store_type.id, bits.bit_order_type.id]
+				}
+			};
+			ids.extend(
+				ty.ty
+					.type_params
+					.iter()
+					.filter_map(|param| param.ty.map(|ty| ty.id)),
+			);
+
+			let item = format!("types[{position}]");
+			for id in ids {
+				self.ty(&item, id);
+			}
+		}
+	}
+
+	fn pallets(&mut self, metadata: &Metadata) {
+		let mut names = BTreeSet::new();
+		let mut indexes = BTreeSet::new();
+		for pallet in metadata.pallets() {
+			let name = pallet.name();
+			if !names.insert(name) {
+				self.errors
+					.push(ValidationError::DuplicatePalletName(name.into()));
+			}
+			if !indexes.insert(pallet.index()) {
+				self.errors
+					.push(ValidationError::DuplicatePalletIndex(pallet.index()));
+			}
+
+			let enums = [
+				("Call", pallet.call_ty()),
+				("Event", pallet.event_ty()),
+				("Error", pallet.error_ty()),
+			];
+			for (kind, ty) in enums {
+				if let Some(ty) = ty {
+					self.variant_ty(&format!("{name}::{kind}"), ty);
+				}
+			}
+
+			for entry in pallet.storage_entries() {
+				self.storage_entry(name, entry);
+			}
+			for constant in pallet.constants() {
+				self.value(
+					&format!("{name}::{}", constant.name),
+					constant.ty,
+					constant.value,
+				);
+			}
+			for associated_type in pallet.associated_types() {
+				let item = format!("{name}::{}", AsRef::<str>::as_ref(&associated_type.name));
+				self.ty(&item, associated_type.ty.id);
+			}
+			for view_function in pallet.view_functions() {
+				let item = format!("{name}::{}", AsRef::<str>::as_ref(&view_function.name));
+				for input in &view_function.inputs {
+					self.ty(&item, input.ty.id);
+				}
+				self.ty(&item, view_function.output.id);
+			}
+		}
+	}
+
+	fn storage_entry(&mut self, pallet: &str, entry: StorageEntry<'_>) {
+		let item = format!("{pallet}::{}", entry.name);
+		let value = match entry.ty {
+			StorageEntryType::Plain(value) => value.id,
+			StorageEntryType::Map {
+				hashers,
+				key,
+				value,
+			} => {
+				if self.ty(&item, key.id) {
+					let keys = match &self.types.resolve(key.id).map(|ty| &ty.type_def) {
+						Some(TypeDef::Tuple(tuple)) if hashers.len() != 1 => tuple.fields.len(),
+						_ => 1,
+					};
+					if keys != hashers.len() {
+						self.errors.push(ValidationError::HasherCountMismatch {
+							item: item.clone(),
+							hashers: hashers.len(),
+							keys,
+						});
+					}
+				}
+				value.id
+			}
+		};
+
+		match entry.modifier {
+			StorageEntryModifier::Default => self.value(&item, value, entry.default),
+			// The default of an optional entry is an encoded `Option` of the value.
+			StorageEntryModifier::Optional => match entry.default.split_first() {
+				Some((0, [])) => {
+					self.ty(&item, value);
+				}
+				Some((1, default)) => self.value(&item, value, default),
+				_ => {
+					if self.ty(&item, value) {
+						self.errors
+							.push(ValidationError::InvalidValue { item, ty: value });
+					}
+				}
+			},
+		}
+	}
+
+	fn extrinsic(&mut self, metadata: &Metadata) {
+		let extrinsic = metadata.extrinsic();
+		let tys = [
+			("address", extrinsic.address_ty()),
+			("call", extrinsic.call_ty()),
+			("signature", extrinsic.signature_ty()),
+		];
+		for (name, ty) in tys {
+			if let Some(ty) = ty {
+				self.ty(&format!("Extrinsic::{name}"), ty);
+			}
+		}
+		for extension in extrinsic.transaction_extensions() {
+			let item = format!("Extrinsic::{}", extension.identifier);
+			self.ty(&item, extension.ty);
+			self.ty(&item, extension.implicit);
+		}
+
+		if let Some(v14) = metadata.as_v14() {
+			self.ty("Extrinsic", v14.extrinsic.ty.id);
+			self.ty("Runtime", v14.ty.id);
+		}
+		if let Some(v15) = metadata.as_v15() {
+			self.ty("Extrinsic::extra", v15.extrinsic.extra_ty.id);
+			self.ty("Runtime", v15.ty.id);
+		}
+		if let Some(v16) = metadata.as_v16() {
+			let count = v16.extrinsic.transaction_extensions.len();
+			for (&version, indexes) in &v16.extrinsic.transaction_extensions_by_version {
+				for index in indexes {
+					if index.0 as usize >= count {
+						self.errors
+							.push(ValidationError::TransactionExtensionNotFound {
+								version,
+								index: index.0,
+							});
+					}
+				}
+			}
+		}
+	}
+
+	fn runtime_apis(&mut self, metadata: &Metadata) {
+		for api in metadata.runtime_apis() {
+			for method in api.methods() {
+				let item = format!("{}::{}", api.name(), method.name);
+				for (_, ty) in &method.inputs {
+					self.ty(&item, *ty);
+				}
+				self.ty(&item, method.output);
+			}
+		}
+	}
+
+	fn outer_enums(&mut self, metadata: &Metadata) {
+		let Some(outer_enums) = metadata.outer_enums() else {
+			return;
+		};
+		let enums = [
+			("call", outer_enums.call_enum_ty.id),
+			("event", outer_enums.event_enum_ty.id),
+			("error", outer_enums.error_enum_ty.id),
+		];
+		for (name, ty) in enums {
+			self.variant_ty(&format!("OuterEnums::{name}"), ty);
+		}
+	}
+
+	fn custom(&mut self, metadata: &Metadata) {
+		let Some(custom) = metadata.custom() else {
+			return;
+		};
+		for (name, value) in &custom.map {
+			let item = format!("Custom::{}", AsRef::<str>::as_ref(name));
+			self.value(&item, value.ty.id, &value.value);
+		}
+	}
+
+	/// Check that the type `id` referenced by `item` exists.
+	fn ty(&mut self, item: &str, id: u32) -> bool {
+		let found = self.types.resolve(id).is_some();
+		if !found {
+			self.errors.push(ValidationError::TypeNotFound {
+				item: item.into(),
+				id,
+			});
+		}
+		found
+	}
+
+	/// Check that the type `id` referenced by `item` exists and is a variant type.
+	fn variant_ty(&mut self, item: &str, id: u32) {
+		let ty = self.types.resolve(id).map(|ty| &ty.type_def);
+		if self.ty(item, id) && !matches!(ty, Some(TypeDef::Variant(_))) {
+			self.errors.push(ValidationError::NotAVariantType {
+				item: item.into(),
+				id,
+			});
+		}
+	}
+
+	/// Check that the type `ty` of `item` exists and that `value` is exactly one encoded
+	/// value of it.
+	fn value(&mut self, item: &str, ty: u32, value: &[u8]) {
+		if self.ty(item, ty) && encoded_len(self.types, ty, value) != Some(value.len()) {
+			self.errors.push(ValidationError::InvalidValue {
+				item: item.into(),
+				ty,
+			});
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		test::{kusama_v14, kusama_v15, kusama_v16},
+		v14, v15,
+	};
+	use codec::Compact;
+	use scale_info::TypeDefPrimitive;
+
+	#[test]
+	fn should_accept_well_formed_metadata() {
+		let v14 = kusama_v14();
+		let v15 = kusama_v15();
+		let v16 = kusama_v16();
+		for metadata in [Metadata::from(v14), v15.into(), v16.into()] {
+			assert_eq!(validate(&metadata), Ok(()));
+		}
+	}
+
+	#[test]
+	fn should_report_missing_types_and_duplicate_pallets() {
+		let mut v14 = kusama_v14();
+		v14.types.types[0].id = 1;
+		let name = v14.pallets[0].name.clone();
+		v14.pallets[1].name = name.clone();
+		let index = v14.pallets[0].index;
+		v14.pallets[2].index = index;
+
+		let balances = v14
+			.pallets
+			.iter_mut()
+			.find(|p| p.name == "Balances")
+			.unwrap();
+		let deposit = &mut balances.constants[0];
+		assert_eq!(deposit.name, "ExistentialDeposit");
+		let u128_ty = deposit.ty.id;
+		deposit.ty = 999_999.into();
+		balances.event.as_mut().unwrap().ty = u128_ty.into();
+
+		let errors = validate(&Metadata::from(v14)).unwrap_err();
+		assert_eq!(
+			errors,
+			[
+				ValidationError::TypeIdMismatch { position: 0, id: 1 },
+				ValidationError::DuplicatePalletName(name),
+				ValidationError::DuplicatePalletIndex(index),
+				ValidationError::NotAVariantType {
+					item: "Balances::Event".into(),
+					id: u128_ty,
+				},
+				ValidationError::TypeNotFound {
+					item: "Balances::ExistentialDeposit".into(),
+					id: 999_999,
+				},
+			]
+		);
+		assert_eq!(
+			errors[4].to_string(),
+			"`Balances::ExistentialDeposit` refers to type 999999, which is not in the registry"
+		);
+	}
+
+	#[test]
+	fn should_check_storage_hashers_and_values() {
+		let mut v14 = kusama_v14();
+		for pallet in &mut v14.pallets {
+			for constant in &mut pallet.constants {
+				if constant.name == "ExistentialDeposit" {
+					constant.value.push(0);
+				}
+			}
+			for entry in pallet.storage.iter_mut().flat_map(|s| &mut s.entries) {
+				match (pallet.name.as_str(), entry.name.as_str()) {
+					("System", "Number") => {
+						entry.default.pop();
+					}
+					("Staking", "ErasStakers") => {
+						let v14::StorageEntryType::Map { hashers, .. } = &mut entry.ty else {
+							panic!("Staking::ErasStakers should be a map")
+						};
+						hashers.push(v14::StorageHasher::Identity);
+					}
+					_ => {}
+				}
+			}
+		}
+
+		let errors = validate(&Metadata::from(v14)).unwrap_err();
+		let [number, deposit, stakers] = &errors[..] else {
+			panic!("expected three errors, got {errors:?}")
+		};
+		assert!(matches!(
+			number,
+			ValidationError::InvalidValue { item, .. } if item == "System::Number"
+		));
+		assert!(matches!(
+			deposit,
+			ValidationError::InvalidValue { item, .. } if item == "Balances::ExistentialDeposit"
+		));
+		assert_eq!(
+			*stakers,
+			ValidationError::HasherCountMismatch {
+				item: "Staking::ErasStakers".into(),
+				hashers: 3,
+				keys: 2,
+			}
+		);
+	}
+
+	#[test]
+	fn should_check_extensions_outer_enums_and_custom_values() {
+		let mut v16 = kusama_v16();
+		let u32_ty = v16
+			.types
+			.types
+			.iter()
+			.find(|ty| ty.ty.type_def == TypeDef::Primitive(TypeDefPrimitive::U32))
+			.unwrap()
+			.id;
+		v16.extrinsic
+			.transaction_extensions_by_version
+			.get_mut(&0)
+			.unwrap()
+			.push(Compact(99));
+		v16.outer_enums.error_enum_ty = u32_ty.into();
+		v16.custom.map.insert(
+			"Answer".into(),
+			v15::CustomValueMetadata {
+				ty: u32_ty.into(),
+				value: vec![42, 0],
+			},
+		);
+
+		assert_eq!(
+			validate(&Metadata::from(v16)).unwrap_err(),
+			[
+				ValidationError::TransactionExtensionNotFound {
+					version: 0,
+					index: 99,
+				},
+				ValidationError::NotAVariantType {
+					item: "OuterEnums::error".into(),
+					id: u32_ty,
+				},
+				ValidationError::InvalidValue {
+					item: "Custom::Answer".into(),
+					ty: u32_ty,
+				},
+			]
+		);
+	}
+}
@@ -0,0 +1,1323 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding and encoding SCALE values of the types described by the metadata.
+//!
+//! Constants, storage defaults and custom values are stored as raw SCALE bytes. They can be
+//! decoded into a known Rust type with `decode_as`, or into a [`Value`](crate::value::Value)
+//! that follows the shape of their type in the
+//! [`PortableRegistry`](scale_info::PortableRegistry) with `decode_value`, without knowing
+//! the type in advance. See [`decode_value`](crate::value::decode_value) to decode any bytes,
+//! and [`encode_value`](crate::value::encode_value) to encode a value as any type.
+
+use crate::{
+	convert::to_string,
+	metadata::{Constant, StorageEntry},
+	scale::{take, MAX_DEPTH},
+	v14, v15, v16,
+};
+use codec::{Compact, Decode, DecodeAll, Encode};
+use scale_info::{
+	form::PortableForm,
+	prelude::{fmt, string::String, vec, vec::Vec},
+	PortableRegistry, TypeDef, TypeDefPrimitive,
+};
+
+#[cfg(feature = "serde_full")]
+use serde::Serialize;
+
+/// The maximum number of zero sized items in a value, which aren't backed by any data.
+const MAX_ZERO_SIZED_ITEMS: usize = 1 << 16;
+
+/// A decoded value, or one to encode.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde_full", derive(Serialize))]
+pub enum Value {
+	/// A struct or tuple.
+	Composite(Composite),
+	/// A variant of an enum.
+	Variant {
+		/// The variant name.
+		name: String,
+		/// The variant index.
+		index: u8,
+		/// The fields of the variant.
+		fields: Composite,
+	},
+	/// A sequence or fixed size array.
+	Sequence(Vec<Value>),
+	/// A primitive value, or a compact encoded integer.
+	Primitive(Primitive),
+	/// A sequence of bits.
+	BitSequence(Vec<bool>),
+}
+
+/// The fields of a struct, tuple or enum variant.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde_full", derive(Serialize))]
+pub enum Composite {
+	/// Named fields, in the order they are encoded.
+	Named(Vec<(String, Value)>),
+	/// Unnamed fields, such as those of a tuple.
+	Unnamed(Vec<Value>),
+}
+
+/// A primitive value.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde_full", derive(Serialize))]
+pub enum Primitive {
+	/// A boolean.
+	Bool(bool),
+	/// A character.
+	Char(char),
+	/// A string.
+	Str(String),
+	/// An unsigned integer of up to 128 bits.
+	U128(u128),
+	/// A signed integer of up to 128 bits.
+	I128(i128),
+	/// A 256-bit unsigned integer, as little endian bytes.
+	U256([u8; 32]),
+	/// A 256-bit signed integer, as little endian bytes.
+	I256([u8; 32]),
+}
+
+impl Value {
+	/// The value as an unsigned integer, if it is one that fits into a `u128`.
+	pub fn as_u128(&self) -> Option<u128> {
+		match self {
+			Value::Primitive(Primitive::U128(value)) => Some(*value),
+			Value::Primitive(Primitive::I128(value)) => (*value).try_into().ok(),
+			_ => None,
+		}
+	}
+
+	/// The value as a string, if it is one.
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			Value::Primitive(Primitive::Str(value)) => Some(value),
+			_ => None,
+		}
+	}
+
+	/// The value as a boolean, if it is one.
+	pub fn as_bool(&self) -> Option<bool> {
+		match self {
+			Value::Primitive(Primitive::Bool(value)) => Some(*value),
+			_ => None,
+		}
+	}
+
+	/// The field with the given name, if this is a composite or variant with named fields.
+	pub fn field(&self, name: &str) -> Option<&Value> {
+		match self {
+			Value::Composite(Composite::Named(fields))
+			| Value::Variant {
+				fields: Composite::Named(fields),
+				..
+			} => fields
+				.iter()
+				.find(|(field, _)| field == name)
+				.map(|(_, value)| value),
+			_ => None,
+		}
+	}
+}
+
+/// A step of the path from a decoded value to one nested in it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PathSegment {
+	/// A named field.
+	Field(String),
+	/// An unnamed field, or an item of a sequence or array, by position.
+	Index(usize),
+	/// A variant of an enum.
+	Variant(String),
+}
+
+/// An error that can occur when decoding a value.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DecodeError {
+	/// The path from the decoded value to the value that failed to decode.
+	pub path: Vec<PathSegment>,
+	/// What went wrong.
+	pub kind: DecodeErrorKind,
+}
+
+/// What went wrong when decoding a value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DecodeErrorKind {
+	/// A type ID was not found in the type registry.
+	TypeNotFound(u32),
+	/// The input ended before the value did.
+	NotEnoughData,
+	/// The input has bytes left after the value.
+	TrailingBytes(usize),
+	/// The enum has no variant with the given index.
+	InvalidVariantIndex(u8),
+	/// A boolean is neither `0` nor `1`.
+	InvalidBool(u8),
+	/// A character is not a valid Unicode scalar value.
+	InvalidChar(u32),
+	/// A string is not valid UTF-8.
+	InvalidUtf8,
+	/// A compact encoded integer or length is invalid.
+	InvalidCompact,
+	/// The type with the given ID cannot be compact encoded.
+	UnsupportedCompact(u32),
+	/// The bit store or bit order of the bit sequence type with the given ID is not supported.
+	UnsupportedBitSequence(u32),
+	/// The value is nested too deeply.
+	TooDeep,
+	/// The sequences and arrays of the value have too many zero sized items.
+	TooManyZeroSizedItems,
+}
+
+impl fmt::Display for PathSegment {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			PathSegment::Field(name) => write!(f, ".{name}"),
+			PathSegment::Index(index) => write!(f, "[{index}]"),
+			PathSegment::Variant(name) => write!(f, "::{name}"),
+		}
+	}
+}
+
+impl fmt::Display for DecodeErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			DecodeErrorKind::TypeNotFound(id) => {
+				write!(f, "type with ID {id} not found in the type registry")
+			}
+			DecodeErrorKind::NotEnoughData => write!(f, "not enough data"),
+			DecodeErrorKind::TrailingBytes(len) => write!(f, "{len} bytes left after the value"),
+			DecodeErrorKind::InvalidVariantIndex(index) => {
+				write!(f, "no variant with index {index}")
+			}
+			DecodeErrorKind::InvalidBool(byte) => write!(f, "invalid boolean {byte}"),
+			DecodeErrorKind::InvalidChar(char) => write!(f, "invalid character {char:#x}"),
+			DecodeErrorKind::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+			DecodeErrorKind::InvalidCompact => write!(f, "invalid compact encoding"),
+			DecodeErrorKind::UnsupportedCompact(id) => {
+				write!(f, "type with ID {id} cannot be compact encoded")
+			}
+			DecodeErrorKind::UnsupportedBitSequence(id) => {
+				write!(f, "bit sequence type with ID {id} is not supported")
+			}
+			DecodeErrorKind::TooDeep => write!(f, "value is nested too deeply"),
+			DecodeErrorKind::TooManyZeroSizedItems => write!(f, "too many zero sized items"),
+		}
+	}
+}
+
+impl fmt::Display for DecodeError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.path.is_empty() {
+			return write!(f, "{}", self.kind);
+		}
+		f.write_str("at `")?;
+		for segment in &self.path {
+			write!(f, "{segment}")?;
+		}
+		write!(f, "`: {}", self.kind)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// An error that can occur when encoding a value.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EncodeError {
+	/// The path from the encoded value to the value that failed to encode.
+	pub path: Vec<PathSegment>,
+	/// What went wrong.
+	pub kind: EncodeErrorKind,
+}
+
+/// What went wrong when encoding a value.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EncodeErrorKind {
+	/// A type ID was not found in the type registry.
+	TypeNotFound(u32),
+	/// The value does not have the shape of the type with the given ID.
+	WrongShape(u32),
+	/// The value has a different number of fields or items than its type.
+	WrongLength {
+		/// The number of fields or items of the type.
+		expected: usize,
+		/// The number of fields or items of the value.
+		found: usize,
+	},
+	/// The value has no field with the given name.
+	MissingField(String),
+	/// The enum has no variant with the given name.
+	UnknownVariant(String),
+	/// The integer does not fit into the integer type with the given ID.
+	OutOfRange(u32),
+	/// The type with the given ID cannot be compact encoded.
+	UnsupportedCompact(u32),
+	/// The bit store or bit order of the bit sequence type with the given ID is not supported.
+	UnsupportedBitSequence(u32),
+}
+
+impl fmt::Display for EncodeErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			EncodeErrorKind::TypeNotFound(id) => {
+				write!(f, "type with ID {id} not found in the type registry")
+			}
+			EncodeErrorKind::WrongShape(id) => {
+				write!(f, "value does not have the shape of the type with ID {id}")
+			}
+			EncodeErrorKind::WrongLength { expected, found } => {
+				write!(f, "expected {expected} fields or items, found {found}")
+			}
+			EncodeErrorKind::MissingField(name) => write!(f, "missing field `{name}`"),
+			EncodeErrorKind::UnknownVariant(name) => write!(f, "no variant named `{name}`"),
+			EncodeErrorKind::OutOfRange(id) => {
+				write!(f, "integer does not fit into the type with ID {id}")
+			}
+			EncodeErrorKind::UnsupportedCompact(id) => {
+				write!(f, "type with ID {id} cannot be compact encoded")
+			}
+			EncodeErrorKind::UnsupportedBitSequence(id) => {
+				write!(f, "bit sequence type with ID {id} is not supported")
+			}
+		}
+	}
+}
+
+impl fmt::Display for EncodeError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.path.is_empty() {
+			return write!(f, "{}", self.kind);
+		}
+		f.write_str("at `")?;
+		for segment in &self.path {
+			write!(f, "{segment}")?;
+		}
+		write!(f, "`: {}", self.kind)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+/// Decode `bytes` as a value of the type `ty`, which must use all of them.
+pub fn decode_value(types: &PortableRegistry, ty: u32, bytes: &[u8]) -> Result<Value, DecodeError> {
+	let mut input = bytes;
+	let value = decode_value_from(types, ty, &mut input)?;
+	if !input.is_empty() {
+		return Err(DecodeError {
+			path: Vec::new(),
+			kind: DecodeErrorKind::TrailingBytes(input.len()),
+		});
+	}
+	Ok(value)
+}
+
+/// Decode a value of the type `ty` from the start of `input`, advancing `input` past it.
+pub fn decode_value_from(
+	types: &PortableRegistry,
+	ty: u32,
+	input: &mut &[u8],
+) -> Result<Value, DecodeError> {
+	let mut decoder = Decoder {
+		types,
+		input,
+		path: Vec::new(),
+		zero_sized_items: 0,
+	};
+	decoder.value(ty)
+}
+
+/// Encode `value` as the type `ty`.
+///
+/// The value must have the shape that [`decode_value`] gives values of the type, except that
+/// the fields of a struct may also be given by position, variants are chosen by name
+/// regardless of their index, and any integer that fits is accepted for an integer type.
+pub fn encode_value(
+	types: &PortableRegistry,
+	ty: u32,
+	value: &Value,
+) -> Result<Vec<u8>, EncodeError> {
+	let mut out = Vec::new();
+	encode_value_to(types, ty, value, &mut out)?;
+	Ok(out)
+}
+
+/// Encode `value` as the type `ty`, appending it to `out`.
+pub fn encode_value_to(
+	types: &PortableRegistry,
+	ty: u32,
+	value: &Value,
+	out: &mut Vec<u8>,
+) -> Result<(), EncodeError> {
+	let mut encoder = Encoder {
+		types,
+		out,
+		path: Vec::new(),
+	};
+	encoder.value(ty, value)
+}
+
+impl Constant<'_> {
+	/// Decode the value of the constant as `T`.
+	pub fn decode_as<T: Decode>(&self) -> Result<T, codec::Error> {
+		T::decode_all(&mut &*self.value)
+	}
+
+	/// Decode the value of the constant as its type in `types`.
+	pub fn decode_value(&self, types: &PortableRegistry) -> Result<Value, DecodeError> {
+		decode_value(types, self.ty, self.value)
+	}
+}
+
+impl StorageEntry<'_> {
+	/// Decode the default value of the entry as `T`, which is `None` for optional entries
+	/// that default to no value.
+	pub fn decode_default_as<T: Decode>(&self) -> Result<Option<T>, codec::Error> {
+		decode_default_as(self.modifier, self.default)
+	}
+
+	/// Decode the default value of the entry as its value type in `types`, which is `None`
+	/// for optional entries that default to no value.
+	pub fn decode_default(&self, types: &PortableRegistry) -> Result<Option<Value>, DecodeError> {
+		decode_default(types, self.modifier, self.ty, self.default)
+	}
+}
+
+impl v14::PalletConstantMetadata<PortableForm> {
+	/// Decode the value of the constant as `T`.
+	pub fn decode_as<T: Decode>(&self) -> Result<T, codec::Error> {
+		T::decode_all(&mut &self.value[..])
+	}
+
+	/// Decode the value of the constant as its type in `types`.
+	pub fn decode_value(&self, types: &PortableRegistry) -> Result<Value, DecodeError> {
+		decode_value(types, self.ty.id, &self.value)
+	}
+}
+
+impl v16::PalletConstantMetadata<PortableForm> {
+	/// Decode the value of the constant as `T`.
+	pub fn decode_as<T: Decode>(&self) -> Result<T, codec::Error> {
+		T::decode_all(&mut &self.value[..])
+	}
+
+	/// Decode the value of the constant as its type in `types`.
+	pub fn decode_value(&self, types: &PortableRegistry) -> Result<Value, DecodeError> {
+		decode_value(types, self.ty.id, &self.value)
+	}
+}
+
+impl v14::StorageEntryMetadata<PortableForm> {
+	/// Decode the default value of the entry as `T`, which is `None` for optional entries
+	/// that default to no value.
+	pub fn decode_default_as<T: Decode>(&self) -> Result<Option<T>, codec::Error> {
+		decode_default_as(&self.modifier, &self.default)
+	}
+
+	/// Decode the default value of the entry as its value type in `types`, which is `None`
+	/// for optional entries that default to no value.
+	pub fn decode_default(&self, types: &PortableRegistry) -> Result<Option<Value>, DecodeError> {
+		decode_default(types, &self.modifier, &self.ty, &self.default)
+	}
+}
+
+impl v16::StorageEntryMetadata<PortableForm> {
+	/// Decode the default value of the entry as `T`, which is `None` for optional entries
+	/// that default to no value.
+	pub fn decode_default_as<T: Decode>(&self) -> Result<Option<T>, codec::Error> {
+		decode_default_as(&self.modifier, &self.default)
+	}
+
+	/// Decode the default value of the entry as its value type in `types`, which is `None`
+	/// for optional entries that default to no value.
+	pub fn decode_default(&self, types: &PortableRegistry) -> Result<Option<Value>, DecodeError> {
+		decode_default(types, &self.modifier, &self.ty, &self.default)
+	}
+}
+
+impl v15::CustomValueMetadata<PortableForm> {
+	/// Decode the custom value as `T`.
+	pub fn decode_as<T: Decode>(&self) -> Result<T, codec::Error> {
+		T::decode_all(&mut &self.value[..])
+	}
+
+	/// Decode the custom value as its type in `types`.
+	pub fn decode_value(&self, types: &PortableRegistry) -> Result<Value, DecodeError> {
+		decode_value(types, self.ty.id, &self.value)
+	}
+}
+
+/// The default of an optional storage entry is an encoded `Option` of the value.
+fn decode_default_as<T: Decode>(
+	modifier: &v14::StorageEntryModifier,
+	default: &[u8],
+) -> Result<Option<T>, codec::Error> {
+	match modifier {
+		v14::StorageEntryModifier::Optional => Option::<T>::decode_all(&mut &*default),
+		v14::StorageEntryModifier::Default => T::decode_all(&mut &*default).map(Some),
+	}
+}
+
+fn decode_default(
+	types: &PortableRegistry,
+	modifier: &v14::StorageEntryModifier,
+	ty: &v14::StorageEntryType<PortableForm>,
+	default: &[u8],
+) -> Result<Option<Value>, DecodeError> {
+	let ty = match ty {
+		v14::StorageEntryType::Plain(value) => value.id,
+		v14::StorageEntryType::Map { value, .. } => value.id,
+	};
+	let error = |kind| DecodeError {
+		path: Vec::new(),
+		kind,
+	};
+	match (modifier, default) {
+		(v14::StorageEntryModifier::Default, _) => decode_value(types, ty, default).map(Some),
+		(v14::StorageEntryModifier::Optional, [0]) => Ok(None),
+		(v14::StorageEntryModifier::Optional, [0, rest @ ..]) => {
+			Err(error(DecodeErrorKind::TrailingBytes(rest.len())))
+		}
+		(v14::StorageEntryModifier::Optional, [1, rest @ ..]) => {
+			decode_value(types, ty, rest).map(Some)
+		}
+		(v14::StorageEntryModifier::Optional, [index, ..]) => {
+			Err(error(DecodeErrorKind::InvalidVariantIndex(*index)))
+		}
+		(v14::StorageEntryModifier::Optional, []) => Err(error(DecodeErrorKind::NotEnoughData)),
+	}
+}
+
+struct Decoder<'a, 'b, 'c> {
+	types: &'a PortableRegistry,
+	input: &'b mut &'c [u8],
+	path: Vec<PathSegment>,
+	/// The number of zero sized items decoded so far.
+	zero_sized_items: usize,
+}
+
+impl<'a> Decoder<'a, '_, '_> {
+	fn error(&self, kind: DecodeErrorKind) -> DecodeError {
+		DecodeError {
+			path: self.path.clone(),
+			kind,
+		}
+	}
+
+	fn resolve(&self, ty: u32) -> Result<&'a TypeDef<PortableForm>, DecodeError> {
+		self.types
+			.resolve(ty)
+			.map(|ty| &ty.type_def)
+			.ok_or_else(|| self.error(DecodeErrorKind::TypeNotFound(ty)))
+	}
+
+	fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+		take(self.input, N)
+			.and_then(|bytes| bytes.try_into().ok())
+			.ok_or_else(|| self.error(DecodeErrorKind::NotEnoughData))
+	}
+
+	fn compact<T>(&mut self) -> Result<T, DecodeError>
+	where
+		Compact<T>: Decode,
+	{
+		Compact::<T>::decode(self.input)
+			.map(|compact| compact.0)
+			.map_err(|_| self.error(DecodeErrorKind::InvalidCompact))
+	}
+
+	/// Descend into `segment` of the current value.
+	fn push(&mut self, segment: PathSegment) -> Result<(), DecodeError> {
+		if self.path.len() >= MAX_DEPTH {
+			return Err(self.error(DecodeErrorKind::TooDeep));
+		}
+		self.path.push(segment);
+		Ok(())
+	}
+
+	/// Decode the value at `segment` of the current value.
+	fn nested(&mut self, segment: PathSegment, ty: u32) -> Result<Value, DecodeError> {
+		self.push(segment)?;
+		let value = self.value(ty)?;
+		self.path.pop();
+		Ok(value)
+	}
+
+	fn value(&mut self, ty: u32) -> Result<Value, DecodeError> {
+		let value = match self.resolve(ty)? {
+			TypeDef::Composite(composite) => Value::Composite(self.fields(&composite.fields)?),
+			TypeDef::Variant(variant) => {
+				let [index] = self.take()?;
+				let variant = variant
+					.variants
+					.iter()
+					.find(|variant| variant.index == index)
+					.ok_or_else(|| self.error(DecodeErrorKind::InvalidVariantIndex(index)))?;
+				let name = to_string(&variant.name);
+				self.push(PathSegment::Variant(name.clone()))?;
+				let fields = self.fields(&variant.fields)?;
+				self.path.pop();
+				Value::Variant {
+					name,
+					index,
+					fields,
+				}
+			}
+			TypeDef::Sequence(sequence) => {
+				let len = self.compact::<u32>()? as usize;
+				self.items(sequence.type_param.id, len)?
+			}
+			TypeDef::Array(array) => self.items(array.type_param.id, array.len as usize)?,
+			TypeDef::Tuple(tuple) => {
+				let mut values = Vec::with_capacity(tuple.fields.len());
+				for (index, ty) in tuple.fields.iter().enumerate() {
+					values.push(self.nested(PathSegment::Index(index), ty.id)?);
+				}
+				Value::Composite(Composite::Unnamed(values))
+			}
+			TypeDef::Primitive(primitive) => Value::Primitive(self.primitive(primitive)?),
+			TypeDef::Compact(compact) => self.compact_value(compact.type_param.id, 0)?,
+			TypeDef::BitSequence(bits) => {
+				let (store, order) = (bits.bit_store_type.id, bits.bit_order_type.id);
+				Value::BitSequence(self.bits(ty, store, order)?)
+			}
+		};
+		Ok(value)
+	}
+
+	fn fields(
+		&mut self,
+		fields: &[scale_info::Field<PortableForm>],
+	) -> Result<Composite, DecodeError> {
+		if fields.iter().all(|field| field.name.is_some()) && !fields.is_empty() {
+			let mut values = Vec::with_capacity(fields.len());
+			for field in fields {
+				let name: String = field.name.as_ref().map_or("", AsRef::as_ref).into();
+				let value = self.nested(PathSegment::Field(name.clone()), field.ty.id)?;
+				values.push((name, value));
+			}
+			Ok(Composite::Named(values))
+		} else {
+			let mut values = Vec::with_capacity(fields.len());
+			for (index, field) in fields.iter().enumerate() {
+				values.push(self.nested(PathSegment::Index(index), field.ty.id)?);
+			}
+			Ok(Composite::Unnamed(values))
+		}
+	}
+
+	/// Decode `len` items of the type `ty`.
+	fn items(&mut self, ty: u32, len: usize) -> Result<Value, DecodeError> {
+		// Every item takes at least a byte, except for zero sized ones.
+		let mut values = Vec::with_capacity(len.min(self.input.len()));
+		for index in 0..len {
+			let remaining = self.input.len();
+			values.push(self.nested(PathSegment::Index(index), ty)?);
+			if self.input.len() == remaining {
+				self.zero_sized_items += 1;
+				if self.zero_sized_items > MAX_ZERO_SIZED_ITEMS {
+					return Err(self.error(DecodeErrorKind::TooManyZeroSizedItems));
+				}
+			}
+		}
+		Ok(Value::Sequence(values))
+	}
+
+	fn primitive(&mut self, primitive: &TypeDefPrimitive) -> Result<Primitive, DecodeError> {
+		let primitive = match primitive {
+			TypeDefPrimitive::Bool => match self.take()? {
+				[0] => Primitive::Bool(false),
+				[1] => Primitive::Bool(true),
+				[byte] => return Err(self.error(DecodeErrorKind::InvalidBool(byte))),
+			},
+			TypeDefPrimitive::Char => {
+				let char = u32::from_le_bytes(self.take()?);
+				let char = char::from_u32(char)
+					.ok_or_else(|| self.error(DecodeErrorKind::InvalidChar(char)))?;
+				Primitive::Char(char)
+			}
+			TypeDefPrimitive::Str => {
+				let len = self.compact::<u32>()? as usize;
+				let bytes = take(self.input, len)
+					.ok_or_else(|| self.error(DecodeErrorKind::NotEnoughData))?;
+				let str = core::str::from_utf8(bytes)
+					.map_err(|_| self.error(DecodeErrorKind::InvalidUtf8))?;
+				Primitive::Str(str.into())
+			}
+			TypeDefPrimitive::U8 => Primitive::U128(u8::from_le_bytes(self.take()?).into()),
+			TypeDefPrimitive::U16 => Primitive::U128(u16::from_le_bytes(self.take()?).into()),
+			TypeDefPrimitive::U32 => Primitive::U128(u32::from_le_bytes(self.take()?).into()),
+			TypeDefPrimitive::U64 => Primitive::U128(u64::from_le_bytes(self.take()?).into()),
+			TypeDefPrimitive::U128 => Primitive::U128(u128::from_le_bytes(self.take()?)),
+			TypeDefPrimitive::U256 => Primitive::U256(self.take()?),
+			TypeDefPrimitive::I8 => Primitive::I128(i8::from_le_bytes(self.take()?).into()),
+			TypeDefPrimitive::I16 => Primitive::I128(i16::from_le_bytes(self.take()?).into()),
+			TypeDefPrimitive::I32 => Primitive::I128(i32::from_le_bytes(self.take()?).into()),
+			TypeDefPrimitive::I64 => Primitive::I128(i64::from_le_bytes(self.take()?).into()),
+			TypeDefPrimitive::I128 => Primitive::I128(i128::from_le_bytes(self.take()?)),
+			TypeDefPrimitive::I256 => Primitive::I256(self.take()?),
+		};
+		Ok(primitive)
+	}
+
+	/// Decode a compact encoded value of the type `ty`, which is an unsigned integer or a
+	/// struct wrapping one.
+	fn compact_value(&mut self, ty: u32, depth: usize) -> Result<Value, DecodeError> {
+		if depth == MAX_DEPTH {
+			return Err(self.error(DecodeErrorKind::TooDeep));
+		}
+		let value = match self.resolve(ty)? {
+			TypeDef::Primitive(primitive) => {
+				let value = match primitive {
+					TypeDefPrimitive::U8 => self.compact::<u8>()?.into(),
+					TypeDefPrimitive::U16 => self.compact::<u16>()?.into(),
+					TypeDefPrimitive::U32 => self.compact::<u32>()?.into(),
+					TypeDefPrimitive::U64 => self.compact::<u64>()?.into(),
+					TypeDefPrimitive::U128 => self.compact::<u128>()?,
+					_ => return Err(self.error(DecodeErrorKind::UnsupportedCompact(ty))),
+				};
+				Value::Primitive(Primitive::U128(value))
+			}
+			TypeDef::Composite(composite) if composite.fields.len() == 1 => {
+				let field = &composite.fields[0];
+				let inner = self.compact_value(field.ty.id, depth + 1)?;
+				let fields = match &field.name {
+					Some(name) => Composite::Named(vec![(to_string(name), inner)]),
+					None => Composite::Unnamed(vec![inner]),
+				};
+				Value::Composite(fields)
+			}
+			_ => return Err(self.error(DecodeErrorKind::UnsupportedCompact(ty))),
+		};
+		Ok(value)
+	}
+
+	fn bits(&mut self, ty: u32, store: u32, order: u32) -> Result<Vec<bool>, DecodeError> {
+		let (store_bytes, msb0) = bit_format(self.types, store, order)
+			.map_err(|id| self.error(DecodeErrorKind::TypeNotFound(id)))?
+			.ok_or_else(|| self.error(DecodeErrorKind::UnsupportedBitSequence(ty)))?;
+
+		let len = self.compact::<u32>()? as usize;
+		let store_bits = store_bytes * 8;
+		let bytes = take(self.input, len.div_ceil(store_bits) * store_bytes)
+			.ok_or_else(|| self.error(DecodeErrorKind::NotEnoughData))?;
+		let bits = bytes
+			.chunks(store_bytes)
+			.flat_map(|store| {
+				let mut word = [0; 8];
+				word[..store_bytes].copy_from_slice(store);
+				let word = u64::from_le_bytes(word);
+				(0..store_bits).map(move |bit| {
+					let shift = if msb0 { store_bits - 1 - bit } else { bit };
+					word >> shift & 1 == 1
+				})
+			})
+			.take(len)
+			.collect();
+		Ok(bits)
+	}
+}
+
+/// The number of bytes of the bit store type `store` and whether the bit order type `order`
+/// is `Msb0`, or `None` if they are not supported. Fails with the ID of a missing type.
+fn bit_format(
+	types: &PortableRegistry,
+	store: u32,
+	order: u32,
+) -> Result<Option<(usize, bool)>, u32> {
+	let store_bytes = match types.resolve(store).ok_or(store)?.type_def {
+		TypeDef::Primitive(TypeDefPrimitive::U8) => 1,
+		TypeDef::Primitive(TypeDefPrimitive::U16) => 2,
+		TypeDef::Primitive(TypeDefPrimitive::U32) => 4,
+		TypeDef::Primitive(TypeDefPrimitive::U64) => 8,
+		_ => return Ok(None),
+	};
+	let order = types.resolve(order).ok_or(order)?;
+	let msb0 = match order.path.ident().as_ref().map(AsRef::<str>::as_ref) {
+		Some("Lsb0") => false,
+		Some("Msb0") => true,
+		_ => return Ok(None),
+	};
+	Ok(Some((store_bytes, msb0)))
+}
+
+struct Encoder<'a, 'b> {
+	types: &'a PortableRegistry,
+	out: &'b mut Vec<u8>,
+	path: Vec<PathSegment>,
+}
+
+impl<'a> Encoder<'a, '_> {
+	fn error(&self, kind: EncodeErrorKind) -> EncodeError {
+		EncodeError {
+			path: self.path.clone(),
+			kind,
+		}
+	}
+
+	fn resolve(&self, ty: u32) -> Result<&'a TypeDef<PortableForm>, EncodeError> {
+		self.types
+			.resolve(ty)
+			.map(|ty| &ty.type_def)
+			.ok_or_else(|| self.error(EncodeErrorKind::TypeNotFound(ty)))
+	}
+
+	/// Encode the value at `segment` of the current value.
+	fn nested(&mut self, segment: PathSegment, ty: u32, value: &Value) -> Result<(), EncodeError> {
+		self.path.push(segment);
+		self.value(ty, value)?;
+		self.path.pop();
+		Ok(())
+	}
+
+	fn value(&mut self, ty: u32, value: &Value) -> Result<(), EncodeError> {
+		let wrong_shape = || self.error(EncodeErrorKind::WrongShape(ty));
+		match (self.resolve(ty)?, value) {
+			(TypeDef::Composite(composite), Value::Composite(fields)) => {
+				self.fields(&composite.fields, fields)
+			}
+			(TypeDef::Variant(variant), Value::Variant { name, fields, .. }) => {
+				let variant = variant
+					.variants
+					.iter()
+					.find(|variant| AsRef::<str>::as_ref(&variant.name) == name)
+					.ok_or_else(|| self.error(EncodeErrorKind::UnknownVariant(name.clone())))?;
+				self.out.push(variant.index);
+				self.path.push(PathSegment::Variant(name.clone()));
+				self.fields(&variant.fields, fields)?;
+				self.path.pop();
+				Ok(())
+			}
+			(TypeDef::Sequence(sequence), Value::Sequence(items)) => {
+				Compact(items.len() as u32).encode_to(self.out);
+				self.items(sequence.type_param.id, items)
+			}
+			(TypeDef::Array(array), Value::Sequence(items)) => {
+				self.check_len(array.len as usize, items.len())?;
+				self.items(array.type_param.id, items)
+			}
+			(TypeDef::Tuple(tuple), Value::Composite(fields)) => {
+				let values = self.positional(fields, tuple.fields.len())?;
+				for (index, (ty, value)) in tuple.fields.iter().zip(values).enumerate() {
+					self.nested(PathSegment::Index(index), ty.id, value)?;
+				}
+				Ok(())
+			}
+			(TypeDef::Primitive(primitive), Value::Primitive(value)) => {
+				self.primitive(ty, primitive, value)
+			}
+			(TypeDef::Compact(compact), _) => self.compact_value(compact.type_param.id, value),
+			(TypeDef::BitSequence(bits), Value::BitSequence(value)) => {
+				let (store, order) = (bits.bit_store_type.id, bits.bit_order_type.id);
+				self.bits(ty, store, order, value)
+			}
+			_ => Err(wrong_shape()),
+		}
+	}
+
+	fn check_len(&self, expected: usize, found: usize) -> Result<(), EncodeError> {
+		if expected != found {
+			return Err(self.error(EncodeErrorKind::WrongLength { expected, found }));
+		}
+		Ok(())
+	}
+
+	/// The values of `fields` in order, which must be `len` many.
+	fn positional<'v>(
+		&self,
+		fields: &'v Composite,
+		len: usize,
+	) -> Result<Vec<&'v Value>, EncodeError> {
+		let values: Vec<_> = match fields {
+			Composite::Named(fields) => fields.iter().map(|(_, value)| value).collect(),
+			Composite::Unnamed(values) => values.iter().collect(),
+		};
+		self.check_len(len, values.len())?;
+		Ok(values)
+	}
+
+	fn fields(
+		&mut self,
+		fields: &[scale_info::Field<PortableForm>],
+		values: &Composite,
+	) -> Result<(), EncodeError> {
+		let named = fields.iter().all(|field| field.name.is_some()) && !fields.is_empty();
+		match values {
+			Composite::Named(values) if named => {
+				self.check_len(fields.len(), values.len())?;
+				for field in fields {
+					let name = field.name.as_ref().map_or("", AsRef::as_ref);
+					let (_, value) = values
+						.iter()
+						.find(|(value, _)| value == name)
+						.ok_or_else(|| self.error(EncodeErrorKind::MissingField(name.into())))?;
+					self.nested(PathSegment::Field(name.into()), field.ty.id, value)?;
+				}
+			}
+			_ => {
+				let values = self.positional(values, fields.len())?;
+				for (index, (field, value)) in fields.iter().zip(values).enumerate() {
+					self.nested(PathSegment::Index(index), field.ty.id, value)?;
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn items(&mut self, ty: u32, items: &[Value]) -> Result<(), EncodeError> {
+		for (index, item) in items.iter().enumerate() {
+			self.nested(PathSegment::Index(index), ty, item)?;
+		}
+		Ok(())
+	}
+
+	/// The value as an unsigned integer of `bits` bits.
+	fn unsigned(&self, ty: u32, value: &Primitive, bits: u32) -> Result<u128, EncodeError> {
+		let value = match value {
+			Primitive::U128(value) => Some(*value),
+			Primitive::I128(value) => (*value).try_into().ok(),
+			_ => return Err(self.error(EncodeErrorKind::WrongShape(ty))),
+		};
+		value
+			.filter(|value| bits == 128 || value >> bits == 0)
+			.ok_or_else(|| self.error(EncodeErrorKind::OutOfRange(ty)))
+	}
+
+	/// The value as a signed integer of `bits` bits.
+	fn signed(&self, ty: u32, value: &Primitive, bits: u32) -> Result<i128, EncodeError> {
+		let value = match value {
+			Primitive::I128(value) => Some(*value),
+			Primitive::U128(value) => (*value).try_into().ok(),
+			_ => return Err(self.error(EncodeErrorKind::WrongShape(ty))),
+		};
+		let shift = 128 - bits;
+		value
+			.filter(|value| value << shift >> shift == *value)
+			.ok_or_else(|| self.error(EncodeErrorKind::OutOfRange(ty)))
+	}
+
+	fn primitive(
+		&mut self,
+		ty: u32,
+		primitive: &TypeDefPrimitive,
+		value: &Primitive,
+	) -> Result<(), EncodeError> {
+		let int_bits = match primitive {
+			TypeDefPrimitive::U8 | TypeDefPrimitive::I8 => 8,
+			TypeDefPrimitive::U16 | TypeDefPrimitive::I16 => 16,
+			TypeDefPrimitive::U32 | TypeDefPrimitive::I32 => 32,
+			TypeDefPrimitive::U64 | TypeDefPrimitive::I64 => 64,
+			_ => 128,
+		};
+		let len = int_bits as usize / 8;
+		match (primitive, value) {
+			(TypeDefPrimitive::Bool, Primitive::Bool(value)) => value.encode_to(self.out),
+			(TypeDefPrimitive::Char, Primitive::Char(value)) => {
+				u32::from(*value).encode_to(self.out)
+			}
+			(TypeDefPrimitive::Str, Primitive::Str(value)) => value.encode_to(self.out),
+			(TypeDefPrimitive::U256, Primitive::U256(value))
+			| (TypeDefPrimitive::I256, Primitive::I256(value)) => self.out.extend_from_slice(value),
+			(TypeDefPrimitive::U256, _) => {
+				let value = self.unsigned(ty, value, 128)?;
+				self.out.extend_from_slice(&value.to_le_bytes());
+				self.out.extend_from_slice(&[0; 16]);
+			}
+			(TypeDefPrimitive::I256, _) => {
+				let value = self.signed(ty, value, 128)?;
+				let sign = if value < 0 { 0xff } else { 0 };
+				self.out.extend_from_slice(&value.to_le_bytes());
+				self.out.extend_from_slice(&[sign; 16]);
+			}
+			(
+				TypeDefPrimitive::U8
+				| TypeDefPrimitive::U16
+				| TypeDefPrimitive::U32
+				| TypeDefPrimitive::U64
+				| TypeDefPrimitive::U128,
+				_,
+			) => {
+				let value = self.unsigned(ty, value, int_bits)?;
+				self.out.extend_from_slice(&value.to_le_bytes()[..len]);
+			}
+			(
+				TypeDefPrimitive::I8
+				| TypeDefPrimitive::I16
+				| TypeDefPrimitive::I32
+				| TypeDefPrimitive::I64
+				| TypeDefPrimitive::I128,
+				_,
+			) => {
+				let value = self.signed(ty, value, int_bits)?;
+				self.out.extend_from_slice(&value.to_le_bytes()[..len]);
+			}
+			_ => return Err(self.error(EncodeErrorKind::WrongShape(ty))),
+		}
+		Ok(())
+	}
+
+	/// Compact encode `value` as the type `ty`, which is an unsigned integer or a struct
+	/// wrapping one.
+	fn compact_value(&mut self, ty: u32, value: &Value) -> Result<(), EncodeError> {
+		match (self.resolve(ty)?, value) {
+			(TypeDef::Primitive(primitive), Value::Primitive(value)) => {
+				let bits = match primitive {
+					TypeDefPrimitive::U8 => 8,
+					TypeDefPrimitive::U16 => 16,
+					TypeDefPrimitive::U32 => 32,
+					TypeDefPrimitive::U64 => 64,
+					TypeDefPrimitive::U128 => 128,
+					_ => return Err(self.error(EncodeErrorKind::UnsupportedCompact(ty))),
+				};
+				// The compact encoding of an integer does not depend on its type.
+				Compact(self.unsigned(ty, value, bits)?).encode_to(self.out);
+				Ok(())
+			}
+			(TypeDef::Composite(composite), Value::Composite(fields))
+				if composite.fields.len() == 1 =>
+			{
+				let [value] = self.positional(fields, 1)?[..] else {
+					unreachable!("`positional` checks the number of fields")
+				};
+				self.compact_value(composite.fields[0].ty.id, value)
+			}
+			(TypeDef::Primitive(_) | TypeDef::Composite(_), _) => {
+				Err(self.error(EncodeErrorKind::WrongShape(ty)))
+			}
+			_ => Err(self.error(EncodeErrorKind::UnsupportedCompact(ty))),
+		}
+	}
+
+	fn bits(&mut self, ty: u32, store: u32, order: u32, bits: &[bool]) -> Result<(), EncodeError> {
+		let (store_bytes, msb0) = bit_format(self.types, store, order)
+			.map_err(|id| self.error(EncodeErrorKind::TypeNotFound(id)))?
+			.ok_or_else(|| self.error(EncodeErrorKind::UnsupportedBitSequence(ty)))?;
+
+		Compact(bits.len() as u32).encode_to(self.out);
+		let store_bits = store_bytes * 8;
+		for chunk in bits.chunks(store_bits) {
+			let word = chunk.iter().enumerate().fold(0u64, |word, (bit, set)| {
+				let shift = if msb0 { store_bits - 1 - bit } else { bit };
+				word | (*set as u64) << shift
+			});
+			self.out
+				.extend_from_slice(&word.to_le_bytes()[..store_bytes]);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{metadata::Metadata, test::kusama_v14};
+	use codec::Encode;
+	use scale_info::{
+		meta_type, Path, PortableType, Registry, Type, TypeDefBitSequence, TypeDefComposite,
+		TypeInfo,
+	};
+
+	#[derive(TypeInfo, Encode)]
+	struct Sample {
+		#[codec(compact)]
+		amount: u64,
+		pair: (bool, u16),
+		names: Vec<String>,
+		choice: Choice,
+	}
+
+	#[derive(TypeInfo, Encode)]
+	enum Choice {
+		#[codec(index = 3)]
+		Some(i16),
+	}
+
+	fn registry<T: TypeInfo + 'static>() -> (PortableRegistry, u32) {
+		let mut registry = Registry::new();
+		let id = registry.register_type(&meta_type::<T>()).id;
+		(registry.into(), id)
+	}
+
+	#[test]
+	fn should_decode_values_of_every_kind() {
+		let (types, id) = registry::<Sample>();
+		let sample = Sample {
+			amount: 1_000,
+			pair: (true, 7),
+			names: vec!["a".into(), "bc".into()],
+			choice: Choice::Some(-7),
+		};
+		let value = decode_value(&types, id, &sample.encode()).unwrap();
+
+		assert_eq!(value.field("amount").and_then(Value::as_u128), Some(1_000));
+		assert_eq!(
+			value.field("pair"),
+			Some(&Value::Composite(Composite::Unnamed(vec![
+				Value::Primitive(Primitive::Bool(true)),
+				Value::Primitive(Primitive::U128(7)),
+			])))
+		);
+		let Some(Value::Sequence(names)) = value.field("names") else {
+			panic!("names should be a sequence")
+		};
+		let names: Vec<_> = names.iter().map(|name| name.as_str().unwrap()).collect();
+		assert_eq!(names, ["a", "bc"]);
+		assert_eq!(
+			value.field("choice"),
+			Some(&Value::Variant {
+				name: "Some".into(),
+				index: 3,
+				fields: Composite::Unnamed(vec![Value::Primitive(Primitive::I128(-7))]),
+			})
+		);
+	}
+
+	#[test]
+	fn should_decode_bit_sequences() {
+		let (mut types, u8_ty) = registry::<u8>();
+		let mut push = |ty| {
+			let id = types.types.len() as u32;
+			types.types.push(PortableType::new(id, ty));
+			id
+		};
+		let order = |name: &'static str| {
+			let path =
+				Path::from_segments_unchecked(["bitvec".into(), "order".into(), name.into()]);
+			Type::new(path, [], TypeDefComposite::new([]), Vec::new())
+		};
+		let lsb0 = push(order("Lsb0"));
+		let msb0 = push(order("Msb0"));
+		let bits = |order: u32| {
+			Type::new(
+				Path::default(),
+				[],
+				TypeDefBitSequence::new_portable(u8_ty.into(), order.into()),
+				Vec::new(),
+			)
+		};
+		let lsb0_bits = push(bits(lsb0));
+		let msb0_bits = push(bits(msb0));
+
+		// Ten bits take two bytes, with the bits `1100000001`.
+		let encoded = [&Compact(10u32).encode()[..], &[0b0000_0011, 0b0000_0010]].concat();
+		let expected = [
+			true, true, false, false, false, false, false, false, false, true,
+		];
+		assert_eq!(
+			decode_value(&types, lsb0_bits, &encoded),
+			Ok(Value::BitSequence(expected.to_vec()))
+		);
+		let encoded = [&Compact(10u32).encode()[..], &[0b1100_0000, 0b0100_0000]].concat();
+		assert_eq!(
+			decode_value(&types, msb0_bits, &encoded),
+			Ok(Value::BitSequence(expected.to_vec()))
+		);
+		let value = Value::BitSequence(expected.to_vec());
+		assert_eq!(encode_value(&types, msb0_bits, &value), Ok(encoded));
+	}
+
+	#[test]
+	fn should_encode_values_that_decode_again() {
+		let (types, id) = registry::<Sample>();
+		let encoded = Sample {
+			amount: 1_000,
+			pair: (true, 7),
+			names: vec!["a".into(), "bc".into()],
+			choice: Choice::Some(-7),
+		}
+		.encode();
+		let value = decode_value(&types, id, &encoded).unwrap();
+		assert_eq!(encode_value(&types, id, &value), Ok(encoded.clone()));
+
+		// Fields by position, and integers of the other signedness.
+		let int = |value| Value::Primitive(Primitive::I128(value));
+		let positional = Value::Composite(Composite::Unnamed(vec![
+			int(1_000),
+			Value::Composite(Composite::Unnamed(vec![
+				Value::Primitive(Primitive::Bool(true)),
+				int(7),
+			])),
+			value.field("names").unwrap().clone(),
+			value.field("choice").unwrap().clone(),
+		]));
+		assert_eq!(encode_value(&types, id, &positional), Ok(encoded));
+
+		let error = |value| encode_value(&types, id, &value).unwrap_err();
+		let Value::Composite(Composite::Named(mut fields)) = value else {
+			panic!("a sample should have named fields")
+		};
+		let pair = fields[1].1.clone();
+		fields[1].1 = Value::Composite(Composite::Unnamed(vec![
+			Value::Primitive(Primitive::Bool(true)),
+			int(70_000),
+		]));
+		let out_of_range = error(Value::Composite(Composite::Named(fields.clone())));
+		assert_eq!(
+			out_of_range.path,
+			[PathSegment::Field("pair".into()), PathSegment::Index(1)]
+		);
+		assert!(matches!(out_of_range.kind, EncodeErrorKind::OutOfRange(_)));
+
+		fields[3].1 = Value::Variant {
+			name: "None".into(),
+			index: 3,
+			fields: Composite::Unnamed(Vec::new()),
+		};
+		fields.remove(1);
+		let missing = error(Value::Composite(Composite::Named(fields.clone())));
+		assert_eq!(
+			missing.kind,
+			EncodeErrorKind::WrongLength {
+				expected: 4,
+				found: 3
+			}
+		);
+		// Named fields may come in any order.
+		fields.push(("pair".into(), pair));
+		let unknown = error(Value::Composite(Composite::Named(fields)));
+		assert_eq!(unknown.to_string(), "at `.choice`: no variant named `None`");
+	}
+
+	#[test]
+	fn should_decode_constants_and_storage_defaults() {
+		let metadata = Metadata::from(kusama_v14());
+		let types = metadata.types();
+		for pallet in metadata.pallets() {
+			for constant in pallet.constants() {
+				assert!(constant.decode_value(types).is_ok(), "{}", constant.name);
+			}
+			for entry in pallet.storage_entries() {
+				assert!(entry.decode_default(types).is_ok(), "{}", entry.name);
+			}
+		}
+
+		let balances = metadata.pallet_by_name("Balances").unwrap();
+		let deposit = balances.constant("ExistentialDeposit").unwrap();
+		let expected = deposit.decode_as::<u128>().unwrap();
+		assert_eq!(
+			deposit.decode_value(types).unwrap().as_u128(),
+			Some(expected)
+		);
+		assert!(deposit.decode_as::<u64>().is_err());
+
+		let system = metadata.pallet_by_name("System").unwrap();
+		let version = system
+			.constant("Version")
+			.unwrap()
+			.decode_value(types)
+			.unwrap();
+		assert_eq!(
+			version.field("spec_name").and_then(Value::as_str),
+			Some("kusama")
+		);
+
+		let number = system.storage_entry("Number").unwrap();
+		assert_eq!(number.decode_default_as::<u32>(), Ok(Some(0)));
+		let account = system.storage_entry("Account").unwrap();
+		let account = account.decode_default(types).unwrap().unwrap();
+		assert_eq!(account.field("nonce").and_then(Value::as_u128), Some(0));
+
+		let staking = metadata.pallet_by_name("Staking").unwrap();
+		let ledger = staking.storage_entry("Ledger").unwrap();
+		assert_eq!(*ledger.modifier, v14::StorageEntryModifier::Optional);
+		assert_eq!(ledger.decode_default(types), Ok(None));
+		assert_eq!(ledger.decode_default_as::<u32>(), Ok(None));
+	}
+
+	#[test]
+	fn should_report_the_path_of_invalid_values() {
+		let metadata = Metadata::from(kusama_v14());
+		let system = metadata.pallet_by_name("System").unwrap();
+		let version = system.constant("Version").unwrap();
+
+		// The spec name is "kusama", and the implementation name is cut short.
+		let error = decode_value(metadata.types(), version.ty, &version.value[..10]).unwrap_err();
+		assert_eq!(error.path, [PathSegment::Field("impl_name".into())]);
+		assert_eq!(error.kind, DecodeErrorKind::NotEnoughData);
+		assert_eq!(error.to_string(), "at `.impl_name`: not enough data");
+
+		let (types, id) = registry::<Sample>();
+		let mut encoded = Sample {
+			amount: 1,
+			pair: (false, 7),
+			names: Vec::new(),
+			choice: Choice::Some(0),
+		}
+		.encode();
+		let choice = encoded.len() - 3;
+		encoded[choice] = 4;
+		let error = decode_value(&types, id, &encoded).unwrap_err();
+		assert_eq!(error.to_string(), "at `.choice`: no variant with index 4");
+
+		encoded[choice] = 3;
+		encoded.push(0);
+		let error = decode_value(&types, id, &encoded).unwrap_err();
+		assert_eq!(error.kind, DecodeErrorKind::TrailingBytes(1));
+	}
+
+	#[derive(TypeInfo)]
+	#[allow(dead_code)]
+	enum Recursive {
+		Nested(Box<Recursive>),
+		Leaf,
+	}
+
+	#[test]
+	fn should_reject_values_nested_too_deeply() {
+		let (types, id) = registry::<Recursive>();
+		let error = decode_value(&types, id, &[0; 100_000]).unwrap_err();
+		assert_eq!(error.kind, DecodeErrorKind::TooDeep);
+		assert_eq!(error.path.len(), MAX_DEPTH);
+
+		let mut encoded = [0; 100];
+		encoded[99] = 1;
+		assert!(decode_value(&types, id, &encoded).is_ok());
+	}
+
+	#[test]
+	fn should_reject_too_many_zero_sized_items() {
+		let (types, id) = registry::<Vec<()>>();
+		let encoded = Compact(u32::MAX >> 2).encode();
+		let error = decode_value(&types, id, &encoded).unwrap_err();
+		assert_eq!(error.kind, DecodeErrorKind::TooManyZeroSizedItems);
+
+		let value = decode_value(&types, id, &vec![(); 3].encode()).unwrap();
+		assert_eq!(
+			value,
+			Value::Sequence(vec![Value::Composite(Composite::Unnamed(Vec::new())); 3])
+		);
+
+		let (types, id) = registry::<[(); u32::MAX as usize]>();
+		let error = decode_value(&types, id, &[]).unwrap_err();
+		assert_eq!(error.kind, DecodeErrorKind::TooManyZeroSizedItems);
+
+		// Every inner sequence is within the bound, but not all of them together.
+		let (types, id) = registry::<Vec<Vec<()>>>();
+		let inner = Compact(MAX_ZERO_SIZED_ITEMS as u32).encode();
+		let encoded = [Compact(2u32).encode(), inner.clone(), inner].concat();
+		let error = decode_value(&types, id, &encoded).unwrap_err();
+		assert_eq!(error.kind, DecodeErrorKind::TooManyZeroSizedItems);
+		let encoded = [
+			Compact(1u32).encode(),
+			Compact(MAX_ZERO_SIZED_ITEMS as u32).encode(),
+		];
+		assert!(decode_value(&types, id, &encoded.concat()).is_ok());
+	}
+}